@@ -1,3 +1,8 @@
+use public_api::{
+    diff::{ChangedPublicItem, SemverBump, TokenDiff},
+    tokens::Token,
+};
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
 #[value(rename_all = "lower")]
 pub enum DenyMethod {
@@ -12,6 +17,10 @@ pub enum DenyMethod {
 
     /// Deny removed things in API diffs
     Removed,
+
+    /// Deny changed things in API diffs, but only if the return type changed
+    #[value(name = "changed-return-type")]
+    ChangedReturnType,
 }
 
 impl DenyMethod {
@@ -26,6 +35,78 @@ impl DenyMethod {
     pub(crate) const fn deny_removed(self) -> bool {
         std::matches!(self, Self::All | Self::Removed)
     }
+
+    /// Whether this particular changed item is denied. Unlike
+    /// [`Self::deny_changed()`], this takes [`Self::ChangedReturnType`] into
+    /// account.
+    pub(crate) fn deny_change(self, changed: &ChangedPublicItem) -> bool {
+        self.deny_changed() || (self == Self::ChangedReturnType && return_type_changed(changed))
+    }
+}
+
+/// Returns `true` if the return type of a changed `fn` has changed, including
+/// if a return type was added or removed. We walk the token diff and keep
+/// track of whether the old and new item respectively have reached their
+/// return type, i.e. the tokens after a `->` outside of any parentheses or
+/// generics and before any `where` clause.
+fn return_type_changed(changed: &ChangedPublicItem) -> bool {
+    #[derive(Default)]
+    struct State {
+        /// How deep into parentheses and generics we are. A `->` in there is
+        /// part of e.g. `fn f<F: Fn() -> u8>()` rather than the return type.
+        depth: usize,
+        in_return_type: bool,
+        /// The bounds of a `where` clause, such as `where F: Fn() -> u8`, come
+        /// after the return type and can contain `->` too.
+        in_where_clause: bool,
+    }
+
+    impl State {
+        fn advance(&mut self, token: &Token) {
+            match token {
+                Token::Symbol(symbol) if symbol == "->" => {
+                    if self.depth == 0 && !self.in_where_clause {
+                        self.in_return_type = true;
+                    }
+                }
+                Token::Symbol(symbol) => {
+                    self.depth += symbol.matches(['(', '<']).count();
+                    self.depth = self
+                        .depth
+                        .saturating_sub(symbol.matches([')', '>']).count());
+                }
+                Token::Keyword(keyword) if keyword == "where" => {
+                    self.in_return_type = false;
+                    self.in_where_clause = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut old = State::default();
+    let mut new = State::default();
+    for token_diff in changed.token_diff() {
+        match &token_diff {
+            TokenDiff::Unchanged(token) => {
+                old.advance(token);
+                new.advance(token);
+            }
+            TokenDiff::Removed(token) => {
+                if old.in_return_type {
+                    return true;
+                }
+                old.advance(token);
+            }
+            TokenDiff::Added(token) => {
+                if new.in_return_type {
+                    return true;
+                }
+                new.advance(token);
+            }
+        }
+    }
+    false
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
@@ -46,11 +127,135 @@ impl Color {
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Colors inspired by dark+ in VS Code
+    Default,
+    /// Bright and bold colors that do not rely on red versus green
+    HighContrast,
+    /// No colors, only bold, italic and similar text styles
+    Monochrome,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum FailOnSemver {
+    /// Fail if the diff requires a major version bump
+    Major,
+
+    /// Fail if the diff requires a minor or a major version bump
+    Minor,
+}
+
+impl FailOnSemver {
+    /// Whether a diff that requires `bump` shall fail.
+    pub(crate) fn fails(self, bump: SemverBump) -> bool {
+        match self {
+            Self::Major => bump >= SemverBump::Major,
+            Self::Minor => bump >= SemverBump::Minor,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum Omit {
+    /// Items of impls generated by `#[derive(...)]`, e.g. `impl Debug for Foo`
+    /// from `#[derive(Debug)]`
+    #[value(name = "auto-derived-impls")]
+    AutoDerivedImpls,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum OutputFormat {
+    /// One item per line, like when listing the public API
+    Plain,
+
+    /// GitHub Actions workflow commands, which become annotations in PRs
+    Github,
+
+    /// A versioned JSON report, for consumption by other tools
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+#[value(rename_all = "lower")]
+pub enum PathStyle {
+    /// Paths begin with the crate name, e.g. `your_crate::module::function`
+    Absolute,
+
+    /// Paths are relative to the crate root, e.g. `module::function`
+    Relative,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DenyMethod;
+    use super::{DenyMethod, FailOnSemver};
+    use public_api::{
+        diff::{ChangedPublicItem, SemverBump},
+        PublicApi,
+    };
     use std::ops::Not;
 
+    fn changed(old: &str, new: &str) -> ChangedPublicItem {
+        let mut items = PublicApi::from_plain_text(&format!("{old}\n{new}")).into_items();
+        let new = items.pop().unwrap();
+        let old = items.pop().unwrap();
+        ChangedPublicItem { old, new }
+    }
+
+    #[test]
+    fn test_deny_changed_return_type() {
+        let deny = |old, new| DenyMethod::ChangedReturnType.deny_change(&changed(old, new));
+
+        assert!(deny("pub fn a::f() -> u8", "pub fn a::f() -> u16"));
+        assert!(deny("pub fn a::f()", "pub fn a::f() -> u8"));
+        assert!(deny("pub fn a::f() -> u8", "pub fn a::f()"));
+        assert!(deny(
+            "pub fn a::f<T>() -> T where T: a::A",
+            "pub fn a::f<T>() -> u8 where T: a::A"
+        ));
+
+        assert!(deny("pub fn a::f(x: u8) -> u8", "pub fn a::f(x: u16) -> u8").not());
+        assert!(deny(
+            "pub fn a::f(g: fn() -> u8) -> u8",
+            "pub fn a::f(g: fn() -> u16) -> u8"
+        )
+        .not());
+        assert!(deny(
+            "pub fn a::f<T>() -> T where T: a::A",
+            "pub fn a::f<T>() -> T where T: a::B"
+        )
+        .not());
+        assert!(deny(
+            "pub fn a::f<F: Fn() -> u8>()",
+            "pub fn a::f<F: Fn() -> u16>()"
+        )
+        .not());
+        assert!(deny(
+            "pub fn a::f<F>() -> u8 where F: Fn() -> u8",
+            "pub fn a::f<F>() -> u8 where F: Fn() -> u16"
+        )
+        .not());
+        assert!(deny(
+            "pub fn a::f<F: Fn() -> u8>() -> u8",
+            "pub fn a::f<F: Fn() -> u8>() -> u16"
+        ));
+        assert!(DenyMethod::ChangedReturnType.deny_changed().not());
+    }
+
+    #[test]
+    fn test_fail_on_semver() {
+        assert!(FailOnSemver::Major.fails(SemverBump::Major));
+        assert!(FailOnSemver::Major.fails(SemverBump::Minor).not());
+        assert!(FailOnSemver::Major.fails(SemverBump::Patch).not());
+
+        assert!(FailOnSemver::Minor.fails(SemverBump::Major));
+        assert!(FailOnSemver::Minor.fails(SemverBump::Minor));
+        assert!(FailOnSemver::Minor.fails(SemverBump::Patch).not());
+    }
+
     #[test]
     fn test_deny_added() {
         assert!(DenyMethod::Added.deny_added());
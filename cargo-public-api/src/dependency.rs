@@ -0,0 +1,28 @@
+//! Listing the public API of a dependency of the current package, without the
+//! need for a checkout of the dependency. Cargo has already downloaded the
+//! source of the dependency, so we document it from there.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Returns the dir with the source of the dependency called `name` of the
+/// package with the given manifest, typically a dir in the cargo registry
+/// cache.
+pub fn source_dir(manifest_path: &Path, name: &str) -> Result<PathBuf> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(manifest_path)
+        .exec()
+        .with_context(|| format!("Failed to get the dependencies of {manifest_path:?}"))?;
+
+    let package = metadata
+        .packages
+        .iter()
+        .find(|package| package.name == name)
+        .ok_or_else(|| anyhow!("`{name}` is not a dependency of {manifest_path:?}"))?;
+
+    Ok(package
+        .manifest_path
+        .parent()
+        .map_or_else(PathBuf::new, |dir| dir.as_std_path().to_owned()))
+}
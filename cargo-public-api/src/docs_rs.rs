@@ -0,0 +1,198 @@
+//! Links public items of published crates to their docs on docs.rs. See
+//! `--docs-rs-links`.
+
+use public_api::tokens::Token;
+use public_api::PublicItem;
+
+/// The docs.rs base URLs of the old and the new side of a diff, e.g.
+/// `https://docs.rs/example_api/0.1.0/`. `None` for sides that are not
+/// published crates.
+#[derive(Clone, Debug, Default)]
+pub struct Links {
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Returns the docs.rs URL of `item`, or `None` for items without a path, such
+/// as `impl`s. Items whose page can not be inferred from the rendered item
+/// alone, such as methods, are linked to a docs.rs search for their path.
+pub fn url(base_url: &str, item: &PublicItem) -> Option<String> {
+    let (kind, path) = kind_and_path(item)?;
+    let (name, parents) = path.split_last()?;
+
+    let page = match (kind.as_str(), parents.split_last()) {
+        ("mod", _) => Some(format!("{}/index.html", names(&path).join("/"))),
+        // The parent of a field of a struct variant is the variant, and its
+        // grandparent is the enum
+        ("struct field", Some((variant, dir))) if dir.last().is_some_and(|s| s.is_type) => {
+            let (enum_, dir) = dir.split_last()?;
+            Some(format!(
+                "{}/enum.{}.html#variant.{}.field.{}",
+                names(dir).join("/"),
+                enum_.name,
+                variant.name,
+                name.name
+            ))
+        }
+        ("struct field", Some((parent, dir))) => Some(format!(
+            "{}/struct.{}.html#structfield.{}",
+            names(dir).join("/"),
+            parent.name,
+            name.name
+        )),
+        ("enum variant", Some((parent, dir))) => Some(format!(
+            "{}/enum.{}.html#variant.{}",
+            names(dir).join("/"),
+            parent.name,
+            name.name
+        )),
+        // The parent is a module, so the item has a page of its own
+        (kind, Some((parent, _))) if !parent.is_type => page_prefix(kind)
+            .map(|prefix| format!("{}/{prefix}.{}.html", names(parents).join("/"), name.name)),
+        _ => None,
+    };
+
+    Some(match page {
+        Some(page) => format!("{base_url}{page}"),
+        None => format!(
+            "{base_url}{}/?search={}",
+            path[0].name,
+            names(&path).join("::")
+        ),
+    })
+}
+
+/// A segment of the path of an item.
+struct Segment {
+    name: String,
+
+    /// If the segment is a type, such as a struct or a trait, as opposed to a
+    /// module.
+    is_type: bool,
+}
+
+impl Segment {
+    fn from_token(token: &Token) -> Option<Self> {
+        let (Token::Identifier(name) | Token::Function(name) | Token::Type(name)) = token else {
+            return None;
+        };
+        // Items parsed from a plain-text listing do not know what is a type,
+        // so also go by naming conventions
+        let is_type = matches!(token, Token::Type(_)) || name.starts_with(char::is_uppercase);
+        Some(Self {
+            name: name.clone(),
+            is_type,
+        })
+    }
+}
+
+fn names(path: &[Segment]) -> Vec<&str> {
+    path.iter().map(|segment| segment.name.as_str()).collect()
+}
+
+/// The prefix that rustdoc uses in the file name of the page of an item of
+/// the given kind.
+fn page_prefix(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "fn" => "fn",
+        "struct" => "struct",
+        "enum" => "enum",
+        "union" => "union",
+        "trait" => "trait",
+        "type" => "type",
+        "const" => "constant",
+        "static" => "static",
+        "macro" => "macro",
+        _ => return None,
+    })
+}
+
+/// Returns the kind of the item, e.g. `struct field`, and its path.
+fn kind_and_path(item: &PublicItem) -> Option<(String, Vec<Segment>)> {
+    let mut tokens = item.tokens().filter(|token| {
+        !matches!(
+            token,
+            Token::Annotation(_) | Token::Qualifier(_) | Token::Whitespace
+        )
+    });
+
+    let mut kind = vec![];
+    let mut path = vec![];
+    for token in tokens.by_ref() {
+        if let Token::Kind(text) = token {
+            kind.push(text.as_str());
+        } else {
+            path.push(Segment::from_token(token)?);
+            break;
+        }
+    }
+    if path.is_empty() {
+        return None;
+    }
+
+    while matches!(tokens.next(), Some(Token::Symbol(symbol)) if symbol == "::") {
+        match tokens.next().and_then(Segment::from_token) {
+            Some(segment) => path.push(segment),
+            None => break,
+        }
+    }
+
+    Some((kind.join(" "), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://docs.rs/krate/1.0.0/";
+
+    fn url_for(item: &str) -> Option<String> {
+        let api = public_api::PublicApi::from_plain_text(&format!("{item}\n"));
+        url(BASE_URL, api.items().next().unwrap())
+    }
+
+    #[test]
+    fn items_with_pages() {
+        assert_eq!(
+            url_for("pub fn krate::module::function(a: usize)").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/module/fn.function.html")
+        );
+        assert_eq!(
+            url_for("#[non_exhaustive] pub struct krate::Struct").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/struct.Struct.html")
+        );
+        assert_eq!(
+            url_for("pub mod krate::module").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/module/index.html")
+        );
+        assert_eq!(
+            url_for("pub static krate::COUNT: usize").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/static.COUNT.html")
+        );
+    }
+
+    #[test]
+    fn items_on_the_page_of_their_parent() {
+        assert_eq!(
+            url_for("pub struct field krate::Struct::field: usize").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/struct.Struct.html#structfield.field")
+        );
+        assert_eq!(
+            url_for("pub enum variant krate::module::Enum::Variant").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/module/enum.Enum.html#variant.Variant")
+        );
+        assert_eq!(
+            url_for("pub struct field krate::Enum::Variant::field: usize").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/enum.Enum.html#variant.Variant.field.field")
+        );
+    }
+
+    #[test]
+    fn items_without_inferable_page() {
+        assert_eq!(
+            url_for("pub fn krate::Struct::method(&self)").as_deref(),
+            Some("https://docs.rs/krate/1.0.0/krate/?search=krate::Struct::method")
+        );
+        assert_eq!(url_for("impl krate::Trait for krate::Struct"), None);
+    }
+}
@@ -1,9 +1,36 @@
-use public_api::{diff::ChangedPublicItem, PublicItem};
+use public_api::{
+    diff::{ChangedPublicItem, SemverBump},
+    PublicItem,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("The API diff is not allowed as per --deny: {0}")]
     DiffDenied(Violations),
+
+    #[error(
+        "Removing these items is not allowed as per --deny-removal-of:\n{}",
+        one_per_line(.0)
+    )]
+    RemovalDenied(Vec<PublicItem>),
+
+    #[error(
+        "The API diff requires a {0} version bump, which is not allowed as per --fail-on-semver"
+    )]
+    SemverBumpNotAllowed(SemverBump),
+
+    #[error("No public items found. Check that the right `--features` and `--target` are used, or pass `--allow-empty` if an empty public API is expected")]
+    NoPublicItems,
+}
+
+/// Lists `items` with one item per line, the same way as they are listed
+/// when diffing.
+fn one_per_line(items: &[PublicItem]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[derive(Debug)]
@@ -16,6 +43,12 @@ pub struct Violations {
 
     /// These items were removed from the API, but no items may be removed from the API
     removed: Vec<PublicItem>,
+
+    /// These items were moved in the API, i.e. removed from their old path and
+    /// added at their new path, but either no items may be removed or no items
+    /// may be added. A moved item counts once, not as one removed and one
+    /// added item.
+    moved: Vec<(PublicItem, PublicItem)>,
 }
 
 impl Violations {
@@ -24,11 +57,20 @@ impl Violations {
             added: Vec::new(),
             changed: Vec::new(),
             removed: Vec::new(),
+            moved: Vec::new(),
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+        self.added.is_empty()
+            && self.changed.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+    }
+
+    /// The number of denied items, of all kinds.
+    pub fn len(&self) -> usize {
+        self.added.len() + self.changed.len() + self.removed.len() + self.moved.len()
     }
 
     pub fn extend_added<I: Iterator<Item = PublicItem>>(&mut self, added: I) {
@@ -42,6 +84,10 @@ impl Violations {
     pub fn extend_removed<I: Iterator<Item = PublicItem>>(&mut self, removed: I) {
         self.removed.extend(removed);
     }
+
+    pub fn extend_moved<I: Iterator<Item = (PublicItem, PublicItem)>>(&mut self, moved: I) {
+        self.moved.extend(moved);
+    }
 }
 
 impl std::fmt::Display for Violations {
@@ -58,6 +104,10 @@ impl std::fmt::Display for Violations {
             write!(f, "Removed items not allowed: {:?} ", self.removed)?;
         }
 
+        if !self.moved.is_empty() {
+            write!(f, "Moved items not allowed: {:?} ", self.moved)?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,45 @@
+//! Named sets of features, defined in `Cargo.toml` like this:
+//!
+//! ```toml
+//! [package.metadata.public-api.feature-sets]
+//! full = ["feature_a", "feature_b"]
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+#[derive(Deserialize, Default)]
+struct PackageMetadata {
+    #[serde(rename = "public-api", default)]
+    public_api: PublicApiMetadata,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct PublicApiMetadata {
+    #[serde(default)]
+    feature_sets: HashMap<String, Vec<String>>,
+}
+
+/// Returns the features of the feature set called `name` in the package with
+/// the given manifest.
+pub fn features(manifest_path: &Path, name: &str) -> Result<Vec<String>> {
+    let manifest =
+        cargo_manifest::Manifest::<PackageMetadata>::from_path_with_metadata(manifest_path)?;
+    manifest
+        .package
+        .and_then(|package| package.metadata)
+        .unwrap_or_default()
+        .public_api
+        .feature_sets
+        .remove(name)
+        .ok_or_else(|| {
+            anyhow!(
+                "No feature set `{name}` in `[package.metadata.public-api.feature-sets]` of {}",
+                manifest_path.display()
+            )
+        })
+}
@@ -1,3 +1,6 @@
+//! Helpers to get information about, and check out commits of, git
+//! repositories. Implemented by running the `git` command.
+
 use std::{
     path::{Path, PathBuf},
     process::Command,
@@ -7,6 +10,10 @@ use anyhow::{anyhow, Context, Result};
 
 /// Synchronously do a `git checkout` of `commit`.
 /// Returns the name of the original branch/commit.
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because `commit` does not exist.
 pub fn git_checkout(commit: &str, git_root: &Path, quiet: bool, force: bool) -> Result<String> {
     let original_branch = current_branch_or_commit(git_root)?;
 
@@ -29,8 +36,60 @@ pub fn git_checkout(commit: &str, git_root: &Path, quiet: bool, force: bool) ->
     }
 }
 
+/// Synchronously do a `git worktree add --detach` of `commit` at `path`, so
+/// that the commit can be built without touching the current working tree.
+///
+/// # Errors
+///
+/// If `path` already exists, since it might be something else than a
+/// worktree that we added. Or if `git` fails, e.g. because `commit` does not
+/// exist.
+pub fn git_worktree_add(git_root: &Path, path: &Path, commit: &str, quiet: bool) -> Result<()> {
+    if path.exists() {
+        return Err(anyhow!(
+            "Refusing to `git worktree add` at {} since it already exists",
+            path.display(),
+        ));
+    }
+
+    let mut command = Command::new("git");
+    command.current_dir(git_root);
+    command.args(["worktree", "add", "--detach"]);
+    if quiet {
+        command.arg("--quiet");
+    }
+    command.arg(path);
+    command.arg(commit);
+    if command.spawn()?.wait()?.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to `git worktree add {} {}`, see error message on stdout/stderr.",
+            path.display(),
+            commit,
+        ))
+    }
+}
+
+/// Removes a worktree that was added with [`git_worktree_add()`], including
+/// any files that were created in it.
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because there is no worktree at `path`.
+pub fn git_worktree_remove(git_root: &Path, path: &Path) -> Result<()> {
+    let mut git = Command::new("git");
+    git.current_dir(git_root);
+    git.args(["worktree", "remove", "--force"]);
+    git.arg(path);
+    trimmed_stdout(git).map(|_| ())
+}
+
 /// Goes up the chain of parents and looks for a `.git` dir.
-#[allow(unused)] // It IS used!
+///
+/// # Errors
+///
+/// If there is no `.git` dir in any parent dir.
 pub fn git_root_from_manifest_path(manifest_path: &Path) -> Result<PathBuf> {
     let err_fn = || anyhow!("No `.git` dir when starting from `{:?}`.", &manifest_path);
     let start = std::fs::canonicalize(manifest_path).with_context(err_fn)?;
@@ -48,6 +107,13 @@ pub fn git_root_from_manifest_path(manifest_path: &Path) -> Result<PathBuf> {
     Err(err_fn())
 }
 
+/// Returns the name of the current branch, or the current commit if there is
+/// no current branch. Useful to get back to where you were after a
+/// [`git_checkout()`].
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because `path` is not in a git repository.
 pub fn current_branch_or_commit(path: impl AsRef<Path>) -> Result<String> {
     let current_branch = current_branch(&path)?;
     let current_commit = current_commit(&path)?;
@@ -56,6 +122,10 @@ pub fn current_branch_or_commit(path: impl AsRef<Path>) -> Result<String> {
 
 /// Returns the name of the current git branch. Or `None` if there is no current
 /// branch.
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because `path` is not in a git repository.
 pub fn current_branch(path: impl AsRef<Path>) -> Result<Option<String>> {
     let branch = trimmed_git_stdout(path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
     if &branch == "HEAD" {
@@ -64,9 +134,14 @@ pub fn current_branch(path: impl AsRef<Path>) -> Result<Option<String>> {
         Ok(Some(branch))
     }
 }
-/// Returns the current commit hash.
+
+/// Returns the full hash of the current commit.
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because `path` is not in a git repository.
 pub fn current_commit(path: impl AsRef<Path>) -> Result<String> {
-    trimmed_git_stdout(path, &["rev-parse", "--short", "HEAD"])
+    trimmed_git_stdout(path, &["rev-parse", "HEAD"])
 }
 
 fn trimmed_git_stdout(path: impl AsRef<Path>, args: &[&str]) -> Result<String> {
@@ -87,7 +162,19 @@ fn trimmed_stdout(mut cmd: Command) -> Result<String> {
 
 /// Resolves a git reference provided at the CLI to an actual commit, allowing
 /// us to validate refs and use "relative" values like HEAD and more.
-#[allow(unused)] // It IS used!
+///
+/// # Errors
+///
+/// If `committish` can't be resolved.
 pub fn resolve_ref(path: impl AsRef<Path>, committish: &str) -> Result<String> {
     trimmed_git_stdout(path, &["rev-parse", committish])
 }
+
+/// Returns the best common ancestor of two commits, i.e. `git merge-base`.
+///
+/// # Errors
+///
+/// If `git` fails, e.g. because the commits have no common ancestor.
+pub fn merge_base(path: impl AsRef<Path>, commit_1: &str, commit_2: &str) -> Result<String> {
+    trimmed_git_stdout(path, &["merge-base", commit_1, commit_2])
+}
@@ -0,0 +1,101 @@
+//! Prints diffs as GitHub Actions workflow commands. See
+//! <https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions>.
+
+use std::io::{Result, Write};
+
+use public_api::{diff::PublicApiDiff, PublicItem};
+
+use crate::Args;
+
+pub struct Github;
+
+impl Github {
+    pub fn print_diff(w: &mut dyn Write, args: &Args, diff: &PublicApiDiff) -> Result<()> {
+        let deny = args.deny.as_deref().unwrap_or_default();
+
+        let level = |denied: bool| if denied { "error" } else { "warning" };
+        let deny_removed = deny.iter().any(|d| d.deny_removed());
+        let removal_denied = |item: &PublicItem| deny_removed || args.denies_removal_of(item);
+        let deny_added = deny.iter().any(|d| d.deny_added());
+        let added = level(deny_added);
+
+        for item in &diff.removed {
+            print_command(
+                w,
+                level(removal_denied(item)),
+                "Removed public API item",
+                item,
+                &item.to_string(),
+            )?;
+        }
+        for item in &diff.changed {
+            let message = format!("-{}\n+{}", item.old, item.new);
+            let changed = level(deny.iter().any(|d| d.deny_change(item)));
+            print_command(w, changed, "Changed public API item", &item.new, &message)?;
+        }
+        for (old, new) in &diff.moved {
+            // A moved item is both removed from its old path and added at its
+            // new path, so it is denied if either of those is
+            let message = format!("-{old}\n+{new}");
+            print_command(
+                w,
+                level(removal_denied(old) || deny_added),
+                "Moved public API item",
+                new,
+                &message,
+            )?;
+        }
+        if !args.only_breaking {
+            for item in &diff.added {
+                print_command(w, added, "Added public API item", item, &item.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints e.g. `::error file=src/lib.rs,line=3,title=Removed public API
+/// item::pub fn krate::f()`. The location is left out if it is not known.
+fn print_command(
+    w: &mut dyn Write,
+    level: &str,
+    title: &str,
+    item: &PublicItem,
+    message: &str,
+) -> Result<()> {
+    let location = item.source_location().map_or_else(String::new, |location| {
+        format!(
+            "file={},line={},",
+            escape_property(&location.file().to_string_lossy()),
+            location.line()
+        )
+    });
+    writeln!(
+        w,
+        "::{level} {location}title={}::{}",
+        escape_property(title),
+        escape_data(message)
+    )
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape_data("-a: b,\n+c%"), "-a: b,%0A+c%25");
+        assert_eq!(escape_property("a::b, c"), "a%3A%3Ab%2C c");
+    }
+}
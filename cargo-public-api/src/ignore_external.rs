@@ -0,0 +1,136 @@
+//! Support for `--ignore-external`. Crates sometimes expose items of private
+//! dependencies, and the paths to such items can change between patch releases
+//! of the dependency without any semantic impact, e.g. if the dependency moves
+//! an item to a different private module. Such changes are just noise in a
+//! diff.
+
+use public_api::{diff::PublicApiDiff, tokens::Token, PublicItem};
+
+/// Removes changed items whose only difference is in the paths to items of the
+/// given external `crates`. Since the path of an impl is the whole impl, such
+/// changes to impls show up as a removed and an added item, so removed and
+/// added items that only differ in that way are removed in pairs.
+pub fn ignore_changes_in(mut diff: PublicApiDiff, crates: &[String]) -> PublicApiDiff {
+    let crates: Vec<String> = crates.iter().map(|name| name.replace('-', "_")).collect();
+    let normalize = |item: &PublicItem| without_external_paths(item, &crates);
+
+    diff.changed
+        .retain(|changed| normalize(&changed.old) != normalize(&changed.new));
+
+    let added: Vec<String> = diff.added.iter().map(normalize).collect();
+    let mut ignored_added = vec![false; added.len()];
+    diff.removed.retain(|removed| {
+        let removed = normalize(removed);
+        let matching_added = (0..added.len()).find(|&i| !ignored_added[i] && added[i] == removed);
+        if let Some(i) = matching_added {
+            ignored_added[i] = true;
+        }
+        matching_added.is_none()
+    });
+    let mut ignored_added = ignored_added.into_iter();
+    diff.added
+        .retain(|_| !ignored_added.next().unwrap_or_default());
+
+    diff
+}
+
+/// Renders `item` with every path that starts with one of `crates`
+/// replaced by e.g. `dep::_`.
+fn without_external_paths(item: &PublicItem, crates: &[String]) -> String {
+    let tokens: Vec<&Token> = item.tokens().collect();
+    let mut rendered = String::new();
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        let starts_path = index == 0 || !is_path_separator(tokens[index - 1]);
+        index += 1;
+
+        match token {
+            Token::Identifier(name) if starts_path && crates.contains(name) => {
+                while tokens
+                    .get(index)
+                    .is_some_and(|token| is_path_separator(token))
+                    && tokens
+                        .get(index + 1)
+                        .is_some_and(|token| is_path_segment(token))
+                {
+                    index += 2;
+                }
+                rendered.push_str(name);
+                rendered.push_str("::_");
+            }
+            token => rendered.push_str(token.text()),
+        }
+    }
+    rendered
+}
+
+fn is_path_separator(token: &Token) -> bool {
+    matches!(token, Token::Symbol(symbol) if symbol == "::")
+}
+
+fn is_path_segment(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_) | Token::Type(_) | Token::Function(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use public_api::PublicApi;
+
+    use super::*;
+
+    fn diff_between(old: &str, new: &str) -> PublicApiDiff {
+        PublicApiDiff::between(
+            PublicApi::from_plain_text(old),
+            PublicApi::from_plain_text(new),
+        )
+    }
+
+    #[test]
+    fn changed_path_to_external_item_is_ignored() {
+        let diff = diff_between(
+            "pub fn krate::f(x: dep::v1::Thing)",
+            "pub fn krate::f(x: dep::v2::Thing)",
+        );
+        assert_eq!(diff.changed.len(), 1);
+
+        let diff = ignore_changes_in(diff, &[String::from("dep")]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn other_changes_are_kept() {
+        let diff = ignore_changes_in(
+            diff_between(
+                "pub fn krate::f(x: dep::Thing) -> u8",
+                "pub fn krate::f(x: dep::Other) -> u16",
+            ),
+            &[String::from("dep")],
+        );
+        assert_eq!(diff.changed.len(), 1);
+
+        let diff = ignore_changes_in(
+            diff_between(
+                "pub fn krate::f(x: other_dep::v1::Thing)",
+                "pub fn krate::f(x: other_dep::v2::Thing)",
+            ),
+            &[String::from("dep")],
+        );
+        assert_eq!(diff.changed.len(), 1);
+    }
+
+    #[test]
+    fn changed_impl_is_ignored() {
+        let diff = diff_between(
+            "impl core::convert::From<dep::v1::Thing> for krate::S",
+            "impl core::convert::From<dep::v2::Thing> for krate::S",
+        );
+        assert_eq!((diff.removed.len(), diff.added.len()), (1, 1));
+
+        let diff = ignore_changes_in(diff, &[String::from("dep")]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.added.is_empty());
+    }
+}
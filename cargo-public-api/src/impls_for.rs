@@ -0,0 +1,63 @@
+//! Support for `--list-impls-for`, which lists only the impls of a single type
+//! and the items of those impls, e.g. to audit what traits a type implements.
+
+use public_api::{tokens::Token, PublicItem};
+
+/// Picks the impls of the type with a given path, e.g. `krate::Struct`, and
+/// the items of those impls, such as methods. Both inherent impls and trait
+/// impls count.
+///
+/// Impls are grouped by their parent in the item tree rather than by path,
+/// since the items of a trait impl are rendered with the path of the trait
+/// when the impl is listed under the trait. So items must be passed to
+/// [`Self::is_impl_item`] one by one in the order of
+/// [`public_api::PublicApi::items()`], which lists each parent right before
+/// its descendants.
+pub struct ImplsFor<'a> {
+    type_path: &'a str,
+
+    /// The depth of the type while its descendants are being passed, see
+    /// [`PublicItem::depth()`]
+    type_depth: Option<usize>,
+}
+
+impl<'a> ImplsFor<'a> {
+    pub fn new(type_path: &'a str) -> Self {
+        Self {
+            type_path,
+            type_depth: None,
+        }
+    }
+
+    /// Returns `true` if `item` is an impl of the type, or an item of such an
+    /// impl.
+    pub fn is_impl_item(&mut self, item: &PublicItem) -> bool {
+        let depth = item.depth();
+        if self
+            .type_depth
+            .is_some_and(|type_depth| depth <= type_depth)
+        {
+            self.type_depth = None;
+        }
+
+        // The impls of a trait are the impls that implement the trait, which
+        // are listed under the implementing types as well
+        if self.type_depth.is_none()
+            && item.impl_kind().is_none()
+            && !is_trait(item)
+            && item.has_path(self.type_path)
+        {
+            self.type_depth = Some(depth);
+            return false;
+        }
+
+        // Other descendants of the type, such as struct fields, have no
+        // `impl_kind()`
+        self.type_depth.is_some() && item.impl_kind().is_some()
+    }
+}
+
+fn is_trait(item: &PublicItem) -> bool {
+    item.tokens()
+        .any(|token| matches!(token, Token::Kind(kind) if kind == "trait"))
+}
@@ -0,0 +1,151 @@
+//! The JSON that `cargo public-api --output-format json` prints for diffs. The
+//! format is versioned with [`SCHEMA_VERSION`] and documented by the JSON
+//! Schema in
+//! [`cargo-public-api/diff-report.schema.json`](https://github.com/Enselic/cargo-public-api/blob/main/cargo-public-api/diff-report.schema.json).
+
+use public_api::diff::PublicApiDiff;
+use public_api::PublicItem;
+use serde::{Deserialize, Serialize};
+
+/// The version of the [`DiffReport`] format. Bumped whenever the format
+/// changes in a way that is not backwards compatible, such as when a field is
+/// removed or renamed. New fields can be added without bumping the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A diff of two public APIs, in a form that is stable and easy to consume
+/// for other tools.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffReport {
+    /// The [`SCHEMA_VERSION`] that the report adheres to.
+    pub schema_version: u32,
+
+    /// Items that have been removed from the public API.
+    pub removed: Vec<ReportItem>,
+
+    /// Items that have been changed in the public API.
+    pub changed: Vec<ChangedReportItem>,
+
+    /// Items that have been added to the public API.
+    pub added: Vec<ReportItem>,
+
+    /// Items that have been moved to a different path in the public API.
+    pub moved: Vec<ChangedReportItem>,
+}
+
+/// A public item in a [`DiffReport`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportItem {
+    /// The rendered item, e.g. `pub fn krate::function(a: usize)`. The same as
+    /// one line of output of `cargo public-api`.
+    pub item: String,
+
+    /// The source file that defines the item, if known.
+    pub file: Option<String>,
+
+    /// The line in [`Self::file`] where the item begins, if known.
+    pub line: Option<usize>,
+}
+
+/// An item in a [`DiffReport`] that is present in both the old and the new
+/// public API, but in different forms.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedReportItem {
+    /// How the item used to look.
+    pub old: ReportItem,
+
+    /// How the item looks now.
+    pub new: ReportItem,
+}
+
+impl From<&PublicApiDiff> for DiffReport {
+    fn from(diff: &PublicApiDiff) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            removed: diff.removed.iter().map(ReportItem::from).collect(),
+            changed: diff
+                .changed
+                .iter()
+                .map(|changed| ChangedReportItem::new(&changed.old, &changed.new))
+                .collect(),
+            added: diff.added.iter().map(ReportItem::from).collect(),
+            moved: diff
+                .moved
+                .iter()
+                .map(|(old, new)| ChangedReportItem::new(old, new))
+                .collect(),
+        }
+    }
+}
+
+impl From<&PublicItem> for ReportItem {
+    fn from(item: &PublicItem) -> Self {
+        let location = item.source_location();
+        Self {
+            item: item.to_string(),
+            file: location.map(|location| location.file().to_string_lossy().into_owned()),
+            line: location.map(public_api::SourceLocation::line),
+        }
+    }
+}
+
+impl ChangedReportItem {
+    fn new(old: &PublicItem, new: &PublicItem) -> Self {
+        Self {
+            old: ReportItem::from(old),
+            new: ReportItem::from(new),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use public_api::PublicApi;
+
+    #[test]
+    fn round_trip() {
+        let old = PublicApi::from_plain_text("pub fn krate::f(a: u8)\npub fn krate::g()\n");
+        let new = PublicApi::from_plain_text("pub fn krate::f(a: u16)\npub fn krate::h()\n");
+        let report = DiffReport::from(&PublicApiDiff::between(old, new));
+
+        let json = serde_json::to_string(&report).unwrap();
+        let deserialized: DiffReport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized, report);
+        assert_eq!(deserialized.schema_version, SCHEMA_VERSION);
+        assert_eq!(deserialized.removed[0].item, "pub fn krate::g()");
+        assert_eq!(deserialized.changed[0].new.item, "pub fn krate::f(a: u16)");
+        assert_eq!(deserialized.added[0].item, "pub fn krate::h()");
+    }
+
+    #[test]
+    fn schema_matches_schema_version() {
+        let schema: serde_json::Value =
+            serde_json::from_str(include_str!("../diff-report.schema.json")).unwrap();
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn schema_requires_all_fields() {
+        let schema: serde_json::Value =
+            serde_json::from_str(include_str!("../diff-report.schema.json")).unwrap();
+        let report = DiffReport::from(&PublicApiDiff::between(vec![], vec![]));
+        let serde_json::Value::Object(report) = serde_json::to_value(report).unwrap() else {
+            panic!("a report must serialize to an object");
+        };
+
+        let mut required: Vec<_> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| field.as_str().unwrap())
+            .collect();
+        let mut fields: Vec<_> = report.keys().map(String::as_str).collect();
+        required.sort_unstable();
+        fields.sort_unstable();
+        assert_eq!(required, fields);
+    }
+}
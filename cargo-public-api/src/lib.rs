@@ -0,0 +1,9 @@
+//! The parts of `cargo public-api` that are also useful to other tools. Most
+//! users want the `cargo public-api` binary itself, or the
+//! [`public-api`](https://docs.rs/public-api) library that it is built upon.
+
+// deny in CI, only warn here
+#![warn(clippy::all, clippy::pedantic, missing_docs)]
+
+pub mod git_utils;
+pub mod json_report;
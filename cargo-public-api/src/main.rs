@@ -1,30 +1,49 @@
 // deny in CI, only warn here
 #![warn(clippy::all, clippy::pedantic)]
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::io::stdout;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
-use arg_types::{Color, DenyMethod};
+use arg_types::{Color, DenyMethod, FailOnSemver, Omit, OutputFormat, PathStyle, Theme};
+use cargo_public_api::git_utils;
+use cargo_public_api::json_report::DiffReport;
+use github::Github;
+use impls_for::ImplsFor;
 use plain::Plain;
 use public_api::diff::PublicApiDiff;
-use public_api::{Options, PublicApi, MINIMUM_RUSTDOC_JSON_VERSION};
+use public_api::tokens::Token;
+use public_api::{Options, PublicApi, PublicItem, MINIMUM_RUSTDOC_JSON_VERSION};
 
 use clap::Parser;
 use rustdoc_json::BuildError;
 
 mod arg_types;
+mod dependency;
+mod docs_rs;
 mod error;
-mod git_utils;
+mod feature_set;
+mod github;
+mod ignore_external;
+mod impls_for;
+mod no_std;
 mod plain;
+mod progress;
 mod published_crate;
 mod toolchain;
+mod truncate;
+mod watch;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Args {
-    /// Path to `Cargo.toml`.
+    /// Path to `Cargo.toml`. May be a glob pattern such as
+    /// `'crates/*/Cargo.toml'`, in which case each matching crate is processed
+    /// in turn, with a header line before the output of each crate.
     #[arg(long, value_name = "PATH", default_value = "Cargo.toml")]
     manifest_path: PathBuf,
 
@@ -61,6 +80,36 @@ pub struct Args {
     #[arg(long)]
     force_git_checkouts: bool,
 
+    /// If diffing fails, e.g. because a build fails, leave the git checkout
+    /// as-is instead of restoring the original branch, and keep any `git
+    /// worktree` that the old commit was built in, so that the failure can be
+    /// investigated.
+    #[arg(long)]
+    no_cleanup_on_error: bool,
+
+    /// Build the old and the new side of a diff at the same time, which
+    /// roughly halves the time it takes to diff.
+    ///
+    /// Works with `--diff-git-checkouts`, `--diff-published` and
+    /// `--baseline-path`, as well as the corresponding `--diff` forms. With
+    /// `--diff-git-checkouts`, the old commit is built in a separate `git
+    /// worktree`. The two sides are always built in separate target dirs, so
+    /// they never wait for each other, except for short moments when `cargo`
+    /// needs exclusive access to its package cache.
+    #[arg(long)]
+    parallel: bool,
+
+    /// When diffing git checkouts, diff against the common ancestor of the
+    /// two commits (`git merge-base COMMIT_1 COMMIT_2`) instead of against
+    /// `COMMIT_1` itself. That way only the changes of e.g. a feature branch
+    /// are reported, even if `main` has advanced since the branch was made.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --diff-git-checkouts main my-feature-branch --merge-base
+    #[arg(long)]
+    merge_base: bool,
+
     /// Diff the public API across two different rustdoc JSON files.
     #[arg(long, num_args = 2, value_names = ["RUSTDOC_JSON_PATH_1", "RUSTDOC_JSON_PATH_2"])]
     diff_rustdoc_json: Option<Vec<String>>,
@@ -70,9 +119,124 @@ pub struct Args {
     /// Example:
     ///
     ///   cargo public-api --diff-published your-crate@1.2.3
+    ///
+    /// The rustdoc JSON of the published version is cached, so later runs do
+    /// not need to build it again. See `--refresh-baseline`.
     #[arg(long, value_name = "CRATE_NAME@VERSION")]
     diff_published: Option<String>,
 
+    /// Build published versions again when diffing against them, even if their
+    /// rustdoc JSON is cached from an earlier run with the same features,
+    /// toolchain and target.
+    #[arg(long)]
+    refresh_baseline: bool,
+
+    /// Fetch published versions from the given alternative registry rather
+    /// than from crates.io when diffing against them. The registry must be
+    /// configured in the cargo config of `$CARGO_HOME` or with
+    /// `CARGO_REGISTRIES_<NAME>_INDEX`, since published versions are built
+    /// outside of your project.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --diff-published your-crate@1.2.3 --registry my-registry
+    #[arg(long, value_name = "NAME")]
+    registry: Option<String>,
+
+    /// Like `--diff-published`, but the published version is read from disk
+    /// rather than downloaded, which is useful for reproducible CI without
+    /// network access. The path is either a `.crate` file, as downloaded from
+    /// crates.io, or a dir with the extracted contents of one.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --baseline-path your-crate-1.2.3.crate
+    #[arg(long, value_name = "PATH")]
+    baseline_path: Option<PathBuf>,
+
+    /// Space or comma separated list of features to activate when building
+    /// published versions, see `--diff-published` and `--baseline-path`. By
+    /// default, published versions are built with the same features as the
+    /// current version. If any `--baseline-*` feature arg is given, only those
+    /// are used for published versions.
+    #[arg(long, num_args = 1..)]
+    baseline_features: Vec<String>,
+
+    /// Activate all available features when building published versions. See
+    /// `--baseline-features`.
+    #[arg(long)]
+    baseline_all_features: bool,
+
+    /// Do not activate the `default` feature when building published
+    /// versions. See `--baseline-features`.
+    #[arg(long)]
+    baseline_no_default_features: bool,
+
+    /// Diff the public API of the current crate when built for one target
+    /// triple against when built for another. Useful to find items that are
+    /// only available on some platforms. The standard library must be
+    /// installed for both targets, e.g. with `rustup target add`.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --diff-targets x86_64-unknown-linux-gnu x86_64-pc-windows-msvc
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["TARGET_TRIPLE_1", "TARGET_TRIPLE_2"],
+        conflicts_with = "target"
+    )]
+    diff_targets: Option<Vec<String>>,
+
+    /// Diff the current API against a baseline listing previously saved from
+    /// the output of `cargo public-api`. Useful if you commit a listing of
+    /// your public API to your repo, since no git history or published crate
+    /// is needed.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api > public-api.txt
+    ///
+    /// and later
+    ///
+    ///   cargo public-api --diff-baseline public-api.txt
+    ///
+    /// Note that the listing format is not stable. Regenerate the baseline
+    /// after upgrading `cargo public-api`, and don't use `--simplified` or
+    /// other options that affect the listing for only one of the sides.
+    #[arg(long, value_name = "BASELINE_PATH")]
+    diff_baseline: Option<PathBuf>,
+
+    /// List only the items that the given feature adds to the public API, on
+    /// top of the features that are activated anyway. Useful to review what a
+    /// feature exposes. Can't be used when diffing.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --feature-delta serde
+    #[arg(
+        long,
+        value_name = "FEATURE",
+        conflicts_with_all = [
+            "diff",
+            "diff_git_checkouts",
+            "diff_rustdoc_json",
+            "diff_published",
+            "baseline_path",
+            "diff_targets",
+            "diff_baseline",
+        ]
+    )]
+    feature_delta: Option<String>,
+
+    /// Instead of listing the public items, list the dependency crates that are
+    /// part of the public API. A dependency is part of the public API if its
+    /// items are re-exported or appear in the signatures of public items. A
+    /// semver breaking bump of such a dependency is typically a semver breaking
+    /// change of the crate itself.
+    #[arg(long)]
+    public_deps: bool,
+
     /// Automatically resolves to either `--diff-git-checkouts`,
     /// `--diff-rustdoc-json`, or `--diff-published` depending on if args ends
     /// in `.json` or not, or if they contain `@`.
@@ -101,9 +265,43 @@ pub struct Args {
     ///
     ///   cargo public-api --diff-published some-crate@1.2.3
     ///
+    /// It is also possible to diff a commit against a rustdoc JSON file, or
+    /// the other way around:
+    ///
+    ///   cargo public-api --diff v0.2.0 v0.3.0.json
+    ///
+    /// Two published versions can also be diffed against each other, without
+    /// involving the current working tree:
+    ///
+    ///   cargo public-api --diff some-crate@1.2.3 some-crate@1.3.0
+    ///
+    /// A single git ref, rustdoc JSON file, or public API listing (see
+    /// `--diff-baseline`) is diffed against the current working tree:
+    ///
+    ///   cargo public-api --diff v0.2.0
+    ///
+    /// A git ref is then built in a separate `git worktree`, so the current
+    /// working tree, including uncommitted changes, is left as-is.
+    ///
     #[arg(long, num_args = 1..=2, value_name = "TARGET")]
     diff: Option<Vec<String>>,
 
+    /// Set when `--diff` is used with one commit and one rustdoc JSON file.
+    /// Can't be set directly by the user.
+    #[arg(skip)]
+    diff_commit_and_rustdoc_json: Option<Vec<String>>,
+
+    /// Set when `--diff` is used with two published crate versions. Can't be
+    /// set directly by the user.
+    #[arg(skip)]
+    diff_two_published: Option<Vec<String>>,
+
+    /// Set when `--diff` is used with a single git ref or rustdoc JSON file,
+    /// which is then diffed against the current working tree. Can't be set
+    /// directly by the user.
+    #[arg(skip)]
+    diff_against_current_dir: Option<String>,
+
     /// List the public API based on the given rustdoc JSON file.
     ///
     /// Example:
@@ -119,13 +317,142 @@ pub struct Args {
     #[arg(long, value_name = "RUSTDOC_JSON_PATH")]
     rustdoc_json: Option<String>,
 
+    /// When listing the public API of a crate, also write the rustdoc JSON
+    /// that the listing is based on to the given path, e.g. to archive it.
+    #[arg(long, value_name = "PATH")]
+    emit_json: Option<PathBuf>,
+
     /// Exit with failure if the specified API diff is detected.
     ///
     /// Can be combined. For example, to only allow additions to the API, use
     /// `--deny=added --deny=changed`.
+    ///
+    /// Use `--deny=changed-return-type` to only deny changed items if their
+    /// return type changed.
     #[arg(long, value_enum)]
     deny: Option<Vec<DenyMethod>>,
 
+    /// Exit with failure if the item with this path is removed from the API,
+    /// e.g. `--deny-removal-of your_crate::critical_function`. Can be used
+    /// several times. Other removed items are allowed, unless they are denied
+    /// by `--deny`. Not affected by `--fail-threshold`.
+    #[arg(long, value_name = "PATH")]
+    deny_removal_of: Vec<String>,
+
+    /// Exit with failure if the diff requires a version bump of at least the
+    /// given level, in semver terminology. Removed, changed and moved items
+    /// require a major bump. Added items require a minor bump.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --diff-published your-crate@1.2.3 --fail-on-semver major
+    #[arg(long, value_enum, value_name = "LEVEL")]
+    fail_on_semver: Option<FailOnSemver>,
+
+    /// When diffing, print only the version bump that the diff requires, in
+    /// semver terminology, instead of the diff itself. Prints one of `major`,
+    /// `minor` or `patch`, which is handy for release automation. Removed,
+    /// changed and moved items require a major bump. Added items require a
+    /// minor bump.
+    #[arg(long)]
+    print_bump: bool,
+
+    /// When diffing, only show changes that can break users of the crate,
+    /// i.e. removed, changed and moved items. Every changed item counts as
+    /// breaking, the same as for `--print-bump`, since changes are not
+    /// classified further. Added items are not shown, but `--deny=added` still
+    /// takes them into account.
+    #[arg(long)]
+    only_breaking: bool,
+
+    /// When diffing, ignore changes whose only difference is in the paths to
+    /// items of this external crate. Useful if the crate exposes items of a
+    /// private dependency whose paths change between releases of the
+    /// dependency without any semantic impact. Can be used several times.
+    #[arg(long, value_name = "CRATE")]
+    ignore_external: Vec<String>,
+
+    /// When diffing, truncate items that are wider than this many columns and
+    /// end them with `…`. Defaults to the width of the terminal if stdout is a
+    /// terminal. Use `--width 0` to never truncate. Links added by
+    /// `--docs-rs-links` are never truncated.
+    #[arg(long, value_name = "COLUMNS")]
+    width: Option<usize>,
+
+    /// Only exit with failure if more than this many items are denied by
+    /// `--deny`. Each item counts once, even if several `--deny` values deny
+    /// it. Added items count too if `--deny=added` or `--deny=all` is used. A
+    /// moved item counts once too, although it is both removed and added.
+    ///
+    /// Useful to tolerate a budget of breaking changes during large
+    /// refactors. For example, `--deny=removed --deny=changed
+    /// --fail-threshold 3` fails only when more than 3 items in total were
+    /// removed or changed.
+    #[arg(long, value_name = "COUNT", requires = "deny")]
+    fail_threshold: Option<usize>,
+
+    /// Exit with failure if the listed public API has more than this many
+    /// items. Useful to keep the public API of a crate from growing without
+    /// notice. The items are still listed.
+    #[arg(long, value_name = "COUNT")]
+    max_public_items: Option<usize>,
+
+    /// Do not fail if the listed public API has no items besides the crate
+    /// root module. Without this flag such an API is treated as an error,
+    /// since an empty API is often the result of wrong `--features` or
+    /// `--target` rather than the actual public API of the crate.
+    #[arg(long)]
+    allow_empty: bool,
+
+    /// Only list the impls of the type with this path, e.g. `--list-impls-for
+    /// your_crate::Struct`, together with the items of those impls, such as
+    /// methods. Both inherent impls and trait impls are listed. Useful when
+    /// auditing a single type. Can't be used when diffing.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = [
+            "diff",
+            "diff_git_checkouts",
+            "diff_rustdoc_json",
+            "diff_published",
+            "baseline_path",
+            "diff_targets",
+            "diff_baseline",
+        ]
+    )]
+    list_impls_for: Option<String>,
+
+    /// Watch the source files of the crate, i.e. `Cargo.toml` and `src/` of
+    /// each package of the workspace, and list or diff again whenever they
+    /// change. Runs until interrupted, e.g. with Ctrl+C. Errors, such as a
+    /// failed build or a denied diff, are printed but do not stop watching.
+    #[arg(long)]
+    watch: bool,
+
+    /// Print the `cargo` command that would be used to build rustdoc JSON for
+    /// the crate, and exit without running it. Useful to debug build failures,
+    /// e.g. in CI. Can't be used when diffing, or with other args that change
+    /// what is built, such as `--rustdoc-json` or `--dependency`.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "diff",
+            "diff_git_checkouts",
+            "diff_rustdoc_json",
+            "diff_published",
+            "baseline_path",
+            "diff_targets",
+            "diff_baseline",
+            "rustdoc_json",
+            "dependency",
+            "feature_delta",
+            "watch",
+            "list_impls_for",
+        ]
+    )]
+    dry_run: bool,
+
     /// Whether or not to use colors.
     ///
     /// You can select between "auto", "never", "always". If "auto" (the
@@ -134,6 +461,36 @@ pub struct Args {
     #[arg(long, value_enum, default_value_t = Color::Auto)]
     color: Color,
 
+    /// What colors and text styles to use for the different kinds of tokens
+    /// when colors are used. Has no effect with `--color=never`.
+    #[arg(long, value_enum, default_value_t = Theme::Default)]
+    theme: Theme,
+
+    /// How to print diffs.
+    ///
+    /// With "github", each diffed item is printed as a GitHub Actions workflow
+    /// command, so that it shows up as an annotation in pull requests. Items
+    /// denied by `--deny` become errors, other items become warnings.
+    ///
+    /// With "json", the diff is printed as a JSON object that is described by
+    /// the JSON Schema in `diff-report.schema.json`, which is part of the
+    /// `cargo-public-api` package. The object has a `schema_version`
+    /// field that is bumped whenever the format changes in a backwards
+    /// incompatible way.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    output_format: OutputFormat,
+
+    /// When diffing published crates, append the docs.rs URL of each item of
+    /// a published version to its line in the diff.
+    ///
+    /// Only used by the "plain" output format.
+    #[arg(long)]
+    docs_rs_links: bool,
+
+    /// Set by `--docs-rs-links`. Can't be set directly by the user.
+    #[arg(skip)]
+    docs_rs: docs_rs::Links,
+
     /// Omit items that belong to Blanket Implementations and Auto Trait
     /// Implementations.
     ///
@@ -148,7 +505,67 @@ pub struct Args {
     #[arg(short, long)]
     simplified: bool,
 
-    /// Show detailed info about processing.
+    /// Include items of Blanket Implementations in the diff even with
+    /// `--simplified`.
+    ///
+    /// A change to the bounds of a blanket impl such as `impl<T: Clone> Trait
+    /// for T` changes what types implement the trait, which can break
+    /// downstream crates.
+    #[arg(long)]
+    include_blanket_impls: bool,
+
+    /// Omit items of the given kind. Can be used several times.
+    ///
+    /// Example:
+    ///
+    ///   cargo public-api --omit auto-derived-impls
+    #[arg(long, value_enum, value_name = "KIND")]
+    omit: Vec<Omit>,
+
+    /// Render references to items in other crates with their full path, e.g.
+    /// `alloc::vec::Vec` instead of `Vec`, also in cases where the rustdoc JSON
+    /// does not directly tell what item a reference points to.
+    ///
+    /// The item is looked up by name, so this only helps if the name is
+    /// unambiguous.
+    #[arg(long)]
+    qualify_external: bool,
+
+    /// Render the values of `const` and `static` items, e.g. `pub const
+    /// krate::MAX: u32 = 100`.
+    #[arg(long)]
+    show_values: bool,
+
+    /// Render trait methods that have a default implementation with a `{ ... }`
+    /// body, to tell them apart from trait methods that implementors must
+    /// implement themselves.
+    #[arg(long)]
+    mark_provided_methods: bool,
+
+    /// Annotate items with the `#[cfg(...)]` predicate they require, e.g.
+    /// `#[cfg(feature = "x")] pub fn krate::f()`.
+    ///
+    /// The rustdoc JSON is built with `--cfg docsrs`, like docs.rs does, so
+    /// that items that crates only document under that cfg are included.
+    /// Combine with `--all-features` to include feature-gated items.
+    #[arg(long)]
+    show_cfgs: bool,
+
+    /// Whether paths to items of the crate itself include the crate name.
+    ///
+    /// With "relative", paths are relative to the crate root, e.g. `pub fn
+    /// module::function()` instead of `pub fn your_crate::module::function()`.
+    /// Paths to items of other crates always include the crate name.
+    #[arg(long, value_enum, default_value_t = PathStyle::Absolute)]
+    path_style: PathStyle,
+
+    /// Do not show the progress of building rustdoc JSON, i.e. neither the
+    /// progress spinner nor the output of `cargo`. Errors are still shown.
+    #[arg(long, short, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Show detailed info about processing, and build rustdoc JSON with
+    /// `cargo --verbose`.
     ///
     /// For debugging purposes. The output is not stable and can change across
     /// patch versions.
@@ -185,6 +602,14 @@ pub struct Args {
     #[arg(long, short = 'F', num_args = 1..)]
     features: Vec<String>,
 
+    /// Activate the features of a named feature set, in addition to any
+    /// `--features`.
+    ///
+    /// Feature sets are defined in the `[package.metadata.public-api.feature-sets]`
+    /// table of `Cargo.toml`, e.g. `full = ["feature_a", "feature_b"]`.
+    #[arg(long, value_name = "NAME")]
+    feature_set: Option<String>,
+
     #[arg(long)]
     /// Activate all available features
     all_features: bool,
@@ -193,13 +618,47 @@ pub struct Args {
     /// Do not activate the `default` feature
     no_default_features: bool,
 
+    /// If the crate is `no_std` but activates its `std` feature by default,
+    /// document it without the `std` feature, so that items that are only
+    /// available with `std` are not listed. Has no effect if features are
+    /// selected with `--features std`, `--all-features` or
+    /// `--no-default-features`.
+    #[arg(long)]
+    no_std_detection: bool,
+
     /// Package to document
     #[arg(long, short)]
     package: Option<String>,
 
+    /// List the public API of the dependency with this name instead of the
+    /// public API of the package itself. The dependency is documented from the
+    /// source that cargo has downloaded for the package, so no checkout of the
+    /// dependency is needed.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["package", "bin"])]
+    dependency: Option<String>,
+
+    /// Binary target to document, for packages without a library target. If
+    /// the package has no library target but a single binary target, that
+    /// binary is documented automatically.
+    #[arg(long, value_name = "NAME")]
+    bin: Option<String>,
+
     /// Forwarded to rustdoc JSON build command
     #[arg(long, hide = true)]
     cap_lints: Option<String>,
+
+    /// Abort building rustdoc JSON if it takes longer than this many seconds.
+    /// Fractions of a second are allowed, e.g. `--timeout 0.5`
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Attempt best-effort parsing of rustdoc JSON with the given format
+    /// version, even if it is not the format version that this tool supports.
+    ///
+    /// Useful when experimenting with new nightly toolchains. Items that can't
+    /// be parsed are skipped, so the output might be incomplete.
+    #[arg(long, value_name = "VERSION")]
+    format_version: Option<u32>,
 }
 
 /// This represents an action that we want to do at some point.
@@ -215,6 +674,11 @@ pub enum Action {
     /// Afterwards, we want to restore the original branch the user was on, to
     /// not mess up their work tree.
     RestoreBranch { name: String, force: bool },
+
+    /// With `--parallel`, `--diff-git-checkouts` builds the old commit in a
+    /// `git worktree`, which we remove afterwards. So does `--diff` with a
+    /// single git ref.
+    RemoveWorktree { path: PathBuf },
 }
 
 // Validate that the toolchain does not start with a `+` character.
@@ -226,24 +690,188 @@ fn parse_toolchain(s: &str) -> Result<String, &'static str> {
     }
 }
 
+// Validate that the timeout is a non-negative number of seconds.
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let secs: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    Duration::try_from_secs_f64(secs).map_err(|e| format!("{e}"))
+}
+
 fn main_() -> Result<()> {
-    let args = get_args()?;
+    let mut args = get_args()?;
+    resolve_target(&mut args);
+
+    if args.watch {
+        return watch::watch(&watch_paths(&args)?, || {
+            if let Err(e) = list_or_diff_each_manifest(args.clone()) {
+                eprintln!("Error: {e:?}");
+            }
+        });
+    }
+
+    list_or_diff_each_manifest(args)
+}
+
+/// The source files to watch for `--watch`, for each crate that a glob
+/// manifest path matches.
+fn watch_paths(args: &Args) -> Result<Vec<PathBuf>> {
+    let manifest_paths = expand_manifest_path_glob(&args.manifest_path)?
+        .unwrap_or_else(|| vec![args.manifest_path.clone()]);
+    watch::source_paths(&manifest_paths)
+}
+
+fn list_or_diff_each_manifest(mut args: Args) -> Result<()> {
+    let Some(manifest_paths) = expand_manifest_path_glob(&args.manifest_path)? else {
+        resolve_feature_set(&mut args)?;
+        resolve_no_std(&mut args)?;
+        return list_or_diff_and_perform_final_actions(&args);
+    };
+
+    let features = args.features.clone();
+    let no_default_features = args.no_default_features;
+    let bin = args.bin.clone();
+    for (index, manifest_path) in manifest_paths.into_iter().enumerate() {
+        // Headers would make the output of e.g. `--output-format=json` unparsable
+        if args.output_format == OutputFormat::Plain {
+            if index > 0 {
+                println!();
+            }
+            println!("==> {} <==", manifest_path.display());
+        }
+
+        args.manifest_path = manifest_path;
+        args.features.clone_from(&features);
+        args.no_default_features = no_default_features;
+        args.bin.clone_from(&bin);
+        resolve_target(&mut args);
+        resolve_feature_set(&mut args)?;
+        resolve_no_std(&mut args)?;
+        list_or_diff_and_perform_final_actions(&args)?;
+    }
+
+    Ok(())
+}
+
+/// Works out which target of the current dir to document, and tells the user
+/// about it. Done once up front, since we may build the current dir several
+/// times, e.g. when diffing, and the notes shall only be printed once.
+fn resolve_target(args: &mut Args) {
+    if args.rustdoc_json.is_some() || args.diff_rustdoc_json.is_some() || args.dependency.is_some()
+    {
+        return;
+    }
+
+    if args.bin.is_none() {
+        args.bin = sole_bin_target(args);
+    }
+    if args.bin.is_none() {
+        note_if_proc_macro(args);
+    }
+}
+
+/// Adds the features of `--feature-set` to the features to activate.
+fn resolve_feature_set(args: &mut Args) -> Result<()> {
+    if let Some(name) = &args.feature_set {
+        let features = feature_set::features(&args.manifest_path, name)?;
+        args.features.extend(features);
+    }
+    Ok(())
+}
+
+/// Replaces the default features with all default features except `std` if
+/// `--no-std-detection` finds a `no_std` crate that activates `std` by default.
+fn resolve_no_std(args: &mut Args) -> Result<()> {
+    if !args.no_std_detection
+        || args.all_features
+        || args.no_default_features
+        || args
+            .features
+            .iter()
+            .flat_map(|features| features.split([',', ' ']))
+            .any(|feature| feature == "std")
+    {
+        return Ok(());
+    }
 
+    if let Some(features) = no_std::default_features_without_std(&args.manifest_path)? {
+        eprintln!("Note: the crate is `no_std`, so its default `std` feature is not activated. Use `--features std` to include items that need `std`");
+        args.no_default_features = true;
+        args.features.extend(features);
+    }
+    Ok(())
+}
+
+/// If `manifest_path` is a glob pattern, returns the paths it matches, in
+/// alphabetical order. Returns `None` if it is a regular path.
+fn expand_manifest_path_glob(manifest_path: &Path) -> Result<Option<Vec<PathBuf>>> {
+    let pattern = manifest_path.to_string_lossy();
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(None);
+    }
+
+    let manifest_paths = glob::glob(&pattern)
+        .with_context(|| format!("Invalid `--manifest-path` glob pattern `{pattern}`"))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if manifest_paths.is_empty() {
+        return Err(anyhow!("No manifest matches `--manifest-path {pattern}`"));
+    }
+
+    Ok(Some(manifest_paths))
+}
+
+fn list_or_diff_and_perform_final_actions(args: &Args) -> Result<()> {
     // A list of actions to perform after we have listed or diffed. Typical
     // examples: restore a git branch or check that a diff is allowed
     let mut final_actions = vec![];
 
-    let result = list_or_diff(&args, &mut final_actions);
+    let result = list_or_diff(args, &mut final_actions);
 
+    if result.is_err() && args.no_cleanup_on_error {
+        let (skipped, rest): (Vec<_>, Vec<_>) =
+            final_actions.into_iter().partition(Action::is_cleanup);
+        note_skipped_cleanup(args, &skipped);
+        final_actions = rest;
+    }
     for action in final_actions {
-        action.perform(&args)?;
+        action.perform(args)?;
     }
 
     result
 }
 
+/// With `--no-cleanup-on-error`, tells the user where we left things after a
+/// failure. Finding out the current git checkout is best-effort, since the
+/// error that made us skip the cleanup is the one the user needs to see.
+fn note_skipped_cleanup(args: &Args, skipped: &[Action]) {
+    let current = args
+        .git_root()
+        .and_then(git_utils::current_branch_or_commit)
+        .ok();
+    for action in skipped {
+        match (action, &current) {
+            (Action::RestoreBranch { name, .. }, Some(current)) => eprintln!(
+                "Note: leaving the git checkout at `{current}` because of `--no-cleanup-on-error`. \
+                Run `git checkout {name}` to go back"
+            ),
+            (Action::RestoreBranch { name, .. }, None) => eprintln!(
+                "Note: leaving the git checkout as-is because of `--no-cleanup-on-error`. \
+                Run `git checkout {name}` to go back"
+            ),
+            (Action::RemoveWorktree { path }, _) => eprintln!(
+                "Note: leaving the git worktree at {} because of `--no-cleanup-on-error`. \
+                Run `git worktree remove --force {}` to remove it",
+                path.display(),
+                path.display()
+            ),
+            (Action::CheckDiff { .. }, _) => {}
+        }
+    }
+}
+
 fn list_or_diff(args: &Args, final_actions: &mut Vec<Action>) -> Result<()> {
-    if let Some(commits) = &args.diff_git_checkouts {
+    if args.dry_run {
+        print_rustdoc_json_build_command(args);
+        Ok(())
+    } else if let Some(commits) = &args.diff_git_checkouts {
         print_diff_between_two_commits(args, commits, final_actions)
     } else if let Some(files) = &args.diff_rustdoc_json {
         // clap ensures both args exists if we get here
@@ -253,36 +881,117 @@ fn list_or_diff(args: &Args, final_actions: &mut Vec<Action>) -> Result<()> {
             files.get(1).unwrap(),
             final_actions,
         )
+    } else if let Some(commit_and_file) = &args.diff_commit_and_rustdoc_json {
+        print_diff_between_commit_and_rustdoc_json_file(args, commit_and_file, final_actions)
+    } else if let Some(package_specs) = &args.diff_two_published {
+        // `resolve_diff_shorthand()` ensures we have exactly two specs
+        let (old, new) = build_old_and_new(
+            args,
+            || published_crate::build_rustdoc_json(&package_specs[0], args),
+            || published_crate::build_rustdoc_json(&package_specs[1], args),
+        )?;
+        print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
     } else if let Some(package_spec) = &args.diff_published {
-        print_diff_between_two_rustdoc_json_files(
+        let (old, new) = build_old_and_new(
             args,
-            &published_crate::build_rustdoc_json(package_spec, args)?,
-            &rustdoc_json_for_current_dir(args)?,
-            final_actions,
-        )
+            || published_crate::build_rustdoc_json(package_spec, args),
+            || rustdoc_json_for_current_dir(args),
+        )?;
+        print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
+    } else if let Some(baseline_path) = &args.baseline_path {
+        let (old, new) = build_old_and_new(
+            args,
+            || published_crate::build_rustdoc_json_for_baseline_path(baseline_path, args),
+            || rustdoc_json_for_current_dir(args),
+        )?;
+        print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
+    } else if let Some(targets) = &args.diff_targets {
+        // clap ensures both args exists if we get here
+        let (old, new) = build_old_and_new(
+            args,
+            || build_rustdoc_json(args, current_dir_builder(args).target(targets[0].clone())),
+            || build_rustdoc_json(args, current_dir_builder(args).target(targets[1].clone())),
+        )?;
+        print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
+    } else if let Some(commit_or_file) = &args.diff_against_current_dir {
+        print_diff_against_current_dir(args, commit_or_file, final_actions)
+    } else if let Some(baseline) = &args.diff_baseline {
+        print_diff_between_baseline_and_current_dir(args, baseline, final_actions)
+    } else if let Some(feature) = &args.feature_delta {
+        print_feature_delta(args, feature)
     } else if let Some(rustdoc_json) = &args.rustdoc_json {
         print_public_items_from_json(args, rustdoc_json)
+    } else if let Some(name) = &args.dependency {
+        print_public_items_of_dependency(args, name)
     } else {
         print_public_items_of_current_dir(args)
     }
 }
 
-/// We were requested to deny diffs, so make sure there is no diff
-fn check_diff(deny: &[DenyMethod], diff: &PublicApiDiff) -> Result<()> {
-    let mut violations = crate::error::Violations::new();
-    for d in deny {
-        if d.deny_added() && !diff.added.is_empty() {
-            violations.extend_added(diff.added.iter().cloned());
-        }
-        if d.deny_changed() && !diff.changed.is_empty() {
-            violations.extend_changed(diff.changed.iter().cloned());
-        }
-        if d.deny_removed() && !diff.removed.is_empty() {
-            violations.extend_removed(diff.removed.iter().cloned());
+/// We were requested to deny the removal of specific items, so make sure none
+/// of them were removed. A moved item is removed from its old path.
+fn check_removals(args: &Args, diff: &PublicApiDiff) -> Result<()> {
+    let removed: Vec<_> = diff
+        .removed
+        .iter()
+        .chain(diff.moved.iter().map(|(old, _)| old))
+        .filter(|item| args.denies_removal_of(item))
+        .cloned()
+        .collect();
+
+    if removed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(error::Error::RemovalDenied(removed)))
+    }
+}
+
+/// We were requested to fail if the diff requires a too large version bump, so
+/// make sure it does not.
+fn check_semver(args: &Args, diff: &PublicApiDiff) -> Result<()> {
+    let bump = diff.semver_bump();
+    match args.fail_on_semver {
+        Some(fail_on_semver) if fail_on_semver.fails(bump) => {
+            Err(anyhow!(error::Error::SemverBumpNotAllowed(bump)))
         }
+        _ => Ok(()),
+    }
+}
+
+/// We were requested to deny diffs, so make sure there is no diff, or at least
+/// no more denied items than `fail_threshold`
+fn check_diff(
+    deny: &[DenyMethod],
+    diff: &PublicApiDiff,
+    fail_threshold: Option<usize>,
+) -> Result<()> {
+    let mut violations = crate::error::Violations::new();
+    // Check each kind of diff only once, so that items are not counted twice
+    // by overlapping `--deny` values such as `--deny=removed --deny=all`
+    let deny_added = deny.iter().any(|d| d.deny_added());
+    let deny_removed = deny.iter().any(|d| d.deny_removed());
+    if deny_added {
+        violations.extend_added(diff.added.iter().cloned());
+    }
+    violations.extend_changed(
+        diff.changed
+            .iter()
+            .filter(|c| deny.iter().any(|d| d.deny_change(c)))
+            .cloned(),
+    );
+    if deny_removed {
+        violations.extend_removed(diff.removed.iter().cloned());
+    }
+    // A moved item is removed from its old path and added at its new path, so
+    // it is denied by either, but only counts once
+    if deny_added || deny_removed {
+        violations.extend_moved(diff.moved.iter().cloned());
     }
 
-    if violations.is_empty() {
+    let tolerated = fail_threshold.map_or(violations.is_empty(), |fail_threshold| {
+        violations.len() <= fail_threshold
+    });
+    if tolerated {
         Ok(())
     } else {
         Err(anyhow!(error::Error::DiffDenied(violations)))
@@ -290,17 +999,101 @@ fn check_diff(deny: &[DenyMethod], diff: &PublicApiDiff) -> Result<()> {
 }
 
 fn print_public_items_of_current_dir(args: &Args) -> Result<()> {
-    let public_api = public_api_for_current_dir(args)?;
-    print_public_items(args, &public_api)
+    let json_path = rustdoc_json_for_current_dir(args)?;
+    if let Some(emit_json) = &args.emit_json {
+        std::fs::copy(&json_path, emit_json)
+            .with_context(|| format!("Failed to write rustdoc JSON to {}", emit_json.display()))?;
+    }
+    print_public_items(args, json_path)
+}
+
+fn print_public_items_of_dependency(args: &Args, name: &str) -> Result<()> {
+    let source_dir = dependency::source_dir(&args.manifest_path, name)?;
+    eprintln!(
+        "Note: listing the public API of dependency `{name}` from {}",
+        source_dir.display()
+    );
+
+    // `cargo rustdoc --package` documents a dependency just like a package of
+    // the workspace, and uses the dependency versions in `Cargo.lock`
+    let json_path = build_rustdoc_json(args, builder_from_args(args).package(name))?;
+    print_public_items(args, json_path)
+}
+
+/// Builds the public API of the current dir both with and without `feature`,
+/// and lists the items that are only present with it.
+fn print_feature_delta(args: &Args, feature: &str) -> Result<()> {
+    let public_api_with_features = |features: &[String]| {
+        let mut builder = builder_from_args(args).features(features);
+        if let Some(bin) = &args.bin {
+            builder = builder.bin(bin);
+        }
+        public_api_from_rustdoc_json_path(build_rustdoc_json(args, builder)?, args)
+    };
+
+    let without = public_api_with_features(&args.features)?;
+    let with =
+        public_api_with_features(&[args.features.clone(), vec![feature.to_owned()]].concat())?;
+
+    let diff = PublicApiDiff::between(without, with);
+    Plain::print_items(&mut stdout(), args, diff.added.iter())?;
+
+    Ok(())
 }
 
 fn print_public_items_from_json(args: &Args, json_path: &str) -> Result<()> {
-    let public_api = public_api_from_rustdoc_json_path(json_path, args)?;
-    print_public_items(args, &public_api)
+    print_public_items(args, json_path)
 }
 
-fn print_public_items(args: &Args, public_api: &PublicApi) -> Result<()> {
-    Plain::print_items(&mut stdout(), args, public_api.items())?;
+fn print_public_items(args: &Args, json_path: impl AsRef<Path>) -> Result<()> {
+    if args.public_deps {
+        let public_api = public_api_from_rustdoc_json_path(json_path, args)?;
+        for dependency in public_api.public_dependencies() {
+            println!("{dependency}");
+        }
+        return Ok(());
+    }
+
+    // Print each item as soon as it has been rendered, so that output begins
+    // early even for crates with very many items. Once writing fails, e.g.
+    // because output is piped to `head`, the remaining items are only counted.
+    let mut w = stdout().lock();
+    let mut count = 0;
+    let mut has_items = false;
+    let mut write_result = Ok(());
+    let mut impls_for = args.list_impls_for.as_deref().map(ImplsFor::new);
+    for_each_item_in_rustdoc_json_path(json_path, args, |item| {
+        if let Some(impls_for) = &mut impls_for {
+            if !impls_for.is_impl_item(&item) {
+                return;
+            }
+        }
+        count += 1;
+        has_items |= !item.is_crate_root();
+        if write_result.is_ok() {
+            write_result = Plain::print_item(&mut w, args, &item);
+        }
+    })?;
+    write_result?;
+
+    if let Some(type_path) = &args.list_impls_for {
+        if count == 0 {
+            return Err(anyhow!("No impls found for `{type_path}`"));
+        }
+    } else if !has_items {
+        if !args.allow_empty {
+            return Err(anyhow!(error::Error::NoPublicItems));
+        }
+        eprintln!("Note: No public items found");
+    }
+
+    if let Some(max) = args.max_public_items {
+        if count > max {
+            return Err(anyhow!(
+                "The public API has {count} items, which is more than the {max} allowed by `--max-public-items`"
+            ));
+        }
+    }
 
     Ok(())
 }
@@ -320,15 +1113,30 @@ fn print_diff_between_two_commits(
     // Validate provided commits and resolve relative refs like HEAD to actual commits
     let old_commit = git_utils::resolve_ref(&args.git_root()?, old_commit)?;
     let new_commit = git_utils::resolve_ref(&args.git_root()?, new_commit)?;
+    let old_commit = if args.merge_base {
+        git_utils::merge_base(&args.git_root()?, &old_commit, &new_commit)?
+    } else {
+        old_commit
+    };
+
+    if args.parallel {
+        return print_diff_between_two_commits_in_parallel(
+            args,
+            &old_commit,
+            &new_commit,
+            final_actions,
+        );
+    }
 
-    // Checkout the first commit and remember the branch so we can restore it
+    // Checkout the first commit and remember the branch so we can restore it,
+    // also if building the first commit fails
     let force = args.force_git_checkouts;
     let original_branch = git_checkout(args, force, &old_commit)?;
-    let old = public_api_for_current_dir(args)?;
     final_actions.push(Action::RestoreBranch {
         name: original_branch,
         force,
     });
+    let old = public_api_for_current_dir(args)?;
 
     // Checkout the second commit
     git_checkout(args, force, &new_commit)?;
@@ -340,6 +1148,103 @@ fn print_diff_between_two_commits(
     Ok(())
 }
 
+/// Like [`print_diff_between_two_commits()`], but builds the old commit in a
+/// separate `git worktree` at the same time as the new commit is built in the
+/// current working tree.
+fn print_diff_between_two_commits_in_parallel(
+    args: &Args,
+    old_commit: &str,
+    new_commit: &str,
+    final_actions: &mut Vec<Action>,
+) -> Result<()> {
+    let worktree = add_worktree(args, old_commit, final_actions)?;
+
+    let force = args.force_git_checkouts;
+    let original_branch = git_checkout(args, force, new_commit)?;
+    final_actions.push(Action::RestoreBranch {
+        name: original_branch,
+        force,
+    });
+
+    let (old, new) = build_old_and_new(
+        args,
+        || rustdoc_json_for_worktree(args, &worktree),
+        || rustdoc_json_for_current_dir(args),
+    )?;
+    print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
+}
+
+/// Adds a `git worktree` with `commit` checked out, which is removed again
+/// by a final action. Returns the path to the worktree.
+fn add_worktree(args: &Args, commit: &str, final_actions: &mut Vec<Action>) -> Result<PathBuf> {
+    let git_root = args.git_root()?;
+    // Refs such as `origin/main` contain `/`, so name the worktree after the
+    // commit hash. The process id keeps concurrent runs apart.
+    let commit = git_utils::resolve_ref(&git_root, commit)?;
+    let worktree = worktrees_dir(args)?.join(format!("{commit}-{}", std::process::id()));
+    git_utils::git_worktree_add(&git_root, &worktree, &commit, !args.verbose)?;
+    final_actions.push(Action::RemoveWorktree {
+        path: worktree.clone(),
+    });
+    Ok(worktree)
+}
+
+/// Builds rustdoc JSON for the same package as [`current_dir_builder()`],
+/// but in the `git worktree` at `worktree`.
+fn rustdoc_json_for_worktree(args: &Args, worktree: &Path) -> Result<PathBuf> {
+    let git_root = args.git_root()?;
+    let manifest_path = std::fs::canonicalize(&args.manifest_path)?;
+    let manifest_path_in_repo = manifest_path.strip_prefix(&git_root)?;
+
+    // The target dir is outside of the worktree so that it survives the
+    // removal of the worktree, which makes the next build incremental
+    build_rustdoc_json(
+        args,
+        current_dir_builder(args)
+            .manifest_path(worktree.join(manifest_path_in_repo))
+            .target_dir(worktrees_dir(args)?.join("target")),
+    )
+}
+
+/// Where we put `git worktree`s. Like for published crates, we prefer a
+/// non-temporary dir so that repeated builds can be incremental. Each repo
+/// gets its own dir, named after a hash of the path of the repo, so that
+/// worktrees of different repos never get mixed up.
+fn worktrees_dir(args: &Args) -> Result<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    std::fs::canonicalize(args.git_root()?)?.hash(&mut hasher);
+
+    let mut dir = args
+        .target_dir
+        .clone()
+        .unwrap_or_else(|| dirs::cache_dir().unwrap_or_else(std::env::temp_dir));
+    dir.push("cargo-public-api");
+    dir.push("worktrees");
+    dir.push(format!("{:016x}", hasher.finish()));
+    Ok(dir)
+}
+
+/// Builds the old and the new side of a diff. At the same time if
+/// `--parallel` is used, otherwise one after the other.
+fn build_old_and_new<T: Send>(
+    args: &Args,
+    old: impl FnOnce() -> Result<T> + Send,
+    new: impl FnOnce() -> Result<T> + Send,
+) -> Result<(T, T)> {
+    if !args.parallel {
+        return Ok((old()?, new()?));
+    }
+
+    std::thread::scope(|scope| {
+        let old = scope.spawn(old);
+        let new = new();
+        let old = old
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        Ok((old?, new?))
+    })
+}
+
 fn print_diff_between_two_rustdoc_json_files(
     args: &Args,
     old_file: impl AsRef<Path>,
@@ -354,6 +1259,75 @@ fn print_diff_between_two_rustdoc_json_files(
     Ok(())
 }
 
+/// Handles `--diff` with one commit and one rustdoc JSON file, in any order.
+/// The public API for the commit is built after a `git checkout` of it.
+fn print_diff_between_commit_and_rustdoc_json_file(
+    args: &Args,
+    commit_and_file: &[String],
+    final_actions: &mut Vec<Action>,
+) -> Result<()> {
+    let mut apis = vec![];
+    for commit_or_file in commit_and_file {
+        let api = if is_json_file(commit_or_file) {
+            public_api_from_rustdoc_json_path(commit_or_file, args)?
+        } else {
+            let commit = git_utils::resolve_ref(&args.git_root()?, commit_or_file)?;
+            let force = args.force_git_checkouts;
+            let original_branch = git_checkout(args, force, &commit)?;
+            final_actions.push(Action::RestoreBranch {
+                name: original_branch,
+                force,
+            });
+            public_api_for_current_dir(args)?
+        };
+        apis.push(api);
+    }
+
+    // `resolve_diff_shorthand()` ensures we have exactly two operands
+    let new = apis.pop().unwrap();
+    let old = apis.pop().unwrap();
+    print_diff(args, old, new, final_actions)
+}
+
+/// Diffs a commit or a rustdoc JSON file against the current working tree,
+/// including any uncommitted changes.
+fn print_diff_against_current_dir(
+    args: &Args,
+    commit_or_file: &str,
+    final_actions: &mut Vec<Action>,
+) -> Result<()> {
+    if is_json_file(commit_or_file) {
+        let old = public_api_from_rustdoc_json_path(commit_or_file, args)?;
+        let new = public_api_for_current_dir(args)?;
+        return print_diff(args, old, new, final_actions);
+    }
+
+    // Build the commit in a separate `git worktree` so that the current working
+    // tree, including any uncommitted changes, is never touched
+    let commit = git_utils::resolve_ref(&args.git_root()?, commit_or_file)?;
+    let worktree = add_worktree(args, &commit, final_actions)?;
+
+    let (old, new) = build_old_and_new(
+        args,
+        || rustdoc_json_for_worktree(args, &worktree),
+        || rustdoc_json_for_current_dir(args),
+    )?;
+    print_diff_between_two_rustdoc_json_files(args, old, new, final_actions)
+}
+
+fn print_diff_between_baseline_and_current_dir(
+    args: &Args,
+    baseline: &Path,
+    final_actions: &mut Vec<Action>,
+) -> Result<()> {
+    let baseline = std::fs::read_to_string(baseline)
+        .with_context(|| format!("Failed to read baseline {}", baseline.display()))?;
+    let new = public_api_for_current_dir(args)?;
+
+    let diff = PublicApiDiff::between_baseline(&baseline, new);
+    print_and_check_diff(args, diff, final_actions)
+}
+
 fn print_diff(
     args: &Args,
     old: PublicApi,
@@ -361,28 +1335,99 @@ fn print_diff(
     final_actions: &mut Vec<Action>,
 ) -> Result<()> {
     let diff = PublicApiDiff::between(old, new);
+    print_and_check_diff(args, diff, final_actions)
+}
+
+fn print_and_check_diff(
+    args: &Args,
+    diff: PublicApiDiff,
+    final_actions: &mut Vec<Action>,
+) -> Result<()> {
+    let diff = if args.ignore_external.is_empty() {
+        diff
+    } else {
+        ignore_external::ignore_changes_in(diff, &args.ignore_external)
+    };
+
+    if args.print_bump {
+        println!("{}", diff.semver_bump());
+    } else {
+        print_diff_in_output_format(args, &diff)?;
+    }
 
-    Plain::print_diff(&mut stdout(), args, &diff)?;
+    // Adding an attribute looks harmless in a diff, so point out why adding
+    // this one is a breaking change
+    for changed in diff.changed.iter().filter(|c| c.became_non_exhaustive()) {
+        eprintln!(
+            "Note: `{}` became `#[non_exhaustive]`, which is a breaking change since other crates can no longer construct it or match on it exhaustively",
+            item_path(&changed.new)
+        );
+    }
 
-    if let Some(deny) = &args.deny {
+    if args.deny.is_some() || !args.deny_removal_of.is_empty() || args.fail_on_semver.is_some() {
         final_actions.push(Action::CheckDiff {
             diff,
-            deny: deny.clone(),
+            deny: args.deny.clone().unwrap_or_default(),
         });
     }
 
     Ok(())
 }
 
+/// Returns the path of `item`, e.g. `krate::Enum` for `#[non_exhaustive] pub
+/// enum krate::Enum<T>`.
+fn item_path(item: &PublicItem) -> String {
+    item.tokens()
+        .skip_while(|token| !matches!(token, Token::Kind(_)))
+        .skip_while(|token| matches!(token, Token::Kind(_) | Token::Whitespace))
+        .take_while(|token| match token {
+            Token::Identifier(_) | Token::Type(_) | Token::Function(_) => true,
+            Token::Symbol(symbol) => symbol == "::",
+            _ => false,
+        })
+        .map(Token::text)
+        .collect()
+}
+
+fn print_diff_in_output_format(args: &Args, diff: &PublicApiDiff) -> Result<()> {
+    match args.output_format {
+        OutputFormat::Plain => Plain::print_diff(&mut stdout(), args, diff)?,
+        OutputFormat::Github => Github::print_diff(&mut stdout(), args, diff)?,
+        OutputFormat::Json => {
+            let mut report = DiffReport::from(diff);
+            if args.only_breaking {
+                report.added.clear();
+            }
+            serde_json::to_writer_pretty(stdout(), &report)?;
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
 impl Action {
+    /// If the action only restores things as they were before we started.
+    const fn is_cleanup(&self) -> bool {
+        matches!(
+            self,
+            Action::RestoreBranch { .. } | Action::RemoveWorktree { .. }
+        )
+    }
+
     fn perform(&self, args: &Args) -> Result<()> {
         match self {
             Action::CheckDiff { deny, diff } => {
-                check_diff(deny, diff)?;
+                check_removals(args, diff)?;
+                check_semver(args, diff)?;
+                check_diff(deny, diff, args.fail_threshold)?;
             }
             Action::RestoreBranch { name, force } => {
                 git_checkout(args, *force, name)?;
             }
+            Action::RemoveWorktree { path } => {
+                git_utils::git_worktree_remove(&args.git_root()?, path)?;
+            }
         };
         Ok(())
     }
@@ -392,6 +1437,24 @@ impl Args {
     fn git_root(&self) -> Result<PathBuf> {
         git_utils::git_root_from_manifest_path(self.manifest_path.as_path())
     }
+
+    /// If `--deny-removal-of` says that `item` must not be removed.
+    fn denies_removal_of(&self, item: &PublicItem) -> bool {
+        self.deny_removal_of.iter().any(|path| item.has_path(path))
+    }
+
+    /// If any of the ways to diff is used, as opposed to listing.
+    fn is_diffing(&self) -> bool {
+        self.diff_git_checkouts.is_some()
+            || self.diff_published.is_some()
+            || self.diff_rustdoc_json.is_some()
+            || self.diff_commit_and_rustdoc_json.is_some()
+            || self.diff_two_published.is_some()
+            || self.diff_against_current_dir.is_some()
+            || self.diff_baseline.is_some()
+            || self.baseline_path.is_some()
+            || self.diff_targets.is_some()
+    }
 }
 
 /// Get CLI args via `clap` while also handling when we are invoked as a cargo
@@ -405,23 +1468,119 @@ fn get_args() -> Result<Args> {
 
     let mut args = Args::parse_from(args_os);
     if let Some(diff_args) = args.diff.clone() {
-        resolve_diff_shorthand(&mut args, diff_args);
+        resolve_diff_shorthand(&mut args, diff_args)?;
     }
     resolve_toolchain(&mut args);
+    resolve_target_dir(&mut args)?;
+    if args.docs_rs_links {
+        resolve_docs_rs_links(&mut args)?;
+    }
 
     // Manually check this until a `cargo public-api diff ...` subcommand is in
     // place, which will enable clap to perform this check
-    if args.deny.is_some()
-        && args.diff_git_checkouts.is_none()
+    if (args.deny.is_some()
+        || !args.deny_removal_of.is_empty()
+        || args.fail_on_semver.is_some()
+        || args.print_bump
+        || args.include_blanket_impls
+        || !args.ignore_external.is_empty()
+        || args.only_breaking)
+        && !args.is_diffing()
+    {
+        let arg = if args.deny.is_some() {
+            "--deny"
+        } else if args.fail_on_semver.is_some() {
+            "--fail-on-semver"
+        } else if args.print_bump {
+            "--print-bump"
+        } else if args.include_blanket_impls {
+            "--include-blanket-impls"
+        } else if !args.ignore_external.is_empty() {
+            "--ignore-external"
+        } else if args.only_breaking {
+            "--only-breaking"
+        } else {
+            "--deny-removal-of"
+        };
+        Err(anyhow!("`{arg}` can only be used when diffing"))
+    } else if args.merge_base && args.diff_git_checkouts.is_none() {
+        Err(anyhow!(
+            "`--merge-base` can only be used when diffing git checkouts"
+        ))
+    } else if args.registry.is_some()
+        && args.diff_published.is_none()
+        && args.diff_two_published.is_none()
+    {
+        Err(anyhow!(
+            "`--registry` can only be used when diffing published crates"
+        ))
+    } else if args.refresh_baseline
         && args.diff_published.is_none()
-        && args.diff_rustdoc_json.is_none()
+        && args.diff_two_published.is_none()
     {
-        Err(anyhow!("`--deny` can only be used when diffing"))
+        Err(anyhow!(
+            "`--refresh-baseline` can only be used when diffing published crates"
+        ))
+    } else if published_crate::Features::has_baseline_args(&args)
+        && args.diff_published.is_none()
+        && args.diff_two_published.is_none()
+        && args.baseline_path.is_none()
+    {
+        Err(anyhow!(
+            "`--baseline-features`, `--baseline-all-features` and `--baseline-no-default-features` can only be used when diffing published crates"
+        ))
+    } else if args.public_deps && (args.is_diffing() || args.feature_delta.is_some()) {
+        Err(anyhow!("`--public-deps` can only be used when listing"))
+    } else if args.emit_json.is_some()
+        && (args.is_diffing()
+            || args.feature_delta.is_some()
+            || args.rustdoc_json.is_some()
+            || args.dependency.is_some())
+    {
+        Err(anyhow!(
+            "`--emit-json` can only be used when listing the public API of the current crate"
+        ))
+    } else if args.max_public_items.is_some() && (args.is_diffing() || args.feature_delta.is_some())
+    {
+        Err(anyhow!(
+            "`--max-public-items` can only be used when listing"
+        ))
     } else {
         Ok(args)
     }
 }
 
+/// Figure out the docs.rs URLs of the published crates we are to diff.
+fn resolve_docs_rs_links(args: &mut Args) -> Result<()> {
+    args.docs_rs = if let Some(package_specs) = &args.diff_two_published {
+        docs_rs::Links {
+            old: Some(published_crate::docs_rs_url(&package_specs[0], args)?),
+            new: Some(published_crate::docs_rs_url(&package_specs[1], args)?),
+        }
+    } else if let Some(package_spec) = &args.diff_published {
+        docs_rs::Links {
+            old: Some(published_crate::docs_rs_url(package_spec, args)?),
+            new: None,
+        }
+    } else {
+        return Err(anyhow!(
+            "`--docs-rs-links` can only be used when diffing published crates"
+        ));
+    };
+    Ok(())
+}
+
+/// Make a relative `--target-dir` absolute up front, so that all builds use
+/// the same target dir, even builds of other checkouts in other dirs.
+fn resolve_target_dir(args: &mut Args) -> Result<()> {
+    if let Some(target_dir) = &args.target_dir {
+        if target_dir.is_relative() {
+            args.target_dir = Some(std::env::current_dir()?.join(target_dir));
+        }
+    }
+    Ok(())
+}
+
 /// Check if using a stable compiler, and use nightly if it is.
 fn resolve_toolchain(args: &mut Args) {
     if toolchain::is_probably_stable(args.toolchain.as_deref()) {
@@ -432,28 +1591,61 @@ fn resolve_toolchain(args: &mut Args) {
     }
 }
 
-/// Resolve `--diff` to either `--diff-git-checkouts` or `--diff-rustdoc-json`
-fn resolve_diff_shorthand(args: &mut Args, diff_args: Vec<String>) {
-    fn is_json_file(file_name: &String) -> bool {
-        Path::extension(Path::new(file_name)).map_or(false, |a| a.eq_ignore_ascii_case("json"))
-    }
-
-    if diff_args.iter().all(is_json_file) {
+/// Resolve `--diff` to either `--diff-git-checkouts`, `--diff-rustdoc-json`,
+/// `--diff-published`, or a diff between a commit and a rustdoc JSON file
+fn resolve_diff_shorthand(args: &mut Args, diff_args: Vec<String>) -> Result<()> {
+    let json_files = diff_args.iter().filter(|a| is_json_file(a)).count();
+
+    if let [arg] = &diff_args[..] {
+        if arg.contains('@') {
+            args.diff_published = Some(arg.clone());
+        } else if !is_json_file(arg) && Path::new(arg).is_file() {
+            args.diff_baseline = Some(PathBuf::from(arg));
+        } else {
+            args.diff_against_current_dir = Some(arg.clone());
+        }
+    } else if json_files == diff_args.len() {
         args.diff_rustdoc_json = Some(diff_args);
+    } else if diff_args.len() > 1 && diff_args.iter().all(|a| a.contains('@')) {
+        args.diff_two_published = Some(diff_args);
     } else if diff_args.iter().any(|a| a.contains('@')) {
-        args.diff_published = diff_args.first().cloned();
+        return Err(anyhow!(
+            "A published crate is diffed either against the current working tree or \
+            against another published crate, so `--diff` takes no other kind of \
+            argument in that case. Got: {diff_args:?}"
+        ));
+    } else if json_files > 0 {
+        args.diff_commit_and_rustdoc_json = Some(diff_args);
     } else {
         args.diff_git_checkouts = Some(diff_args);
     }
+
+    Ok(())
+}
+
+fn is_json_file(file_name: impl AsRef<Path>) -> bool {
+    file_name
+        .as_ref()
+        .extension()
+        .map_or(false, |a| a.eq_ignore_ascii_case("json"))
 }
 
 /// Figure out what [`Options`] to pass to
-/// [`public_api::PublicApi::from_rustdoc_json_str`] based on our
+/// [`public_api::PublicApi::from_rustdoc_json`] based on our
 /// [`Args`]
 fn get_options(args: &Args) -> Options {
     let mut options = Options::default();
     options.debug_sorting = args.debug_sorting;
     options.simplified = args.simplified;
+    options.include_blanket_impls = args.include_blanket_impls;
+    options.omit_auto_derived_impls = args.omit.contains(&Omit::AutoDerivedImpls);
+    options.qualify_external = args.qualify_external;
+    options.show_values = args.show_values;
+    options.mark_provided_methods = args.mark_provided_methods;
+    options.show_cfgs = args.show_cfgs;
+    options.relative_paths = args.path_style == PathStyle::Relative;
+    options.public_dependencies = args.public_deps;
+    options.format_version = args.format_version;
     options
 }
 
@@ -475,8 +1667,56 @@ fn public_api_for_current_dir(args: &Args) -> Result<PublicApi, anyhow::Error> {
 /// Builds the rustdoc JSON for the library in the current working directory.
 /// Also see [`public_api_for_current_dir()`].
 fn rustdoc_json_for_current_dir(args: &Args) -> Result<PathBuf, anyhow::Error> {
-    let builder = builder_from_args(args);
-    build_rustdoc_json(builder)
+    build_rustdoc_json(args, current_dir_builder(args))
+}
+
+/// Creates a rustdoc JSON builder for the library in the current working
+/// directory, or for its binary if it has no library.
+fn current_dir_builder(args: &Args) -> rustdoc_json::Builder {
+    let mut builder = builder_from_args(args);
+    if let Some(bin) = &args.bin {
+        builder = builder.bin(bin);
+    }
+    builder
+}
+
+/// Proc-macro crates can only export their macros, so their public API is
+/// often close to empty. Let the user know, so that a short listing does not
+/// come as a surprise.
+fn note_if_proc_macro(args: &Args) {
+    if args.package.is_some() {
+        return;
+    }
+
+    let Ok(manifest) = cargo_manifest::Manifest::from_path(&args.manifest_path) else {
+        return;
+    };
+    if let (Some(package), Some(lib)) = (&manifest.package, &manifest.lib) {
+        if lib.proc_macro {
+            eprintln!(
+                "Note: `{}` is a proc-macro crate, so only the macros it defines (if any) are listed",
+                package.name
+            );
+        }
+    }
+}
+
+/// If the package has no library target but exactly one binary target, returns
+/// the name of the binary target, so that we can document that instead.
+fn sole_bin_target(args: &Args) -> Option<String> {
+    if args.package.is_some() {
+        return None;
+    }
+
+    let manifest = cargo_manifest::Manifest::from_path(&args.manifest_path).ok()?;
+    match (&manifest.package, &manifest.lib, manifest.bin.as_deref()) {
+        (Some(_), None, Some([bin])) => {
+            let name = bin.name.clone()?;
+            eprintln!("Note: no library target found, listing the public API of binary `{name}`");
+            Some(name)
+        }
+        _ => None,
+    }
 }
 
 /// Creates a rustdoc JSON builder based on the args to this program.
@@ -486,7 +1726,10 @@ fn builder_from_args(args: &Args) -> rustdoc_json::Builder {
         .manifest_path(&args.manifest_path)
         .all_features(args.all_features)
         .no_default_features(args.no_default_features)
-        .features(&args.features);
+        .features(&args.features)
+        .quiet(args.quiet)
+        .verbose(args.verbose)
+        .timeout(args.timeout);
     if let Some(target_dir) = &args.target_dir {
         builder = builder.target_dir(target_dir.clone());
     }
@@ -499,14 +1742,75 @@ fn builder_from_args(args: &Args) -> rustdoc_json::Builder {
     if let Some(cap_lints) = &args.cap_lints {
         builder = builder.cap_lints(Some(cap_lints));
     }
+    if args.show_cfgs {
+        builder = builder.cargo_config("build.rustdocflags", r#"["--cfg", "docsrs"]"#);
+    }
     builder
 }
 
+/// Prints the command that builds rustdoc JSON for the current dir, for
+/// `--dry-run`. Quoted so that it can be copied and pasted into a shell.
+fn print_rustdoc_json_build_command(args: &Args) {
+    let command = current_dir_builder(args).command();
+    let line = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("{line}");
+}
+
+/// Puts `arg` in single quotes unless it only consists of characters that no
+/// shell treats specially.
+fn shell_quote(arg: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
 /// Helper to build rustdoc JSON with a builder while also handling any virtual
-/// manifest errors.
-fn build_rustdoc_json(builder: rustdoc_json::Builder) -> Result<PathBuf, anyhow::Error> {
-    match builder.build() {
+/// manifest errors. Shows a progress spinner if stderr is a terminal, unless
+/// `--quiet` is used.
+fn build_rustdoc_json(
+    args: &Args,
+    mut builder: rustdoc_json::Builder,
+) -> Result<PathBuf, anyhow::Error> {
+    let spinner = progress::spinner_for_stderr(args.quiet);
+    if let Some(spinner) = spinner.clone() {
+        builder = builder.progress_callback(move |line| {
+            // Progress output is best-effort, so ignore any errors
+            let _ = spinner.lock().map(|mut spinner| spinner.line(line));
+        });
+    }
+
+    let result = builder.build();
+
+    if let Some(spinner) = &spinner {
+        let _ = spinner.lock().map(|mut spinner| spinner.finish());
+    }
+
+    match result {
         Err(BuildError::VirtualManifest(manifest_path)) => virtual_manifest_error(&manifest_path),
+        Err(BuildError::NoLibraryTarget(manifest_path)) => no_library_target_error(&manifest_path),
+        Err(BuildError::Timeout(timeout)) => Err(anyhow!(
+            "Building rustdoc JSON timed out after {} seconds. Use a larger `--timeout` to allow more time",
+            timeout.as_secs_f64()
+        )),
+        Err(BuildError::RustupNotFound(toolchain)) => Err(anyhow!(
+            "The `{toolchain}` toolchain was requested, but `rustup` could not be found.\n\n\
+            Rustdoc JSON is built with `rustup run {toolchain} cargo rustdoc ...` when a toolchain is\n\
+            requested. A toolchain is requested either with `--toolchain`, or automatically if the\n\
+            `cargo` in PATH is a stable cargo, since building rustdoc JSON requires nightly.\n\n\
+            Either install rustup from https://rustup.rs, or make sure that the `cargo` in PATH is a\n\
+            nightly cargo and do not use `--toolchain`."
+        )),
+        // The spinner has already printed the errors from the build
+        Err(BuildError::General(_)) if spinner.is_some() => {
+            Err(anyhow!("Failed to build rustdoc JSON. See above"))
+        }
         res => Ok(res?),
     }
 }
@@ -515,34 +1819,82 @@ fn public_api_from_rustdoc_json_path<T: AsRef<Path>>(
     json_path: T,
     args: &Args,
 ) -> Result<PublicApi> {
-    let options = get_options(args);
+    let json_path = json_path.as_ref();
+    if !json_path.is_file() {
+        return Err(anyhow!(read_error_context(json_path)));
+    }
+    print_processing(args, json_path);
+
+    // Reads the file bit by bit, so that the whole rustdoc JSON never needs to
+    // be in memory at once
+    let public_api = PublicApi::from_rustdoc_json(json_path, get_options(args))
+        .with_context(|| parse_error_context(json_path))?;
+    print_best_effort_warning(args, &public_api);
+    print_verbose_notes(args, &public_api);
 
-    let rustdoc_json = &std::fs::read_to_string(&json_path)
-        .with_context(|| format!("Failed to read rustdoc JSON at {:?}", json_path.as_ref()))?;
+    Ok(public_api)
+}
+
+/// Like [`public_api_from_rustdoc_json_path`], but passes each item to
+/// `on_item` as soon as it has been rendered, see
+/// [`PublicApi::for_each_item_in_rustdoc_json`].
+fn for_each_item_in_rustdoc_json_path(
+    json_path: impl AsRef<Path>,
+    args: &Args,
+    on_item: impl FnMut(PublicItem),
+) -> Result<()> {
+    let json_path = json_path.as_ref();
+    if !json_path.is_file() {
+        return Err(anyhow!(read_error_context(json_path)));
+    }
+    print_processing(args, json_path);
+
+    let public_api =
+        PublicApi::for_each_item_in_rustdoc_json(json_path, get_options(args), on_item)
+            .with_context(|| parse_error_context(json_path))?;
+    print_best_effort_warning(args, &public_api);
+    print_verbose_notes(args, &public_api);
+
+    Ok(())
+}
+
+fn read_error_context(json_path: &Path) -> String {
+    format!("Failed to read rustdoc JSON at {json_path:?}")
+}
 
+fn print_processing(args: &Args, json_path: &Path) {
     if args.verbose {
-        println!("Processing {:?}", json_path.as_ref());
-    }
-
-    let public_api = PublicApi::from_rustdoc_json_str(rustdoc_json, options).with_context(|| {
-        format!(
-            "Failed to parse rustdoc JSON at {:?}.\n\
-            This version of `cargo public-api` requires at least:\n\n    {}\n\n\
-            If you have that, it might be `cargo public-api` that is out of date. Try\n\
-            to install the latest version with `cargo install cargo-public-api`. If the\n\
-            issue remains, please report at\n\n    https://github.com/Enselic/cargo-public-api/issues",
-            json_path.as_ref(),
-            MINIMUM_RUSTDOC_JSON_VERSION,
-        )
-    })?;
+        println!("Processing {json_path:?}");
+    }
+}
+
+fn parse_error_context(json_path: &Path) -> String {
+    format!(
+        "Failed to parse rustdoc JSON at {json_path:?}.\n\
+        This version of `cargo public-api` requires at least:\n\n    {MINIMUM_RUSTDOC_JSON_VERSION}\n\n\
+        If you have that, it might be `cargo public-api` that is out of date. Try\n\
+        to install the latest version with `cargo install cargo-public-api`. If the\n\
+        issue remains, please report at\n\n    https://github.com/Enselic/cargo-public-api/issues"
+    )
+}
 
+/// With `--format-version`, format adapters are not used, so a format adapter
+/// means that the rustdoc JSON did not parse and best-effort parsing was used.
+fn print_best_effort_warning(args: &Args, public_api: &PublicApi) {
+    if let (Some(format_version), Some(_)) = (args.format_version, public_api.format_adapter()) {
+        eprintln!("Warning: used best-effort parsing of rustdoc JSON with format version {format_version}. The public API might be incomplete or wrong!");
+    }
+}
+
+fn print_verbose_notes(args: &Args, public_api: &PublicApi) {
     if args.verbose {
+        if let Some(format_adapter) = public_api.format_adapter() {
+            println!("NOTE: {format_adapter}");
+        }
         public_api.missing_item_ids().for_each(|i| {
             println!("NOTE: rustdoc JSON missing referenced item with ID \"{i}\"");
         });
     }
-
-    Ok(public_api)
 }
 
 fn virtual_manifest_error(manifest_path: &Path) -> Result<PathBuf> {
@@ -559,6 +1911,27 @@ Try
     ))
 }
 
+fn no_library_target_error(manifest_path: &Path) -> Result<PathBuf> {
+    let bins = cargo_manifest::Manifest::from_path(manifest_path)
+        .ok()
+        .and_then(|manifest| manifest.bin)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|bin| bin.name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Err(anyhow!(
+        "`{:?}` has no library target, only binary targets: {bins}
+
+Try
+
+    cargo public-api --bin <NAME>
+",
+        manifest_path
+    ))
+}
+
 /// Wrapper to handle <https://github.com/rust-lang/rust/issues/46016>
 fn main() -> Result<()> {
     match main_() {
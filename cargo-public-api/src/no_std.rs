@@ -0,0 +1,79 @@
+//! Detection of `no_std` crates that have a default `std` feature, like this:
+//!
+//! ```toml
+//! [features]
+//! default = ["std"]
+//! std = []
+//! ```
+//!
+//! Documenting such crates with their default features would list items that
+//! are only available with `std`, which is not what `no_std` users get.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+/// The name of the feature that by convention enables `std`.
+const STD_FEATURE: &str = "std";
+
+/// If the package with the given manifest is `no_std` and activates the `std`
+/// feature by default, returns the rest of its default features. Otherwise
+/// returns `None`.
+pub fn default_features_without_std(manifest_path: &Path) -> Result<Option<Vec<String>>> {
+    let manifest = cargo_manifest::Manifest::from_path(manifest_path)?;
+    let Some(default_features) = manifest
+        .features
+        .and_then(|mut features| features.remove("default"))
+    else {
+        return Ok(None);
+    };
+    if !default_features
+        .iter()
+        .any(|feature| feature == STD_FEATURE)
+    {
+        return Ok(None);
+    }
+
+    let lib_path = manifest
+        .lib
+        .and_then(|lib| lib.path)
+        .unwrap_or_else(|| "src/lib.rs".to_owned());
+    let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let Ok(lib_source) = std::fs::read_to_string(manifest_dir.join(lib_path)) else {
+        return Ok(None);
+    };
+    if !is_no_std(&lib_source) {
+        return Ok(None);
+    }
+
+    Ok(Some(
+        default_features
+            .into_iter()
+            .filter(|feature| feature != STD_FEATURE)
+            .collect(),
+    ))
+}
+
+/// Returns `true` if the crate root has a `#![no_std]` attribute, including
+/// e.g. `#![cfg_attr(not(feature = "std"), no_std)]`.
+fn is_no_std(lib_source: &str) -> bool {
+    lib_source
+        .lines()
+        .map(str::trim_start)
+        .any(|line| line.starts_with("#![") && line.contains("no_std"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_no_std() {
+        assert!(is_no_std("#![no_std]\npub fn f() {}"));
+        assert!(is_no_std(
+            "//! Docs\n#![cfg_attr(not(feature = \"std\"), no_std)]"
+        ));
+        assert!(!is_no_std("pub fn f() {}"));
+        assert!(!is_no_std("// Could be #![no_std] some day"));
+    }
+}
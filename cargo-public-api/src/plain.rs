@@ -1,9 +1,14 @@
 use std::io::{Result, Write};
 
 use nu_ansi_term::{AnsiString, AnsiStrings, Color, Style};
-use public_api::{diff::PublicApiDiff, tokens::Token, PublicItem};
+use public_api::{
+    diff::{PublicApiDiff, TokenDiff},
+    tokens::Token,
+    PublicItem,
+};
 
-use crate::Args;
+use crate::truncate;
+use crate::{arg_types::Theme, docs_rs, Args};
 
 pub struct Plain;
 
@@ -14,27 +19,47 @@ impl Plain {
         items: impl Iterator<Item = &'a PublicItem>,
     ) -> Result<()> {
         for item in items {
-            print_item(args, w, item)?;
+            Self::print_item(w, args, item)?;
         }
 
         Ok(())
     }
 
+    pub fn print_item(w: &mut dyn Write, args: &Args, item: &PublicItem) -> Result<()> {
+        if args.color.active() {
+            writeln!(w, "{}", color_item(item, args.theme))
+        } else {
+            writeln!(w, "{item}")
+        }
+    }
+
     pub fn print_diff(w: &mut dyn Write, args: &Args, diff: &PublicApiDiff) -> Result<()> {
+        // Only the items themselves are truncated, since a truncated docs.rs
+        // link would be useless. One column is left for the leading `-` or `+`
+        let max_width = truncate::max_width(args.width);
+        let fit = |item: String| match max_width {
+            Some(width) => truncate::truncate_line(&item, width.saturating_sub(1).max(1)),
+            None => item,
+        };
+
         let use_color = args.color.active();
+        let theme = args.theme;
+        let render = |item: &PublicItem| {
+            fit(if use_color {
+                color_item(item, theme)
+            } else {
+                item.to_string()
+            })
+        };
+        let old_link = |item: &PublicItem| docs_rs_link(args.docs_rs.old.as_deref(), item);
+        let new_link = |item: &PublicItem| docs_rs_link(args.docs_rs.new.as_deref(), item);
 
         print_items_with_header(
             w,
             "Removed items from the public API\n\
              =================================",
             &diff.removed,
-            |w, item| {
-                if use_color {
-                    writeln!(w, "-{}", color_item(item))
-                } else {
-                    writeln!(w, "-{}", item)
-                }
-            },
+            |w, item| writeln!(w, "-{}{}", render(item), old_link(item)),
         )?;
 
         print_items_with_header(
@@ -43,105 +68,181 @@ impl Plain {
              ===============================",
             &diff.changed,
             |w, changed_item| {
-                if use_color {
-                    let old_tokens: Vec<&Token> = changed_item.old.tokens().collect();
-                    let new_tokens: Vec<&Token> = changed_item.new.tokens().collect();
-                    let diff_slice = diff::slice(old_tokens.as_slice(), new_tokens.as_slice());
-                    writeln!(
-                        w,
-                        "-{}\n+{}",
-                        color_item_with_diff(&diff_slice, true),
-                        color_item_with_diff(&diff_slice, false),
+                let (old, new) = if use_color {
+                    let token_diff = changed_item.token_diff();
+                    (
+                        fit(color_item_with_diff(&token_diff, true, theme)),
+                        fit(color_item_with_diff(&token_diff, false, theme)),
                     )
                 } else {
-                    writeln!(w, "-{}\n+{}", changed_item.old, changed_item.new)
-                }
+                    (render(&changed_item.old), render(&changed_item.new))
+                };
+                writeln!(
+                    w,
+                    "-{}{}\n+{}{}",
+                    old,
+                    old_link(&changed_item.old),
+                    new,
+                    new_link(&changed_item.new),
+                )
             },
         )?;
 
-        print_items_with_header(
-            w,
-            "Added items to the public API\n\
-             =============================",
-            &diff.added,
-            |w, item| {
-                if use_color {
-                    writeln!(w, "+{}", color_item(item))
-                } else {
-                    writeln!(w, "+{}", item)
-                }
-            },
-        )?;
+        // Moves are rare, so only mention them when there are any
+        if !diff.moved.is_empty() {
+            print_items_with_header(
+                w,
+                "Moved items in the public API\n\
+                 =============================",
+                &diff.moved,
+                |w, (old, new)| {
+                    writeln!(
+                        w,
+                        "-{}{}\n+{}{}",
+                        render(old),
+                        old_link(old),
+                        render(new),
+                        new_link(new)
+                    )
+                },
+            )?;
+        }
+
+        if !args.only_breaking {
+            print_items_with_header(
+                w,
+                "Added items to the public API\n\
+                 =============================",
+                &diff.added,
+                |w, item| writeln!(w, "+{}{}", render(item), new_link(item)),
+            )?;
+        }
 
         Ok(())
     }
 }
 
-fn print_item(args: &Args, w: &mut dyn Write, item: &PublicItem) -> Result<()> {
-    if args.color.active() {
-        writeln!(w, "{}", color_item(item))
-    } else {
-        writeln!(w, "{}", item)
-    }
+/// Returns the docs.rs URL of `item`, with a leading space, if `base_url` is
+/// set. Otherwise returns an empty string.
+fn docs_rs_link(base_url: Option<&str>, item: &PublicItem) -> String {
+    base_url
+        .and_then(|base_url| docs_rs::url(base_url, item))
+        .map(|url| format!(" {url}"))
+        .unwrap_or_default()
 }
 
-fn color_item(item: &public_api::PublicItem) -> String {
-    color_token_stream(item.tokens(), None)
+fn color_item(item: &public_api::PublicItem, theme: Theme) -> String {
+    color_token_stream(item.tokens(), None, theme)
 }
 
-fn color_token_stream<'a>(tokens: impl Iterator<Item = &'a Token>, bg: Option<Color>) -> String {
-    let styled = tokens.map(|t| color_item_token(t, bg)).collect::<Vec<_>>();
+fn color_token_stream<'a>(
+    tokens: impl Iterator<Item = &'a Token>,
+    bg: Option<Color>,
+    theme: Theme,
+) -> String {
+    let styled = tokens
+        .map(|t| color_item_token(t, bg, theme))
+        .collect::<Vec<_>>();
     AnsiStrings(&styled).to_string()
 }
 
-/// Color the given Token to render it with a nice syntax highlighting. The
-/// theme is inspired by dark+ in VS Code and uses the default colors from the
-/// terminal to always provide a readable and consistent color scheme.
-/// An extra color can be provided to be used as background color.
-fn color_item_token(token: &Token, bg: Option<Color>) -> AnsiString<'_> {
-    let style = |colour: Style, text: &str| {
-        bg.map_or_else(
-            || colour.paint(text.to_string()),
-            |bg| colour.on(bg).paint(text.to_string()),
-        )
-    };
+/// Color the given Token according to `theme` to render it with syntax
+/// highlighting. An extra color can be provided to be used as background
+/// color.
+fn color_item_token(token: &Token, bg: Option<Color>, theme: Theme) -> AnsiString<'_> {
+    let style = token_style(token, theme);
+    let style = bg.map_or(style, |bg| style.on(bg));
+    style.paint(token.text())
+}
+
+fn token_style(token: &Token, theme: Theme) -> Style {
+    match theme {
+        Theme::Default => default_token_style(token),
+        Theme::HighContrast => high_contrast_token_style(token),
+        Theme::Monochrome => monochrome_token_style(token),
+    }
+}
+
+/// The default theme is inspired by dark+ in VS Code and uses the default
+/// colors from the terminal to always provide a readable and consistent color
+/// scheme.
+fn default_token_style(token: &Token) -> Style {
+    #[allow(clippy::match_same_arms)]
+    match token {
+        Token::Symbol(_) => Style::default(),
+        Token::Qualifier(_) => Color::Blue.into(),
+        Token::Kind(_) => Color::Blue.into(),
+        Token::Whitespace => Style::default(),
+        Token::Identifier(_) => Color::Cyan.into(),
+        Token::Annotation(_) => Style::default(),
+        Token::Self_(_) => Color::Blue.into(),
+        Token::Function(_) => Color::Yellow.into(),
+        Token::Lifetime(_) => Color::Blue.into(),
+        Token::Keyword(_) => Color::Blue.into(),
+        Token::Generic(_) => Color::Green.into(),
+        Token::Primitive(_) => Color::Green.into(),
+        Token::Type(_) => Color::Green.into(),
+    }
+}
+
+/// Bright and bold colors that stand out on both dark and light backgrounds.
+/// Avoids telling tokens apart by red versus green only.
+fn high_contrast_token_style(token: &Token) -> Style {
     #[allow(clippy::match_same_arms)]
     match token {
-        Token::Symbol(text) => style(Style::default(), text),
-        Token::Qualifier(text) => style(Color::Blue.into(), text),
-        Token::Kind(text) => style(Color::Blue.into(), text),
-        Token::Whitespace => style(Style::default(), " "),
-        Token::Identifier(text) => style(Color::Cyan.into(), text),
-        Token::Annotation(text) => style(Style::default(), text),
-        Token::Self_(text) => style(Color::Blue.into(), text),
-        Token::Function(text) => style(Color::Yellow.into(), text),
-        Token::Lifetime(text) => style(Color::Blue.into(), text),
-        Token::Keyword(text) => style(Color::Blue.into(), text),
-        Token::Generic(text) => style(Color::Green.into(), text),
-        Token::Primitive(text) => style(Color::Green.into(), text),
-        Token::Type(text) => style(Color::Green.into(), text),
+        Token::Symbol(_) => Style::default(),
+        Token::Qualifier(_) => Color::LightBlue.bold(),
+        Token::Kind(_) => Color::LightBlue.bold(),
+        Token::Whitespace => Style::default(),
+        Token::Identifier(_) => Color::LightCyan.into(),
+        Token::Annotation(_) => Color::LightGray.into(),
+        Token::Self_(_) => Color::LightBlue.bold(),
+        Token::Function(_) => Color::LightYellow.bold(),
+        Token::Lifetime(_) => Color::LightBlue.into(),
+        Token::Keyword(_) => Color::LightBlue.bold(),
+        Token::Generic(_) => Color::LightMagenta.into(),
+        Token::Primitive(_) => Color::LightMagenta.into(),
+        Token::Type(_) => Color::LightMagenta.bold(),
+    }
+}
+
+/// No colors at all, only bold and italic text.
+fn monochrome_token_style(token: &Token) -> Style {
+    match token {
+        Token::Qualifier(_) | Token::Kind(_) | Token::Self_(_) | Token::Keyword(_) => {
+            Style::new().bold()
+        }
+        Token::Function(_) | Token::Type(_) => Style::new().italic(),
+        _ => Style::default(),
+    }
+}
+
+/// The style of tokens that were removed (`is_old_item`) or added in a
+/// changed item.
+fn changed_token_style(theme: Theme, is_old_item: bool) -> Style {
+    match (theme, is_old_item) {
+        (Theme::Default, true) => Color::Fixed(9).on(Color::Fixed(52)).bold(),
+        (Theme::Default, false) => Color::Fixed(10).on(Color::Fixed(22)).bold(),
+        (Theme::HighContrast, true) => Color::White.on(Color::Red).bold(),
+        (Theme::HighContrast, false) => Color::Black.on(Color::LightCyan).bold(),
+        (Theme::Monochrome, true) => Style::new().strikethrough(),
+        (Theme::Monochrome, false) => Style::new().reverse(),
     }
 }
 
 /// Returns a styled string similar to `color_item_token`, but where whole tokens are highlighted if
 /// they contain a difference.
-fn color_item_with_diff(diff_slice: &[diff::Result<&&Token>], is_old_item: bool) -> String {
-    let styled_strings = diff_slice
+fn color_item_with_diff(token_diff: &[TokenDiff], is_old_item: bool, theme: Theme) -> String {
+    let styled_strings = token_diff
         .iter()
-        .filter_map(|diff_result| match diff_result {
-            diff::Result::Left(&token) => is_old_item.then(|| {
-                Color::Fixed(9)
-                    .on(Color::Fixed(52))
-                    .bold()
-                    .paint(token.text())
-            }),
-            diff::Result::Both(&token, _) => Some(color_item_token(token, None)),
-            diff::Result::Right(&token) => (!is_old_item).then(|| {
-                Color::Fixed(10)
-                    .on(Color::Fixed(22))
-                    .bold()
-                    .paint(token.text())
-            }),
+        .filter_map(|token_diff| match token_diff {
+            TokenDiff::Removed(token) => {
+                is_old_item.then(|| changed_token_style(theme, true).paint(token.text()))
+            }
+            TokenDiff::Unchanged(token) => Some(color_item_token(token, None, theme)),
+            TokenDiff::Added(token) => {
+                (!is_old_item).then(|| changed_token_style(theme, false).paint(token.text()))
+            }
         })
         .collect::<Vec<_>>();
 
@@ -0,0 +1,94 @@
+//! Shows a spinner together with the current `cargo` status line (e.g.
+//! "Documenting foo v0.1.0") while rustdoc JSON is being built, so that long
+//! builds of large crates do not look like they hang.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Clears the current terminal line and moves the cursor to its start.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// The words that `cargo` starts its status lines with. Other lines, such as
+/// warnings, are passed through as-is.
+const CARGO_STATUS_WORDS: [&str; 11] = [
+    "Adding",
+    "Blocking",
+    "Checking",
+    "Compiling",
+    "Documenting",
+    "Downloaded",
+    "Downloading",
+    "Finished",
+    "Fresh",
+    "Locking",
+    "Updating",
+];
+
+pub struct Spinner<W> {
+    out: W,
+    frame: usize,
+}
+
+impl<W: Write> Spinner<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, frame: 0 }
+    }
+
+    /// Handles a line that `cargo` wrote to stderr. Status lines replace the
+    /// current spinner line, other lines are printed above it.
+    pub fn line(&mut self, line: &str) -> std::io::Result<()> {
+        let status = line.trim_start();
+        if is_cargo_status(status) {
+            self.frame = (self.frame + 1) % FRAMES.len();
+            write!(self.out, "{CLEAR_LINE}{} {status}", FRAMES[self.frame])?;
+        } else {
+            writeln!(self.out, "{CLEAR_LINE}{line}")?;
+        }
+        self.out.flush()
+    }
+
+    /// Removes the spinner line. Call once the build is done.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        write!(self.out, "{CLEAR_LINE}")?;
+        self.out.flush()
+    }
+}
+
+/// Creates a spinner that writes to stderr, but only if stderr is a terminal
+/// and we are not to be `quiet`. If stderr is e.g. piped to a file we want the
+/// regular `cargo` output.
+pub fn spinner_for_stderr(quiet: bool) -> Option<Arc<Mutex<Spinner<std::io::Stderr>>>> {
+    (!quiet && atty::is(atty::Stream::Stderr))
+        .then(|| Arc::new(Mutex::new(Spinner::new(std::io::stderr()))))
+}
+
+fn is_cargo_status(line: &str) -> bool {
+    matches!(line.split_once(' '), Some((word, _)) if CARGO_STATUS_WORDS.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spinner_shows_status_lines() {
+        let mut out = vec![];
+        let mut spinner = Spinner::new(&mut out);
+        spinner.line("   Compiling foo v0.1.0").unwrap();
+        spinner.line(" Documenting bar v0.2.0").unwrap();
+        spinner.line("warning: unused import").unwrap();
+        spinner.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "{CLEAR_LINE}⠙ Compiling foo v0.1.0\
+                 {CLEAR_LINE}⠹ Documenting bar v0.2.0\
+                 {CLEAR_LINE}warning: unused import\n\
+                 {CLEAR_LINE}"
+            )
+        );
+    }
+}
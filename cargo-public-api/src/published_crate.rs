@@ -1,16 +1,28 @@
 //! Creates a dummy project with a dependency on the crate we want to build
 //! rustdoc JSON for. We then build rustdoc JSON for the crate using this dummy
 //! project.
+//!
+//! The dummy project is built in isolation from the project of the user, so
+//! that `[patch]` sections of the user do not affect what version of the crate
+//! we build. Otherwise we could end up diffing against a patched crate rather
+//! than the published one.
 
 use crate::Args;
-use anyhow::{anyhow, Result};
-use std::{fmt::Display, path::PathBuf};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 pub fn build_rustdoc_json(package_spec_str: &str, args: &Args) -> Result<PathBuf> {
     let fallback_name = package_name_from_args(args);
     let spec = PackageSpec::from_str_with_fallback(package_spec_str, fallback_name.as_deref())?;
 
-    let build_dir = build_dir(args, &spec);
+    // Must be absolute since we run the build in this dir
+    let build_dir = std::env::current_dir()?.join(build_dir(args, &spec));
     std::fs::create_dir_all(&build_dir)?;
 
     let write_file = |name: &str, contents: &str| -> std::io::Result<PathBuf> {
@@ -21,17 +33,130 @@ pub fn build_rustdoc_json(package_spec_str: &str, args: &Args) -> Result<PathBuf
     };
 
     write_file("lib.rs", "// empty lib")?;
-    let manifest = write_file("Cargo.toml", &manifest_for(&spec))?;
+    let manifest = write_file("Cargo.toml", &manifest_for(&spec, args.registry.as_deref()))?;
 
-    // Since we used `crate::builder_from_args(args)` above it means that if
-    // `args.target_dir` is set, both the dummy crate and the real crate will
-    // write to the same JSON path since they have the same project name! That
-    // won't work. So always clear the target dir before we use the builder.
-    let builder = crate::builder_from_args(args)
+    // `crate::builder_from_args(args)` sets the target dir to
+    // `args.target_dir` if it is set. Then the published crate would write its
+    // rustdoc JSON to the same path as the current version of the crate, since
+    // they have the same name, and the diff would compare a crate with itself.
+    // So clear the target dir, which makes the build use the target dir of the
+    // dummy project in the build dir instead.
+    //
+    // We build from within the build dir so that `[patch]` sections in
+    // `.cargo/config.toml` files of the user's project do not apply.
+    let builder = Features::for_published(args)
+        .apply(crate::builder_from_args(args))
         .clear_target_dir()
         .manifest_path(&manifest)
+        .current_dir(&build_dir)
         .package(&spec.name);
-    crate::build_rustdoc_json(builder)
+
+    // A published version never changes, so there is no need to build it
+    // again if we built it the same way before
+    let cached_json = cached_json_path(args, &spec, &builder);
+    if cached_json.is_file() && !args.refresh_baseline {
+        eprintln!(
+            "Note: reusing the rustdoc JSON for {spec} cached at {}. Use `--refresh-baseline` to build it again",
+            cached_json.display()
+        );
+        return Ok(cached_json);
+    }
+
+    let json_path = crate::build_rustdoc_json(args, builder)?;
+
+    std::fs::create_dir_all(cached_json.parent().unwrap())?;
+    std::fs::copy(&json_path, &cached_json)
+        .with_context(|| format!("Failed to cache rustdoc JSON at {}", cached_json.display()))?;
+    Ok(cached_json)
+}
+
+/// The docs.rs URL of the docs of the published crate, e.g.
+/// `https://docs.rs/example_api/0.1.0/`.
+pub fn docs_rs_url(package_spec_str: &str, args: &Args) -> Result<String> {
+    let fallback_name = package_name_from_args(args);
+    let spec = PackageSpec::from_str_with_fallback(package_spec_str, fallback_name.as_deref())?;
+    Ok(format!("https://docs.rs/{}/{}/", spec.name, spec.version))
+}
+
+/// Like [`build_rustdoc_json`], but for a crate that is already on disk, so
+/// that no network access is needed. The `path` is either a `.crate` file, as
+/// downloaded from a registry, or a dir with the extracted source of one.
+pub fn build_rustdoc_json_for_baseline_path(path: &Path, args: &Args) -> Result<PathBuf> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Invalid baseline path {}", path.display()))?;
+
+    // Keyed on the whole path rather than e.g. its file name, since that is
+    // empty for `.`, and different dirs can have the same name
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let mut build_dir = std::env::current_dir()?.join(build_root(args));
+    build_dir.push("baseline-paths");
+    build_dir.push(format!("{:016x}", hasher.finish()));
+
+    let source_dir = if path.is_dir() {
+        path
+    } else {
+        extract_crate_file(&path, &build_dir.join("extracted"))?
+    };
+
+    // Like for published crates, build through a dummy project so that the
+    // crate is not treated as part of a workspace in a parent dir of it, such
+    // as the project of the user
+    let package_name = cargo_manifest::Manifest::from_path(source_dir.join("Cargo.toml"))
+        .with_context(|| format!("Failed to read manifest in {}", source_dir.display()))?
+        .package
+        .map(|package| package.name)
+        .ok_or_else(|| anyhow!("No package in {}", source_dir.display()))?;
+
+    // Not in `build_dir` itself, since a path dependency in the dir of the
+    // dummy project would become a member of its workspace
+    let dummy_dir = build_dir.join("dummy");
+    std::fs::create_dir_all(&dummy_dir)?;
+    std::fs::write(dummy_dir.join("lib.rs"), "// empty lib")?;
+    std::fs::write(
+        dummy_dir.join("Cargo.toml"),
+        dummy_manifest(&path_dependency(&package_name, &source_dir)),
+    )?;
+
+    // Resolve dependencies the same way as the crate does by itself, so that
+    // no network access is needed if they are already downloaded
+    let lock_file = source_dir.join("Cargo.lock");
+    if lock_file.is_file() {
+        std::fs::copy(&lock_file, dummy_dir.join("Cargo.lock"))?;
+    }
+
+    let builder = Features::for_published(args)
+        .apply(crate::builder_from_args(args))
+        .target_dir(build_dir.join("target"))
+        .manifest_path(dummy_dir.join("Cargo.toml"))
+        .current_dir(&dummy_dir)
+        .package(&package_name);
+    crate::build_rustdoc_json(args, builder)
+}
+
+/// A `.crate` file is a gzipped tarball with a single top-level dir, named
+/// e.g. `regex-1.7.0`. Returns the path to that dir once extracted to `dest`.
+fn extract_crate_file(crate_file: &Path, dest: &Path) -> Result<PathBuf> {
+    let file = std::fs::File::open(crate_file)
+        .with_context(|| format!("Failed to open {}", crate_file.display()))?;
+
+    // Start from scratch in case the file has changed since last time
+    if dest.exists() {
+        std::fs::remove_dir_all(dest)?;
+    }
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(dest)
+        .with_context(|| format!("Failed to extract {}", crate_file.display()))?;
+
+    let mut entries = std::fs::read_dir(dest)?.collect::<std::io::Result<Vec<_>>>()?;
+    match (entries.pop(), entries.is_empty()) {
+        (Some(entry), true) if entry.path().is_dir() => Ok(entry.path()),
+        _ => Err(anyhow!(
+            "Expected a single top-level dir in {}",
+            crate_file.display()
+        )),
+    }
 }
 
 /// When diffing against a published crate, we want to allow the user to not
@@ -52,21 +177,102 @@ fn package_name_from_args(args: &Args) -> Option<String> {
 /// incremental. But when tests run, they will set `args.target_dir` to a
 /// temporary dir so that tests can run in parallel without interference.
 fn build_dir(args: &Args, spec: &PackageSpec) -> PathBuf {
-    let mut build_dir = if let Some(target_dir) = &args.target_dir {
+    let mut build_dir = build_root(args);
+    build_dir.push(spec.as_dir_name());
+    build_dir
+}
+
+/// Where the rustdoc JSON of a published crate is cached. Everything that
+/// affects what the rustdoc JSON looks like is part of the path, so that e.g.
+/// building with other features does not reuse the cached rustdoc JSON. That
+/// is the `cargo rustdoc` command that `builder` runs, the registry, and the
+/// version of the toolchain, since e.g. `nightly` is a different toolchain
+/// from one day to the next. The hash is only used to get a short file name,
+/// so it is fine if it changes between Rust versions, that just means we build
+/// again.
+fn cached_json_path(args: &Args, spec: &PackageSpec, builder: &rustdoc_json::Builder) -> PathBuf {
+    let command = builder.command();
+    let mut hasher = DefaultHasher::new();
+    command.get_program().hash(&mut hasher);
+    command
+        .get_args()
+        // These only affect what `cargo` prints
+        .filter(|arg| *arg != "--quiet" && *arg != "--verbose")
+        .for_each(|arg| arg.hash(&mut hasher));
+    command.get_envs().for_each(|env| env.hash(&mut hasher));
+    args.registry.hash(&mut hasher);
+    cargo_version(&command).hash(&mut hasher);
+
+    let mut path = build_root(args);
+    path.push("cached-rustdoc-json");
+    path.push(spec.as_dir_name());
+    path.push(format!("{:016x}.json", hasher.finish()));
+    path
+}
+
+/// The output of `cargo --version` of the toolchain that `cargo_rustdoc` runs,
+/// e.g. `cargo 1.70.0-nightly (9e2fe2a 2023-03-14)`. `None` if it could not be
+/// found out, in which case the build itself will fail with a better error.
+fn cargo_version(cargo_rustdoc: &Command) -> Option<String> {
+    // The args before `--config` and `rustdoc` select the toolchain, as in
+    // `rustup run nightly cargo rustdoc`
+    let mut command = Command::new(cargo_rustdoc.get_program());
+    command.args(
+        cargo_rustdoc
+            .get_args()
+            .take_while(|arg| *arg != "--config" && *arg != "rustdoc"),
+    );
+    if let Some(current_dir) = cargo_rustdoc.get_current_dir() {
+        command.current_dir(current_dir);
+    }
+    let output = command.arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn build_root(args: &Args) -> PathBuf {
+    let mut build_root = if let Some(target_dir) = &args.target_dir {
         target_dir.clone()
     } else {
         dirs::cache_dir().unwrap_or_else(std::env::temp_dir)
     };
 
-    build_dir.push("cargo-public-api");
-    build_dir.push("build-root-for-published-crates");
-    build_dir.push(spec.as_dir_name());
-    build_dir
+    build_root.push("cargo-public-api");
+    build_root.push("build-root-for-published-crates");
+    build_root
 }
 
-fn manifest_for(spec: &PackageSpec) -> String {
+/// If `registry` is set, the crate is fetched from that alternative registry
+/// instead of crates.io.
+fn manifest_for(spec: &PackageSpec, registry: Option<&str>) -> String {
+    let dependency = match registry {
+        Some(registry) => format!(
+            "{} = {{ version = \"={}\", registry = \"{registry}\" }}",
+            spec.name, spec.version
+        ),
+        None => format!("{} = \"={}\"", spec.name, spec.version),
+    };
+    dummy_manifest(&dependency)
+}
+
+/// A `[dependencies]` line for the crate in `dir`. The path is written as a
+/// TOML basic string, in which only `\\` and `"` need escaping.
+fn path_dependency(name: &str, dir: &Path) -> String {
+    let dir = dir.display().to_string();
+    let dir = dir.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{name} = {{ path = \"{dir}\" }}")
+}
+
+/// The manifest of a dummy project with the given line in its
+/// `[dependencies]`. The empty `[workspace]` makes the dummy project its own
+/// workspace root, so that it never becomes part of (and gets patched by) a
+/// workspace that happens to be in a parent dir of the build dir.
+fn dummy_manifest(dependency: &str) -> String {
     format!(
         "\
+        [workspace]\n\
         [package]\n\
         name = \"crate-downloader\"\n\
         version = \"0.1.0\"\n\
@@ -74,12 +280,52 @@ fn manifest_for(spec: &PackageSpec) -> String {
         [lib]\n\
         path = \"lib.rs\"\n\
         [dependencies]\n\
-        {} = \"={}\"\n
-        ",
-        spec.name, spec.version
+        {dependency}\n
+        "
     )
 }
 
+/// The features to build published versions with. The same as for the
+/// current version, unless `--baseline-features` or similar is used.
+#[derive(Debug)]
+pub struct Features {
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+}
+
+impl Features {
+    /// Whether any of the `--baseline-*` feature args were given.
+    pub fn has_baseline_args(args: &Args) -> bool {
+        !args.baseline_features.is_empty()
+            || args.baseline_all_features
+            || args.baseline_no_default_features
+    }
+
+    fn for_published(args: &Args) -> Self {
+        if Self::has_baseline_args(args) {
+            Self {
+                features: args.baseline_features.clone(),
+                all_features: args.baseline_all_features,
+                no_default_features: args.baseline_no_default_features,
+            }
+        } else {
+            Self {
+                features: args.features.clone(),
+                all_features: args.all_features,
+                no_default_features: args.no_default_features,
+            }
+        }
+    }
+
+    fn apply(&self, builder: rustdoc_json::Builder) -> rustdoc_json::Builder {
+        builder
+            .features(&self.features)
+            .all_features(self.all_features)
+            .no_default_features(self.no_default_features)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct PackageSpec {
     name: String,
@@ -120,6 +366,35 @@ impl Display for PackageSpec {
 mod tests {
     use super::*;
 
+    #[test]
+    fn manifest_is_own_workspace_root() {
+        let spec = PackageSpec {
+            name: String::from("foo"),
+            version: String::from("1.0.0"),
+        };
+        let manifest = manifest_for(&spec, None);
+        assert!(manifest.starts_with("[workspace]\n"));
+        assert!(manifest.contains("foo = \"=1.0.0\""));
+    }
+
+    #[test]
+    fn manifest_with_registry() {
+        let spec = PackageSpec {
+            name: String::from("foo"),
+            version: String::from("1.0.0"),
+        };
+        let manifest = manifest_for(&spec, Some("private"));
+        assert!(manifest.contains("foo = { version = \"=1.0.0\", registry = \"private\" }"));
+    }
+
+    #[test]
+    fn path_dependency_escapes_path() {
+        assert_eq!(
+            path_dependency("foo", Path::new(r#"C:\Users\me\"quoted" ünïcode"#)),
+            r#"foo = { path = "C:\\Users\\me\\\"quoted\" ünïcode" }"#
+        );
+    }
+
     #[test]
     fn test_parse_spec() {
         assert!(PackageSpec::from_str_with_fallback("", None).is_err());
@@ -0,0 +1,104 @@
+//! Truncation of long lines, so that long item signatures do not wrap around
+//! in narrow terminals and make a diff hard to read.
+
+/// What to end a truncated line with.
+const ELLIPSIS: char = '…';
+
+/// Returns how many columns wide lines may be: `--width` if given, otherwise
+/// the width of the terminal if stdout is a terminal. `None` means that lines
+/// shall not be truncated, which `--width 0` asks for explicitly.
+pub fn max_width(width_arg: Option<usize>) -> Option<usize> {
+    match width_arg {
+        Some(width) => (width > 0).then_some(width),
+        None => {
+            let (terminal_size::Width(width), _) = terminal_size::terminal_size()?;
+            Some(usize::from(width))
+        }
+    }
+}
+
+/// Truncates `line` to `width` visible columns, with the last visible column
+/// being `…`. Lines that fit are returned as is. If the line contains ANSI
+/// escape codes, the truncated line ends with a reset, so that styles do not
+/// leak into the next line.
+pub fn truncate_line(line: &str, width: usize) -> String {
+    let mut truncated = String::with_capacity(line.len());
+    let mut visible = 0;
+    let mut has_escape_codes = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Copy the escape code, e.g. `\x1b[1;94m`, up to and including
+            // its final letter
+            has_escape_codes = true;
+            truncated.push(c);
+            for c in chars.by_ref() {
+                truncated.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        // Only truncate if there are more visible chars than fit
+        let is_last_that_fits = visible + 1 == width;
+        if is_last_that_fits && has_more_visible_chars(chars.clone()) {
+            truncated.push(ELLIPSIS);
+            if has_escape_codes {
+                truncated.push_str("\u{1b}[0m");
+            }
+            return truncated;
+        }
+        truncated.push(c);
+        visible += 1;
+    }
+    truncated
+}
+
+/// Returns `true` if there are chars left that are not part of ANSI escape
+/// codes.
+fn has_more_visible_chars(mut chars: impl Iterator<Item = char>) -> bool {
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            return true;
+        }
+        for c in chars.by_ref() {
+            if c.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_lines_are_untouched() {
+        assert_eq!(truncate_line("pub fn f()", 10), "pub fn f()");
+        assert_eq!(truncate_line("", 10), "");
+    }
+
+    #[test]
+    fn long_lines_are_truncated() {
+        assert_eq!(truncate_line("pub fn f(x: usize)", 10), "pub fn f(…");
+    }
+
+    #[test]
+    fn escape_codes_do_not_count() {
+        assert_eq!(
+            truncate_line("\u{1b}[34mpub\u{1b}[0m fn f()", 10),
+            "\u{1b}[34mpub\u{1b}[0m fn f()"
+        );
+        assert_eq!(
+            truncate_line(
+                "\u{1b}[34mpub\u{1b}[0m fn \u{1b}[33mfunction\u{1b}[0m()",
+                10
+            ),
+            "\u{1b}[34mpub\u{1b}[0m fn \u{1b}[33mfu…\u{1b}[0m"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Implementation of `--watch`, i.e. listing or diffing again whenever the
+//! source files of the crate change.
+
+use std::io::{stdout, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+/// Editors often write a file in several steps, and `git checkout` changes
+/// many files at once. Wait until things have been quiet for this long before
+/// we run again, so that we run once per change rather than once per event.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Returns the source files and dirs of the packages of the workspaces with
+/// the given manifests, i.e. the `Cargo.toml` and `src` dir of each package.
+/// Not the whole dir of a package, since that typically contains the `target`
+/// dir that we build into.
+pub fn source_paths(manifest_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for manifest_path in manifest_paths {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(manifest_path)
+            .no_deps()
+            .exec()
+            .with_context(|| format!("Failed to get the packages of {manifest_path:?}"))?;
+
+        // The workspace root might be a virtual manifest without a package
+        paths.push(metadata.workspace_root.as_std_path().join("Cargo.toml"));
+        // With `no_deps()`, these are only the packages of the workspace
+        for package in &metadata.packages {
+            let manifest_path = package.manifest_path.as_std_path();
+            let src = manifest_path.with_file_name("src");
+            if src.is_dir() {
+                paths.push(src);
+            }
+            paths.push(manifest_path.to_owned());
+        }
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Calls `run` once, and then again every time one of `paths` changes, until
+/// the process is killed. Dirs are watched recursively. The screen is cleared
+/// before each run if stdout is a terminal.
+pub fn watch(paths: &[PathBuf], mut run: impl FnMut()) -> Result<()> {
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        // The receiver only goes away when we exit, so ignore errors
+        let _ = sender.send(event);
+    })?;
+    for path in paths {
+        let mode = if path.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode)?;
+    }
+    let watched = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    loop {
+        clear_screen()?;
+        run();
+
+        // Changes made by the run itself, like build output and `git
+        // checkout`s, must not trigger another run
+        while receiver.try_recv().is_ok() {}
+        eprintln!("Watching {watched} for changes...");
+
+        wait_for_change(&receiver)?;
+    }
+}
+
+/// Blocks until a file has changed and no more changes have happened for
+/// [`DEBOUNCE`].
+fn wait_for_change(receiver: &Receiver<notify::Result<Event>>) -> Result<()> {
+    loop {
+        if is_change(&receiver.recv()??) {
+            break;
+        }
+    }
+
+    loop {
+        match receiver.recv_timeout(DEBOUNCE) {
+            Ok(event) => {
+                event?;
+            }
+            Err(RecvTimeoutError::Timeout) => return Ok(()),
+            Err(err @ RecvTimeoutError::Disconnected) => return Err(err.into()),
+        }
+    }
+}
+
+/// Building the crate reads the source files, so events that only say that a
+/// file was accessed must be ignored.
+fn is_change(event: &Event) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+}
+
+fn clear_screen() -> Result<()> {
+    if atty::is(atty::Stream::Stdout) {
+        print!("\x1B[2J\x1B[1;1H");
+        stdout().flush()?;
+    }
+    Ok(())
+}
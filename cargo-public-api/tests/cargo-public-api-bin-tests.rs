@@ -15,6 +15,7 @@ use std::{
 
 use assert_cmd::assert::Assert;
 use assert_cmd::Command;
+use predicates::boolean::PredicateBooleanExt;
 use predicates::str::contains;
 
 // rust-analyzer bug: https://github.com/rust-lang/rust-analyzer/issues/9173
@@ -24,8 +25,8 @@ use tempfile::tempdir;
 use test_utils::assert_or_bless::AssertOrBless;
 use test_utils::rustdoc_json_path_for_crate;
 
-#[path = "../src/git_utils.rs"] // Say NO to copy-paste!
-mod git_utils;
+use cargo_public_api::git_utils;
+use cargo_public_api::json_report::{self, DiffReport};
 
 fn create_test_repo_with_dirty_git_tree() -> TestRepo {
     let test_repo = TestRepo::new();
@@ -58,6 +59,37 @@ fn list_public_items() {
         .success();
 }
 
+/// The progress spinner must only be shown if stderr is a terminal, so when
+/// stderr is piped we expect the regular `cargo` output.
+#[test]
+fn no_progress_spinner_when_piped() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.3.0/Cargo.toml",
+    ]);
+    cmd.assert()
+        .stderr(contains("Documenting example_api"))
+        .stderr(contains('⠙').not())
+        .success();
+}
+
+/// With `--quiet`, neither the progress spinner nor the `cargo` output is
+/// shown.
+#[test]
+fn quiet_hides_build_progress() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.3.0/Cargo.toml",
+    ]);
+    cmd.arg("--quiet");
+    cmd.assert()
+        .stderr(contains("Documenting example_api").not())
+        .stderr(contains('⠙').not())
+        .success();
+}
+
 #[test]
 fn list_public_items_with_lint_error() {
     let mut cmd = TestCmd::new().with_separate_target_dir();
@@ -67,6 +99,179 @@ fn list_public_items_with_lint_error() {
         .success();
 }
 
+#[test]
+fn list_public_items_of_manifest_path_glob() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.[12].0/Cargo.toml",
+    ]);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/manifest_path_glob.txt")
+        .success();
+}
+
+#[test]
+fn diff_manifest_path_glob_output_format_json() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.[12].0/Cargo.toml",
+        "--output-format=json",
+        "--diff-rustdoc-json",
+    ]);
+    cmd.arg(old);
+    cmd.arg(new);
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    // One report per manifest, without any `==> path <==` headers in between
+    let reports = serde_json::Deserializer::from_slice(&output)
+        .into_iter::<DiffReport>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(reports.len(), 2);
+}
+
+#[test]
+fn manifest_path_glob_without_matches() {
+    let mut cmd = TestCmd::new();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/does-not-exist-*/Cargo.toml",
+    ]);
+    cmd.assert()
+        .stderr(contains(
+            "No manifest matches `--manifest-path ../test-apis/does-not-exist-*/Cargo.toml`",
+        ))
+        .failure();
+}
+
+#[test]
+fn list_public_items_of_bin_only_package() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["--manifest-path", "../test-apis/bin_only/Cargo.toml"]);
+    cmd.assert()
+        .stdout(contains("pub fn bin_only::public_function_in_bin()"))
+        .stderr(contains("listing the public API of binary `bin_only`"))
+        .success();
+}
+
+/// The binary is worked out once, so the note about it shall be printed once
+/// even if it is built several times.
+#[test]
+fn bin_only_note_printed_once() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/bin_only/Cargo.toml",
+        "--diff-targets",
+        "x86_64-unknown-linux-gnu",
+        "x86_64-pc-windows-msvc",
+    ]);
+    let assert = cmd.assert().success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert_eq!(
+        stderr
+            .matches("listing the public API of binary `bin_only`")
+            .count(),
+        1,
+        "{stderr}"
+    );
+}
+
+#[test]
+fn public_deps() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--public-deps",
+    ]);
+    cmd.assert().stdout("example_api\n").success();
+}
+
+#[test]
+fn public_deps_without_dependencies() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.3.0/Cargo.toml",
+        "--public-deps",
+    ]);
+    cmd.assert().stdout("").success();
+}
+
+#[test]
+fn public_deps_when_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--diff", "v0.2.0", "v0.3.0", "--public-deps"]);
+    cmd.assert()
+        .stderr(contains("`--public-deps` can only be used when listing"))
+        .failure();
+}
+
+#[test]
+fn list_public_items_of_proc_macro_crate() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--manifest-path");
+    cmd.arg("../test-apis/comprehensive_api_proc_macro/Cargo.toml");
+    cmd.assert()
+        .stdout(contains("pub proc macro comprehensive_api_proc_macro::simple_proc_macro!()"))
+        .stderr(contains(
+            "Note: `comprehensive_api_proc_macro` is a proc-macro crate, so only the macros it defines (if any) are listed",
+        ))
+        .success();
+}
+
+/// The proc-macro note shall be printed once even if the crate is built
+/// several times.
+#[test]
+fn proc_macro_note_printed_once() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api_proc_macro/Cargo.toml",
+        "--diff-targets",
+        "x86_64-unknown-linux-gnu",
+        "x86_64-pc-windows-msvc",
+    ]);
+    let assert = cmd.assert().success();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert_eq!(
+        stderr.matches("is a proc-macro crate").count(),
+        1,
+        "{stderr}"
+    );
+}
+
+#[test]
+fn list_public_items_of_explicit_bin() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["--manifest-path", "../test-apis/multiple_bins/Cargo.toml"]);
+    cmd.args(["--bin", "second"]);
+    cmd.assert()
+        .stdout(contains("pub fn second::second()"))
+        .success();
+}
+
+#[test]
+fn multiple_bins_without_lib_suggests_bin_arg() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["--manifest-path", "../test-apis/multiple_bins/Cargo.toml"]);
+    cmd.assert()
+        .stderr(contains(
+            "has no library target, only binary targets: first, second",
+        ))
+        .stderr(contains("cargo public-api --bin <NAME>"))
+        .failure();
+}
+
 #[test]
 fn custom_toolchain() {
     let mut cmd = TestCmd::new().with_test_repo();
@@ -77,6 +282,21 @@ fn custom_toolchain() {
         .success();
 }
 
+#[test]
+fn custom_toolchain_without_rustup() {
+    let empty_dir = tempdir().unwrap();
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.env("PATH", empty_dir.path());
+    cmd.arg("--toolchain");
+    cmd.arg("nightly");
+    cmd.assert()
+        .stderr(contains(
+            "The `nightly` toolchain was requested, but `rustup` could not be found",
+        ))
+        .stderr(contains("install rustup from https://rustup.rs"))
+        .failure();
+}
+
 #[test]
 fn list_public_items_explicit_manifest_path() {
     let test_repo = TestRepo::new();
@@ -91,19 +311,141 @@ fn list_public_items_explicit_manifest_path() {
         .success();
 }
 
+#[test]
+fn list_public_items_with_relative_paths() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--path-style", "relative"]);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api-v0.3.0_relative_paths.txt")
+        .success();
+}
+
+#[test]
+fn list_impls_for_type() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--list-impls-for", "example_api::Struct"]);
+    cmd.assert()
+        .stdout(
+            "impl core::fmt::Debug for example_api::Struct\n\
+             pub fn example_api::Struct::fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result\n",
+        )
+        .success();
+}
+
+#[test]
+fn list_impls_for_type_with_trait_impls() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--list-impls-for",
+        "comprehensive_api::structs::Unit",
+    ]);
+    cmd.assert()
+        .stdout(contains(
+            "impl comprehensive_api::traits::Simple for comprehensive_api::structs::Unit\n\
+             pub fn comprehensive_api::structs::Unit::act()\n",
+        ))
+        .stdout(contains("comprehensive_api::traits::Simple::act").not())
+        .success();
+}
+
+#[test]
+fn list_impls_for_trait() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--list-impls-for",
+        "comprehensive_api::traits::Simple",
+    ]);
+    cmd.assert()
+        .stdout("")
+        .stderr(contains(
+            "No impls found for `comprehensive_api::traits::Simple`",
+        ))
+        .failure();
+}
+
+#[test]
+fn list_impls_for_when_diffing() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--list-impls-for", "example_api::Struct"]);
+    cmd.args(["--diff", "v0.1.0", "v0.2.0"]);
+    cmd.assert()
+        .stderr(contains("cannot be used with"))
+        .failure();
+}
+
+#[test]
+fn list_impls_for_unknown_type() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--list-impls-for", "example_api::NoSuchStruct"]);
+    cmd.assert()
+        .stdout("")
+        .stderr(contains("No impls found for `example_api::NoSuchStruct`"))
+        .failure();
+}
+
 /// Make sure we can run the tool with a specified package from a virtual
 /// manifest.
 #[test]
 fn list_public_items_via_package_spec() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/virtual-manifest");
+    cmd.arg("--package");
+    cmd.arg("specific-crate");
+    cmd.arg("--allow-empty");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/specific-crate.txt")
+        .stderr(contains("Note: No public items found"))
+        .success();
+}
+
+/// `specific-crate` has no public items besides its root module, which is
+/// treated as an error unless `--allow-empty` is passed.
+#[test]
+fn no_public_items() {
     let mut cmd = TestCmd::new().with_separate_target_dir();
     cmd.current_dir("../test-apis/virtual-manifest");
     cmd.arg("--package");
     cmd.arg("specific-crate");
     cmd.assert()
         .stdout_or_bless("./tests/expected-output/specific-crate.txt")
+        .stderr(contains("No public items found"))
+        .stderr(contains("pass `--allow-empty`"))
+        .failure();
+}
+
+/// `cargo-public-api` itself depends on `serde`, so we can list the public API
+/// of `serde` without a checkout of it.
+#[test]
+fn list_public_items_of_dependency() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["--manifest-path", "./Cargo.toml", "--dependency", "serde"]);
+    cmd.assert()
+        .stdout(contains("pub trait serde::ser::Serialize"))
+        .stdout(contains("pub trait serde::de::Deserialize<'de>"))
+        .stderr(contains(
+            "Note: listing the public API of dependency `serde` from",
+        ))
         .success();
 }
 
+#[test]
+fn list_public_items_of_unknown_dependency() {
+    let mut cmd = TestCmd::new();
+    cmd.args([
+        "--manifest-path",
+        "./Cargo.toml",
+        "--dependency",
+        "no-such-crate",
+    ]);
+    cmd.assert()
+        .stderr(contains("`no-such-crate` is not a dependency of"))
+        .failure();
+}
+
 #[test]
 fn target_arg() {
     // A bit of a hack but similar to how rustc bootstrap script does it:
@@ -215,6 +557,33 @@ fn diff_public_items_impl(diff_arg: &str) {
     assert_eq!(branch_before, branch_after);
 }
 
+/// Building both commits at the same time shall give the same result as
+/// building them one after the other.
+#[test]
+fn diff_public_items_parallel() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    let branch_before = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.arg("v0.3.0");
+    cmd.arg("--parallel");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
+        .success();
+    let branch_after = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    assert_eq!(branch_before, branch_after);
+
+    // The worktree of the old commit shall be removed afterwards
+    let worktrees = std::process::Command::new("git")
+        .args(["worktree", "list", "--porcelain"])
+        .current_dir(&test_repo_path)
+        .output()
+        .unwrap();
+    let worktrees = String::from_utf8(worktrees.stdout).unwrap();
+    assert_eq!(worktrees.matches("worktree ").count(), 1, "{worktrees}");
+}
+
 /// Test that the mechanism to restore the original git branch works even if
 /// there is no current branch
 #[test]
@@ -240,44 +609,208 @@ fn diff_public_items_detached_head() {
     assert_eq!(before, after);
 }
 
-/// Test that diffing fails if the git tree is dirty
+/// Test that `git_utils` gives the current commit as a full hash, the same as
+/// what `HEAD` resolves to
 #[test]
-#[cfg_attr(target_family = "windows", ignore)]
-fn diff_public_items_with_dirty_tree_fails() {
-    let test_repo = create_test_repo_with_dirty_git_tree();
+fn current_commit_is_full_hash() {
+    let test_repo = TestRepo::new();
 
-    // Make sure diffing does not destroy uncommitted data!
-    let mut cmd = TestCmd::new();
-    cmd.current_dir(&test_repo.path);
-    cmd.arg("--diff-git-checkouts");
-    cmd.arg("v0.2.0");
-    cmd.arg("v0.3.0");
-    cmd.assert()
-        .stderr(contains(
-            "Your local changes to the following files would be overwritten by checkout",
-        ))
-        .failure();
+    let commit = git_utils::current_commit(test_repo.path()).unwrap();
+    assert_eq!(commit.len(), 40, "Unexpected commit: {commit}");
+    assert!(commit.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(
+        git_utils::resolve_ref(test_repo.path(), "HEAD").unwrap(),
+        commit
+    );
 }
 
-/// Test that diffing succeedes if the git tree is dirty and
-/// `force-git-checkout` option is specified.
+/// Diff a feature branch made from v0.1.0 against `main`, which has advanced to
+/// v0.3.0 since the branch was made. With `--merge-base`, only the changes of
+/// the branch shall be reported.
 #[test]
-#[cfg_attr(target_family = "windows", ignore)]
-fn diff_public_items_with_dirty_tree_succeedes_with_force_option() {
-    let test_repo = create_test_repo_with_dirty_git_tree();
+fn diff_git_checkouts_merge_base() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(&test_repo_path)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["checkout", "--quiet", "-b", "feature", "v0.1.0"]);
+    for file in ["Cargo.toml", "src/lib.rs"] {
+        std::fs::copy(
+            Path::new("../test-apis/example_api-v0.2.0").join(file),
+            test_repo_path.join(file),
+        )
+        .unwrap();
+    }
+    git(&["commit", "--quiet", "--all", "-m", "feature"]);
 
-    let mut cmd = TestCmd::new();
-    cmd.current_dir(&test_repo.path);
     cmd.arg("--diff-git-checkouts");
-    cmd.arg("v0.2.0");
-    cmd.arg("v0.3.0");
-    cmd.arg("--force-git-checkouts");
+    cmd.arg("main");
+    cmd.arg("HEAD");
+    cmd.arg("--merge-base");
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
         .success();
 }
 
-/// Test that relative git references like HEAD and HEAD^ work
+/// With `--no-cleanup-on-error`, a failed build shall leave the repo at the
+/// commit that failed to build, rather than restoring the original branch.
+#[test]
+fn no_cleanup_on_error() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    commit_broken_branch(&test_repo_path);
+
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.1.0");
+    cmd.arg("broken");
+    cmd.arg("--no-cleanup-on-error");
+    cmd.assert()
+        .stderr(contains("because of `--no-cleanup-on-error`"))
+        .stderr(contains("Run `git checkout main` to go back"))
+        .failure();
+
+    assert_eq!(None, git_utils::current_branch(&test_repo_path).unwrap());
+    assert_eq!(
+        git_utils::current_commit(&test_repo_path).unwrap(),
+        git_utils::resolve_ref(&test_repo_path, "broken").unwrap()
+    );
+}
+
+/// If the first commit fails to build, the original branch shall be restored,
+/// unless `--no-cleanup-on-error` is used.
+#[test]
+fn old_commit_fails_to_build() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    commit_broken_branch(&test_repo_path);
+
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("broken");
+    cmd.arg("v0.1.0");
+    cmd.assert().failure();
+
+    assert_eq!(
+        Some("main".to_owned()),
+        git_utils::current_branch(&test_repo_path).unwrap()
+    );
+
+    let mut cmd = TestCmd::new();
+    cmd.current_dir(&test_repo_path);
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("broken");
+    cmd.arg("v0.1.0");
+    cmd.arg("--no-cleanup-on-error");
+    cmd.assert()
+        .stderr(contains("Run `git checkout main` to go back"))
+        .failure();
+
+    assert_eq!(
+        git_utils::current_commit(&test_repo_path).unwrap(),
+        git_utils::resolve_ref(&test_repo_path, "broken").unwrap()
+    );
+}
+
+/// With `--parallel`, `--no-cleanup-on-error` shall also leave the `git
+/// worktree` of the old commit, since that is where the failure might be.
+#[test]
+fn no_cleanup_on_error_parallel() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    commit_broken_branch(&test_repo_path);
+
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("broken");
+    cmd.arg("v0.1.0");
+    cmd.arg("--parallel");
+    cmd.arg("--no-cleanup-on-error");
+    cmd.assert()
+        .stderr(contains("leaving the git worktree at"))
+        .stderr(contains("Run `git checkout main` to go back"))
+        .failure();
+
+    let worktrees = std::process::Command::new("git")
+        .current_dir(&test_repo_path)
+        .args(["worktree", "list", "--porcelain"])
+        .output()
+        .unwrap();
+    let worktrees = String::from_utf8(worktrees.stdout).unwrap();
+    assert_eq!(worktrees.matches("worktree ").count(), 2, "{worktrees}");
+}
+
+/// Adds a `broken` branch that fails to build to the test repo at `path`.
+fn commit_broken_branch(path: &Path) {
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(path)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["checkout", "--quiet", "-b", "broken", "v0.3.0"]);
+    let lib_rs = path.join("src/lib.rs");
+    let mut contents = std::fs::read_to_string(&lib_rs).unwrap();
+    contents.push_str("compile_error!(\"broken\");\n");
+    std::fs::write(&lib_rs, contents).unwrap();
+    git(&["commit", "--quiet", "--all", "-m", "broken"]);
+    git(&["checkout", "--quiet", "main"]);
+}
+
+#[test]
+fn merge_base_without_git_checkouts() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--merge-base");
+    cmd.assert()
+        .stderr(contains(
+            "`--merge-base` can only be used when diffing git checkouts",
+        ))
+        .failure();
+}
+
+/// Test that diffing fails if the git tree is dirty
+#[test]
+#[cfg_attr(target_family = "windows", ignore)]
+fn diff_public_items_with_dirty_tree_fails() {
+    let test_repo = create_test_repo_with_dirty_git_tree();
+
+    // Make sure diffing does not destroy uncommitted data!
+    let mut cmd = TestCmd::new();
+    cmd.current_dir(&test_repo.path);
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.arg("v0.3.0");
+    cmd.assert()
+        .stderr(contains(
+            "Your local changes to the following files would be overwritten by checkout",
+        ))
+        .failure();
+}
+
+/// Test that diffing succeedes if the git tree is dirty and
+/// `force-git-checkout` option is specified.
+#[test]
+#[cfg_attr(target_family = "windows", ignore)]
+fn diff_public_items_with_dirty_tree_succeedes_with_force_option() {
+    let test_repo = create_test_repo_with_dirty_git_tree();
+
+    let mut cmd = TestCmd::new();
+    cmd.current_dir(&test_repo.path);
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.arg("v0.3.0");
+    cmd.arg("--force-git-checkouts");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
+        .success();
+}
+
+/// Test that relative git references like HEAD and HEAD^ work
 /// (even as the second diff target).
 #[test]
 fn diff_public_items_relative_refs() {
@@ -395,6 +928,49 @@ fn deny_removed_with_diff() {
         .failure();
 }
 
+#[test]
+fn deny_removal_of_removed_item() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.arg("v0.3.0");
+    cmd.arg("--deny-removal-of");
+    cmd.arg("example_api::Struct");
+    cmd.arg("--deny-removal-of");
+    cmd.arg("example_api::function");
+    cmd.assert()
+        .stderr(contains(
+            "Removing these items is not allowed as per --deny-removal-of:\n\
+             pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)\n",
+        ))
+        .failure();
+}
+
+#[test]
+fn deny_removal_of_kept_item() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.arg("v0.3.0");
+    cmd.arg("--deny-removal-of");
+    cmd.arg("example_api::Struct");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
+        .success();
+}
+
+#[test]
+fn deny_removal_of_when_not_diffing() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--deny-removal-of");
+    cmd.arg("example_api::Struct");
+    cmd.assert()
+        .stderr(contains(
+            "`--deny-removal-of` can only be used when diffing",
+        ))
+        .failure();
+}
+
 #[test]
 fn deny_with_invalid_arg() {
     let mut cmd = TestCmd::new().with_test_repo();
@@ -451,104 +1027,1094 @@ fn diff_public_items_with_color() {
         .success();
 }
 
+/// A relative `--target-dir` must be the same dir for both builds, also if a
+/// build happens in another checkout.
+#[test]
+fn diff_git_checkouts_with_relative_target_dir() {
+    let test_repo = TestRepo::new();
+    let json_path = std::fs::canonicalize(test_repo.path())
+        .unwrap()
+        .join("relative-target/doc/example_api.json");
+
+    let mut cmd = TestCmd::new();
+    cmd.current_dir(test_repo.path());
+    cmd.args(["--target-dir", "relative-target", "--verbose"]);
+    cmd.args(["--diff-git-checkouts", "v0.1.0", "v0.2.0"]);
+    cmd.assert()
+        .stdout(contains(format!("Processing {json_path:?}")).count(2))
+        .success();
+}
+
+#[test]
+fn diff_public_items_with_width() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--width", "40", "--diff-git-checkouts", "v0.1.0", "v0.2.0"]);
+    cmd.assert()
+        .stdout(contains("\n-pub struct example_api::Struct\n"))
+        .stdout(contains("\n+pub fn example_api::function(v1_param:…\n"))
+        .stdout(contains("\n+#[non_exhaustive] pub struct example_a…\n"))
+        .stdout(contains("v2_param").not())
+        .success();
+}
+
 #[test]
 fn list_public_items_with_color() {
     let mut cmd = TestCmd::new().with_test_repo();
-    cmd.arg("--color=always");
+    cmd.arg("--color=always");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_v0.3.0_colored.txt")
+        .success();
+}
+
+#[test]
+fn list_public_items_with_high_contrast_theme() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--color=always", "--theme", "high-contrast"]);
+    cmd.assert()
+        .stdout(contains("\u{1b}[1;94mpub\u{1b}[0m"))
+        .stdout(contains("\u{1b}[34mpub\u{1b}[0m").not())
+        .success();
+}
+
+#[test]
+fn list_public_items_with_monochrome_theme() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--color=always", "--theme", "monochrome"]);
+    cmd.assert()
+        .stdout(contains("\u{1b}[1mpub\u{1b}[0m"))
+        .stdout(contains("\u{1b}[36m").not())
+        .success();
+}
+
+#[test]
+fn diff_public_items_from_files() {
+    diff_public_items_from_files_impl("--diff-rustdoc-json");
+}
+#[test]
+fn diff_public_items_from_files_smart_diff() {
+    diff_public_items_from_files_impl("--diff");
+}
+
+fn diff_public_items_from_files_impl(diff_arg: &str) {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg(diff_arg);
+    cmd.arg(old);
+    cmd.arg(new);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_only_breaking() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg(old);
+    cmd.arg(new);
+    cmd.arg("--only-breaking");
+    cmd.assert()
+        .stdout_or_bless(
+            "./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0_only_breaking.txt",
+        )
+        .success();
+}
+
+#[test]
+fn only_breaking_when_not_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--only-breaking");
+    cmd.assert()
+        .stderr(contains("`--only-breaking` can only be used when diffing"))
+        .failure();
+}
+
+#[test]
+fn diff_output_format_github() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg(old);
+    cmd.arg(new);
+    cmd.arg("--output-format=github");
+    cmd.arg("--deny=removed");
+    cmd.assert()
+        .stdout(contains(
+            "::error file=src/lib.rs,line=16,title=Removed public API item::pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)\n",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_output_format_json() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir2);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg(old);
+    cmd.arg(new);
+    cmd.arg("--output-format=json");
+    let output = cmd.assert().success().get_output().stdout.clone();
+
+    let report: DiffReport = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report.schema_version, json_report::SCHEMA_VERSION);
+    let removed = &report.removed[0];
+    assert_eq!(
+        removed.item,
+        "pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)"
+    );
+    assert_eq!(removed.file.as_deref(), Some("src/lib.rs"));
+    assert_eq!(removed.line, Some(16));
+}
+
+#[test]
+fn diff_commit_and_rustdoc_json_file() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    let branch_before = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    cmd.arg("--diff");
+    cmd.arg("v0.1.0");
+    cmd.arg(new);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+    let branch_after = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    assert_eq!(branch_before, branch_after);
+}
+
+#[test]
+fn diff_rustdoc_json_file_and_commit() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff");
+    cmd.arg(old);
+    cmd.arg("v0.2.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_baseline() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    std::fs::write(&baseline, output).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_moved_item() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub mod example_api\n\
+         pub mod example_api::old\n\
+         pub fn example_api::old::function(v1_param: example_api::Struct, v2_param: usize)\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.assert()
+        .stdout(contains(
+            "Moved items in the public API\n\
+             =============================\n\
+             -pub fn example_api::old::function(v1_param: example_api::Struct, v2_param: usize)\n\
+             +pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)\n",
+        ))
+        .stdout(contains("-pub mod example_api::old\n"))
+        .success();
+}
+
+/// The new path of a moved item counts as added, so `--deny=added` must make
+/// its annotation an error
+#[test]
+fn diff_moved_item_output_format_github_deny_added() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub mod example_api\n\
+         pub mod example_api::old\n\
+         pub fn example_api::old::function(v1_param: example_api::Struct, v2_param: usize)\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--output-format=github");
+    cmd.arg("--deny=added");
+    cmd.assert()
+        .stdout(contains(
+            "::error file=src/lib.rs,line=16,title=Moved public API item::-pub fn example_api::old::function(v1_param: example_api::Struct, v2_param: usize)%0A+pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)\n",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_include_blanket_impls() {
+    test_diff_blanket_impl_bound(true)
+        .stdout(contains(
+            "-impl<T, U> core::convert::Into<U> for example_api::Struct where U: core::convert::TryFrom<T>\n",
+        ))
+        .stdout(contains(
+            "+impl<T, U> core::convert::Into<U> for example_api::Struct where U: core::convert::From<T>\n",
+        ))
+        .success();
+}
+
+#[test]
+fn diff_simplified_omits_blanket_impls() {
+    test_diff_blanket_impl_bound(false)
+        .stdout(contains("+impl<T, U> core::convert::Into<U>").not())
+        .success();
+}
+
+#[test]
+fn include_blanket_impls_when_not_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--include-blanket-impls");
+    cmd.assert()
+        .stderr(contains(
+            "`--include-blanket-impls` can only be used when diffing",
+        ))
+        .failure();
+}
+
+/// Diffs `example_api-v0.2.0` with `--simplified`, which [`TestCmd::new()`]
+/// passes, against a baseline where the bound of the blanket `Into` impl of
+/// `example_api::Struct` differs.
+fn test_diff_blanket_impl_bound(include_blanket_impls: bool) -> Assert {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "impl<T, U> core::convert::Into<U> for example_api::Struct where U: core::convert::TryFrom<T>\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    if include_blanket_impls {
+        cmd.arg("--include-blanket-impls");
+    }
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.assert()
+}
+
+#[test]
+fn deny_changed_return_type() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize) -> usize\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--deny=changed-return-type");
+    cmd.assert()
+        .stderr(contains("Changed items not allowed"))
+        .failure();
+}
+
+#[test]
+fn deny_changed_return_type_allows_changed_param() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub fn example_api::function(v1_param: example_api::Struct)\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--deny=changed-return-type");
+    cmd.assert()
+        .stdout(contains(
+            "+pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)",
+        ))
+        .success();
+}
+
+#[test]
+fn diff_baseline_deny() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(&baseline, "pub fn example_api::removed()\n").unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--deny=removed");
+    cmd.assert()
+        .stdout(contains("-pub fn example_api::removed()"))
+        .failure();
+}
+
+#[test]
+fn fail_on_semver_major_with_added_items() {
+    test_fail_on_semver("", "major").success();
+}
+
+#[test]
+fn fail_on_semver_minor_with_added_items() {
+    test_fail_on_semver("", "minor")
+        .stderr(contains(
+            "The API diff requires a minor version bump, which is not allowed as per --fail-on-semver",
+        ))
+        .failure();
+}
+
+#[test]
+fn fail_on_semver_major_with_removed_items() {
+    test_fail_on_semver("pub fn example_api::removed()\n", "major")
+        .stderr(contains(
+            "The API diff requires a major version bump, which is not allowed as per --fail-on-semver",
+        ))
+        .failure();
+}
+
+#[test]
+fn print_bump_with_removed_items() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(&baseline, "pub fn example_api::removed()\n").unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--print-bump");
+    cmd.assert().stdout("major\n").success();
+}
+
+#[test]
+fn print_bump_without_diff() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--print-bump");
+    cmd.assert()
+        .stderr(contains("`--print-bump` can only be used when diffing"))
+        .failure();
+}
+
+#[test]
+fn diff_item_that_became_non_exhaustive() {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub enum comprehensive_api::attributes::NonExhaustive\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.arg("--deny=changed");
+    cmd.assert()
+        .stdout(contains(
+            "-pub enum comprehensive_api::attributes::NonExhaustive\n\
+             +#[non_exhaustive] pub enum comprehensive_api::attributes::NonExhaustive\n",
+        ))
+        .stderr(contains(
+            "Note: `comprehensive_api::attributes::NonExhaustive` became `#[non_exhaustive]`, which is a breaking change",
+        ))
+        .stderr(contains("The API diff is not allowed as per --deny"))
+        .failure();
+}
+
+/// Diffs `example_api-v0.1.0` against a baseline listing with the given
+/// contents.
+fn test_fail_on_semver(baseline_contents: &str, level: &str) -> Assert {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(&baseline, baseline_contents).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.args(["--fail-on-semver", level]);
+    cmd.assert()
+}
+
+#[test]
+fn fail_threshold_not_exceeded() {
+    test_fail_threshold(&["--deny=removed"], "3").success();
+}
+
+#[test]
+fn fail_threshold_exceeded() {
+    test_fail_threshold(&["--deny=removed"], "1")
+        .stderr(contains("The API diff is not allowed as per --deny"))
+        .failure();
+}
+
+/// Test that items denied by several `--deny` values count once. The 2
+/// removed items count once each, and the 6 items of the current API, e.g.
+/// `pub mod example_api`, count as added.
+#[test]
+fn fail_threshold_overlapping_deny() {
+    test_fail_threshold(&["--deny=removed", "--deny=all"], "8").success();
+}
+
+#[test]
+fn fail_threshold_counts_added() {
+    test_fail_threshold(&["--deny=added"], "1")
+        .stderr(contains("The API diff is not allowed as per --deny"))
+        .failure();
+}
+
+/// Test that a moved item counts once, even though it is both removed from its
+/// old path and added at its new path.
+#[test]
+fn fail_threshold_counts_moved_item_once() {
+    let manifest_path = [
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ];
+    let mut list = TestCmd::new().with_separate_target_dir();
+    list.args(manifest_path);
+    let output = list.assert().success().get_output().stdout.clone();
+    let baseline_items = String::from_utf8(output).unwrap().replace(
+        "pub fn example_api::function(",
+        "pub fn example_api::old::function(",
+    );
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(&baseline, baseline_items).unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(manifest_path);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.args(["--deny=all", "--fail-threshold", "1"]);
+    cmd.assert()
+        .stdout(contains("Moved items in the public API"))
+        .success();
+}
+
+#[test]
+fn fail_threshold_without_deny() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--diff", "v0.1.0", "v0.2.0", "--fail-threshold", "1"]);
+    cmd.assert()
+        .stderr(contains("required arguments were not provided"))
+        .failure();
+}
+
+/// Diffs against a baseline with two items that are not in the current API.
+fn test_fail_threshold(deny: &[&str], fail_threshold: &str) -> Assert {
+    let baseline_dir = tempdir().unwrap();
+    let baseline = baseline_dir.path().join("saved.txt");
+    std::fs::write(
+        &baseline,
+        "pub fn example_api::removed()\npub fn example_api::also_removed()\n",
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    cmd.arg("--diff-baseline");
+    cmd.arg(&baseline);
+    cmd.args(deny);
+    cmd.args(["--fail-threshold", fail_threshold]);
+    cmd.assert()
+}
+
+/// Diffing two published versions shall give the same result as diffing the
+/// corresponding git checkouts.
+#[test]
+fn diff_two_published() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg("example_api@0.1.0");
+    cmd.arg("example_api@0.2.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_two_published_parallel() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg("example_api@0.1.0");
+    cmd.arg("example_api@0.2.0");
+    cmd.arg("--parallel");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_two_published_with_docs_rs_links() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--diff");
+    cmd.arg("example_api@0.1.0");
+    cmd.arg("example_api@0.2.0");
+    cmd.arg("--docs-rs-links");
+    cmd.assert()
+        .stdout_or_bless(
+            "./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0_docs_rs_links.txt",
+        )
+        .success();
+}
+
+#[test]
+fn diff_two_published_with_docs_rs_links_and_width() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args(["--diff", "example_api@0.1.0", "example_api@0.2.0"]);
+    cmd.args(["--docs-rs-links", "--width", "40"]);
+    cmd.assert()
+        .stdout(contains(
+            "\n+pub fn example_api::function(v1_param:… https://docs.rs/example_api/0.2.0/example_api/fn.function.html\n",
+        ))
+        .success();
+}
+
+#[test]
+fn docs_rs_links_when_not_diffing_published() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--diff", "v0.1.0", "v0.2.0", "--docs-rs-links"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--docs-rs-links` can only be used when diffing published crates",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_published_and_commit() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff");
+    cmd.arg("example_api@0.1.0");
+    cmd.arg("v0.2.0");
+    cmd.assert()
+        .stderr(contains("`--diff` takes no other kind of argument"))
+        .failure();
+}
+
+#[test]
+fn diff_published() {
+    diff_published_impl("--diff-published", "example_api@0.1.0");
+}
+
+#[test]
+fn diff_published_smart_diff() {
+    diff_published_impl("--diff", "example_api@0.1.0");
+}
+
+#[test]
+fn diff_published_fallback() {
+    diff_published_impl("--diff-published", "@0.1.0");
+}
+
+#[test]
+fn diff_published_smart_diff_fallback() {
+    diff_published_impl("--diff", "@0.1.0");
+}
+
+/// Diff against a published crate.
+fn diff_published_impl(diff_arg: &str, spec: &str) {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg(diff_arg);
+    cmd.arg(spec);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .success();
+}
+
+/// The published baseline must be built from the registry, even if the user
+/// has a `[patch]` for the crate in `.cargo/config.toml`.
+#[test]
+#[cfg_attr(target_family = "windows", ignore)]
+fn diff_published_ignores_patches() {
+    // A local crate with the API of v0.2.0 that claims to be v0.1.0
+    let patched = tempdir().unwrap();
+    let mut copy = std::process::Command::new("cp");
+    copy.args(["-r", "../test-apis/example_api-v0.2.0/."])
+        .arg(patched.path());
+    assert!(copy.status().unwrap().success());
+    let manifest = patched.path().join("Cargo.toml");
+    let contents = std::fs::read_to_string(&manifest).unwrap();
+    std::fs::write(&manifest, contents.replace("0.2.0", "0.1.0")).unwrap();
+
+    let mut cmd = TestCmd::new().with_test_repo();
+    let config_dir = cmd.test_repo_path().join(".cargo");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        format!(
+            "[patch.crates-io]\nexample_api = {{ path = {:?} }}\n",
+            patched.path()
+        ),
+    )
+    .unwrap();
+
+    cmd.arg("--diff-published");
+    cmd.arg("example_api@0.1.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .success();
+}
+
+#[test]
+fn baseline_path_crate_file() {
+    let package_dir = tempdir().unwrap();
+    let crate_file = package_example_api_v0_1_0(package_dir.path());
+
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--baseline-path");
+    cmd.arg(crate_file);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .success();
+}
+
+#[test]
+#[cfg_attr(target_family = "windows", ignore)]
+fn baseline_path_extracted_dir() {
+    let package_dir = tempdir().unwrap();
+    let crate_file = package_example_api_v0_1_0(package_dir.path());
+    let mut tar = std::process::Command::new("tar");
+    tar.current_dir(package_dir.path())
+        .arg("-xzf")
+        .arg(crate_file);
+    assert!(tar.status().unwrap().success());
+
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--baseline-path");
+    cmd.arg(package_dir.path().join("example_api-0.1.0"));
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .success();
+}
+
+/// The baseline must not be built as part of the workspace of the current
+/// crate, even if it is in a dir of that workspace
+#[test]
+#[cfg_attr(target_family = "windows", ignore)]
+fn baseline_path_extracted_dir_inside_workspace() {
+    let workspace_dir = tempdir().unwrap();
+    let crate_file = package_example_api_v0_1_0(workspace_dir.path());
+    let mut tar = std::process::Command::new("tar");
+    tar.current_dir(workspace_dir.path())
+        .arg("-xzf")
+        .arg(crate_file);
+    assert!(tar.status().unwrap().success());
+
+    // Make v0.2.0 the root of a workspace that contains the extracted v0.1.0
+    // without it being a member
+    let manifest = std::fs::read_to_string("../test-apis/example_api-v0.2.0/Cargo.toml").unwrap();
+    std::fs::write(
+        workspace_dir.path().join("Cargo.toml"),
+        format!("[workspace]\n{manifest}"),
+    )
+    .unwrap();
+    std::fs::create_dir(workspace_dir.path().join("src")).unwrap();
+    std::fs::copy(
+        "../test-apis/example_api-v0.2.0/src/lib.rs",
+        workspace_dir.path().join("src/lib.rs"),
+    )
+    .unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir(workspace_dir.path());
+    cmd.arg("--baseline-path");
+    cmd.arg("example_api-0.1.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .success();
+}
+
+#[test]
+fn baseline_all_features() {
+    let baseline = std::fs::canonicalize("../test-apis/features").unwrap();
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/features");
+    cmd.arg("--baseline-path");
+    cmd.arg(baseline);
+    cmd.arg("--baseline-all-features");
+    cmd.assert()
+        .stdout(contains(
+            "Removed items from the public API\n\
+             =================================\n\
+             -pub struct field features::AStruct::feature_b: ()\n\
+             -pub struct field features::AStruct::feature_c: ()\n",
+        ))
+        .success();
+}
+
+#[test]
+fn baseline_features_when_not_diffing_published() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--baseline-features", "feature_b"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--baseline-features`, `--baseline-all-features` and `--baseline-no-default-features` can only be used when diffing published crates",
+        ))
+        .failure();
+}
+
+/// Creates the same kind of `.crate` file as would be downloaded from
+/// crates.io, and returns the path to it.
+fn package_example_api_v0_1_0(target_dir: &Path) -> PathBuf {
+    let mut cargo = std::process::Command::new("cargo");
+    cargo.args(["package", "--quiet", "--no-verify", "--allow-dirty"]);
+    cargo.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.1.0/Cargo.toml",
+    ]);
+    cargo.arg("--target-dir").arg(target_dir);
+    assert!(cargo.status().unwrap().success());
+    target_dir.join("package").join("example_api-0.1.0.crate")
+}
+
+/// The second diff against the same published version must reuse the rustdoc
+/// JSON built by the first, unless `--refresh-baseline` is used, or unless it
+/// is built differently, e.g. with `--show-cfgs`.
+#[test]
+fn diff_published_reuses_cached_baseline() {
+    let test_repo = TestRepo::new();
+    let target_dir = tempdir().unwrap();
+    let diff_published = |extra_args: &[&str]| {
+        let mut cmd = TestCmd::new();
+        cmd.current_dir(test_repo.path());
+        cmd.arg("--target-dir").arg(target_dir.path());
+        cmd.arg("--diff-published").arg("example_api@0.1.0");
+        for arg in extra_args {
+            cmd.arg(arg);
+        }
+        cmd.assert()
+            .stdout_or_bless("./tests/expected-output/diff_published.txt")
+            .success()
+    };
+
+    diff_published(&[]).stderr(contains("cached").not());
+    diff_published(&[]).stderr(contains(
+        "Note: reusing the rustdoc JSON for example_api@0.1.0 cached at",
+    ));
+    diff_published(&["--show-cfgs"]).stderr(contains("cached").not());
+    diff_published(&["--refresh-baseline"]).stderr(contains("cached").not());
+}
+
+#[test]
+fn refresh_baseline_when_not_diffing_published() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--diff", "v0.2.0", "v0.3.0", "--refresh-baseline"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--refresh-baseline` can only be used when diffing published crates",
+        ))
+        .failure();
+}
+
+#[test]
+fn diff_published_explicit_package() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("-p");
+    cmd.arg("example_api");
+    cmd.arg("--diff-published");
+    cmd.arg("@0.1.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .success();
+}
+
+/// The registry does not exist, so the build fails, but it must fail while
+/// fetching from the configured registry rather than from crates.io.
+#[test]
+fn diff_published_from_alternative_registry() {
+    let index_dir = tempdir().unwrap();
+    let index = index_dir.path().join("no-such-index");
+
+    let mut cmd = TestCmd::new().with_test_repo().without_cargo_colors();
+    cmd.env(
+        "CARGO_REGISTRIES_PRIVATE_INDEX",
+        format!("file://{}", index.display()),
+    );
+    cmd.args(["--diff-published", "example_api@0.1.0"]);
+    cmd.args(["--registry", "private"]);
+    cmd.assert()
+        .stderr(contains("registry `private`"))
+        .failure();
+}
+
+#[test]
+fn registry_when_not_diffing_published() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.args(["--diff", "v0.1.0", "v0.2.0", "--registry", "private"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--registry` can only be used when diffing published crates",
+        ))
+        .failure();
+}
+
+#[test]
+fn list_public_items_from_json_file() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json_file = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir);
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--rustdoc-json");
+    cmd.arg(json_file);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api-v0.3.0.txt")
+        .success();
+}
+
+#[test]
+fn diff_single_git_ref_against_current_dir() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let test_repo_path = cmd.test_repo_path().to_owned();
+    let branch_before = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    cmd.arg("--diff");
+    cmd.arg("v0.2.0");
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
+        .success();
+    let branch_after = git_utils::current_branch(&test_repo_path).unwrap().unwrap();
+    assert_eq!(branch_before, branch_after);
+}
+
+/// Test that the git ref is built without touching the current working tree,
+/// so that uncommitted changes survive and only end up on the new side
+#[test]
+fn diff_single_git_ref_against_uncommitted_changes() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    let lib_rs = cmd.test_repo_path().join("src/lib.rs");
+    let mut contents = std::fs::read_to_string(&lib_rs).unwrap();
+    contents.push_str("pub fn uncommitted_function() {}\n");
+    std::fs::write(&lib_rs, &contents).unwrap();
+
+    cmd.arg("--diff");
+    cmd.arg("v0.2.0");
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/example_api_v0.3.0_colored.txt")
+        .stdout(contains("+pub fn example_api::uncommitted_function()\n"))
         .success();
-}
 
-#[test]
-fn diff_public_items_from_files() {
-    diff_public_items_from_files_impl("--diff-rustdoc-json");
-}
-#[test]
-fn diff_public_items_from_files_smart_diff() {
-    diff_public_items_from_files_impl("--diff");
+    assert_eq!(std::fs::read_to_string(&lib_rs).unwrap(), contents);
 }
 
-fn diff_public_items_from_files_impl(diff_arg: &str) {
-    // Create independent build dirs so all tests can run in parallel
+#[test]
+fn diff_single_rustdoc_json_against_current_dir() {
+    // Create independent build dir so all tests can run in parallel
     let build_dir = tempdir().unwrap();
-    let build_dir2 = tempdir().unwrap();
 
-    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
-    let new = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
-    let mut cmd = TestCmd::new().with_separate_target_dir();
-    cmd.arg(diff_arg);
+    let old = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff");
     cmd.arg(old);
-    cmd.arg(new);
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.1.0_to_v0.2.0.txt")
+        .stdout_or_bless("./tests/expected-output/example_api_diff_v0.2.0_to_v0.3.0.txt")
         .success();
 }
 
 #[test]
-fn diff_published() {
-    diff_published_impl("--diff-published", "example_api@0.1.0");
+fn diff_single_listing_against_current_dir() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff");
+    cmd.arg(
+        Path::new("tests/expected-output/example_api-v0.3.0.txt")
+            .canonicalize()
+            .unwrap(),
+    );
+    cmd.assert()
+        .stdout(contains(
+            "Removed items from the public API\n\
+             =================================\n\
+             (none)\n\
+             \n\
+             Changed items in the public API\n\
+             ===============================\n\
+             (none)\n\
+             \n\
+             Added items to the public API\n\
+             =============================\n\
+             (none)\n",
+        ))
+        .success();
 }
 
 #[test]
-fn diff_published_smart_diff() {
-    diff_published_impl("--diff", "example_api@0.1.0");
+fn diff_public_items_missing_one_arg() {
+    let mut cmd = TestCmd::new().with_test_repo();
+    cmd.arg("--diff-git-checkouts");
+    cmd.arg("v0.2.0");
+    cmd.assert()
+        .stderr(contains("requires 2 values, but 1 was provided"))
+        .failure();
 }
 
 #[test]
-fn diff_published_fallback() {
-    diff_published_impl("--diff-published", "@0.1.0");
+fn unsupported_format_version() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--rustdoc-json");
+    cmd.arg("../public-api/tests/rustdoc-json/format_version_9999.json");
+    cmd.assert()
+        .stderr(contains("format version 9999 is not supported"))
+        .failure();
 }
 
 #[test]
-fn diff_published_smart_diff_fallback() {
-    diff_published_impl("--diff", "@0.1.0");
-}
-
-/// Diff against a published crate.
-fn diff_published_impl(diff_arg: &str, spec: &str) {
-    let mut cmd = TestCmd::new().with_test_repo();
-    cmd.arg(diff_arg);
-    cmd.arg(spec);
+fn format_version_override() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--rustdoc-json");
+    cmd.arg("../public-api/tests/rustdoc-json/format_version_9999.json");
+    cmd.arg("--format-version");
+    cmd.arg("9999");
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .stdout("pub mod future\n")
+        .stderr(contains("Warning: used best-effort parsing"))
         .success();
 }
 
+/// The warning about best-effort parsing is only printed if the rustdoc JSON
+/// actually needed it.
 #[test]
-fn diff_published_explicit_package() {
-    let mut cmd = TestCmd::new().with_test_repo();
-    cmd.arg("-p");
-    cmd.arg("example_api");
-    cmd.arg("--diff-published");
-    cmd.arg("@0.1.0");
+fn format_version_override_not_needed() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/example_api-v0.2.0/Cargo.toml",
+    ]);
+    cmd.arg("--format-version");
+    cmd.arg("9999");
+    cmd.assert().stderr(contains("best-effort").not()).success();
+}
+
+#[test]
+fn show_values() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.args([
+        "--manifest-path",
+        "../test-apis/comprehensive_api/Cargo.toml",
+        "--show-values",
+    ]);
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/diff_published.txt")
+        .stdout(contains(
+            "pub const comprehensive_api::constants::MAX: u32 = 100\n",
+        ))
         .success();
 }
 
 #[test]
-fn list_public_items_from_json_file() {
-    // Create independent build dir so all tests can run in parallel
-    let build_dir = tempdir().unwrap();
-
-    let json_file = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir);
-    let mut cmd = TestCmd::new().with_separate_target_dir();
+fn mark_provided_methods() {
+    let mut cmd = TestCmd::new();
     cmd.arg("--rustdoc-json");
-    cmd.arg(json_file);
+    cmd.arg("../public-api/tests/rustdoc-json/provided_methods.json");
+    cmd.arg("--mark-provided-methods");
     cmd.assert()
-        .stdout_or_bless("./tests/expected-output/example_api-v0.3.0.txt")
+        .stdout(contains("pub fn provided::Trait::required()\n"))
+        .stdout(contains("pub fn provided::Trait::provided() { ... }\n"))
         .success();
 }
 
 #[test]
-fn diff_public_items_missing_one_arg() {
-    let mut cmd = TestCmd::new().with_test_repo();
-    cmd.arg("--diff-git-checkouts");
-    cmd.arg("v0.2.0");
+fn qualify_external() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--rustdoc-json");
+    cmd.arg("../public-api/tests/rustdoc-json/unresolved_external_path.json");
+    cmd.arg("--qualify-external");
     cmd.assert()
-        .stderr(contains("requires 2 values, but 1 was provided"))
-        .failure();
+        .stdout(contains(
+            "pub fn unresolved::bytes() -> alloc::vec::Vec<u8>",
+        ))
+        .success();
 }
 
 #[test]
@@ -563,6 +2129,30 @@ fn verbose() {
         .success();
 }
 
+#[test]
+fn timeout() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.arg("--manifest-path");
+    cmd.arg("../test-apis/example_api-v0.2.0/Cargo.toml");
+    cmd.arg("--timeout");
+    cmd.arg("0.001");
+    cmd.assert()
+        .stdout("")
+        .stderr(contains(
+            "Building rustdoc JSON timed out after 0.001 seconds",
+        ))
+        .failure();
+}
+
+#[test]
+fn invalid_timeout() {
+    let mut cmd = TestCmd::new();
+    cmd.arg("--timeout=-1");
+    cmd.assert()
+        .stderr(contains("Invalid value '-1' for '--timeout <SECONDS>'"))
+        .failure();
+}
+
 #[test]
 fn long_help() {
     let mut cmd = TestCmd::new();
@@ -674,6 +2264,302 @@ fn features_b_c() {
     test_features(&F::new(&["feature_c"]).none()); // includes `feature_b`
 }
 
+#[test]
+fn feature_delta() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/features");
+    cmd.args(["--feature-delta", "feature_b"]);
+    cmd.assert()
+        .stdout("pub struct field features::AStruct::feature_b: ()\n")
+        .success();
+}
+
+#[test]
+fn feature_delta_when_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--feature-delta", "feature_a"]);
+    cmd.args(["--diff-git-checkouts", "v0.2.0", "v0.3.0"]);
+    cmd.assert()
+        .stderr(contains("cannot be used with"))
+        .failure();
+}
+
+#[test]
+fn max_public_items_exceeded() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.args(["--max-public-items", "5"]);
+    cmd.assert()
+        .stdout(contains("pub struct example_api::Struct\n"))
+        .stderr(contains(
+            "The public API has 8 items, which is more than the 5 allowed by `--max-public-items`",
+        ))
+        .failure();
+}
+
+#[test]
+fn max_public_items_not_exceeded() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.args(["--max-public-items", "8"]);
+    cmd.assert().success();
+}
+
+#[test]
+fn max_public_items_when_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--diff", "v0.2.0", "v0.3.0", "--max-public-items", "1"]);
+    cmd.assert()
+        .stderr(contains(
+            "`--max-public-items` can only be used when listing",
+        ))
+        .failure();
+}
+
+#[test]
+fn watch() {
+    use std::io::{BufRead, BufReader, Read};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+
+    fn lines(output: impl Read + Send + 'static) -> Receiver<String> {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(output).lines() {
+                let _ = sender.send(line.unwrap());
+            }
+        });
+        receiver
+    }
+
+    fn wait_for_line(lines: &Receiver<String>, expected: &str) -> String {
+        loop {
+            let line = lines.recv_timeout(Duration::from_mins(2)).unwrap();
+            if line.starts_with(expected) {
+                return line;
+            }
+        }
+    }
+
+    let crate_dir = tempdir().unwrap();
+    let target_dir = tempdir().unwrap();
+    std::fs::create_dir(crate_dir.path().join("src")).unwrap();
+    for file in ["Cargo.toml", "src/lib.rs"] {
+        std::fs::copy(
+            Path::new("../test-apis/example_api-v0.1.0").join(file),
+            crate_dir.path().join(file),
+        )
+        .unwrap();
+    }
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("cargo-public-api"))
+        .args(["--simplified", "--watch", "--target-dir"])
+        .arg(target_dir.path())
+        .current_dir(&crate_dir)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = lines(child.stdout.take().unwrap());
+    let stderr = lines(child.stderr.take().unwrap());
+
+    wait_for_line(
+        &stdout,
+        "pub fn example_api::function(v1_param: example_api::Struct)",
+    );
+    // The target dir is built into, so it must not be watched
+    let watching = wait_for_line(&stderr, "Watching ");
+    assert!(watching.ends_with("src for changes..."), "{watching}");
+    assert!(!watching.contains("target"), "{watching}");
+    OpenOptions::new()
+        .append(true)
+        .open(crate_dir.path().join("src/lib.rs"))
+        .unwrap()
+        .write_all(b"\npub fn added_while_watching() {}\n")
+        .unwrap();
+    wait_for_line(&stdout, "pub fn example_api::added_while_watching()");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn emit_json() {
+    let dir = tempdir().unwrap();
+    let json_path = dir.path().join("api.json");
+
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.arg("--emit-json").arg(&json_path);
+    cmd.assert()
+        .stdout(contains("pub struct example_api::StructV2\n"))
+        .success();
+
+    let mut cmd = TestCmd::new();
+    cmd.arg("--rustdoc-json").arg(&json_path);
+    cmd.assert()
+        .stdout(contains("pub struct example_api::StructV2\n"))
+        .success();
+}
+
+#[test]
+fn emit_json_when_not_listing_current_crate() {
+    for args in [
+        ["--diff", "v0.2.0", "v0.3.0"].as_slice(),
+        ["--rustdoc-json", "public-api.json"].as_slice(),
+    ] {
+        let mut cmd = TestCmd::new();
+        cmd.args(args);
+        cmd.args(["--emit-json", "emitted.json"]);
+        cmd.assert()
+            .stderr(contains(
+                "`--emit-json` can only be used when listing the public API of the current crate",
+            ))
+            .failure();
+    }
+}
+
+#[test]
+fn omit_auto_derived_impls() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.args(["--omit", "auto-derived-impls"]);
+    cmd.assert()
+        .stdout(contains("pub struct example_api::Struct\n"))
+        .stdout(contains("impl core::fmt::Debug for example_api::Struct").not())
+        .stdout(contains("pub fn example_api::Struct::fmt").not())
+        .success();
+}
+
+#[test]
+fn dry_run() {
+    let mut cmd = TestCmd::new();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.arg("--dry-run");
+    cmd.assert()
+        .stdout(contains("cargo"))
+        .stdout(contains("--output-format json"))
+        .stdout(contains("pub struct").not())
+        .success();
+}
+
+#[test]
+fn dry_run_quotes_args() {
+    let mut cmd = TestCmd::new();
+    cmd.current_dir("../test-apis/example_api-v0.3.0");
+    cmd.args(["--dry-run", "--show-cfgs"]);
+    cmd.assert()
+        .stdout(contains(
+            r#" --config 'build.rustdocflags=["--cfg", "docsrs"]' "#,
+        ))
+        .success();
+}
+
+#[test]
+fn dry_run_when_diffing() {
+    let mut cmd = TestCmd::new();
+    cmd.args(["--dry-run", "--diff", "v0.2.0", "v0.3.0"]);
+    cmd.assert()
+        .stderr(contains("cannot be used with"))
+        .failure();
+}
+
+#[test]
+fn dry_run_with_other_build() {
+    for args in [
+        ["--rustdoc-json", "public-api.json"],
+        ["--dependency", "rustdoc-json"],
+    ] {
+        let mut cmd = TestCmd::new();
+        cmd.arg("--dry-run");
+        cmd.args(args);
+        cmd.assert()
+            .stderr(contains("cannot be used with"))
+            .failure();
+    }
+}
+
+#[test]
+fn diff_targets() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/cross_platform");
+    cmd.args([
+        "--diff-targets",
+        "x86_64-unknown-linux-gnu",
+        "x86_64-pc-windows-msvc",
+    ]);
+    cmd.assert()
+        .stdout_or_bless("./tests/expected-output/diff_targets.txt")
+        .success();
+}
+
+#[test]
+fn no_std_detection() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/no_std");
+    cmd.arg("--no-std-detection");
+    cmd.assert()
+        .stdout("pub mod no_std\npub fn no_std::core_only()\npub fn no_std::extra()\n")
+        .stderr(contains("the crate is `no_std`"))
+        .success();
+}
+
+#[test]
+fn no_std_detection_with_std_feature() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/no_std");
+    cmd.args(["--no-std-detection", "--features", "std"]);
+    cmd.assert()
+        .stdout(contains(
+            "pub fn no_std::needs_std() -> alloc::string::String\n",
+        ))
+        .stderr(contains("the crate is `no_std`").not())
+        .success();
+}
+
+#[test]
+fn feature_set() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/features");
+    cmd.args(["--feature-set", "full"]);
+    cmd.assert()
+        .stdout(contains(
+            "pub struct field features::AStruct::feature_a: ()\n",
+        ))
+        .stdout(contains(
+            "pub struct field features::AStruct::feature_b: ()\n",
+        ))
+        .stdout(contains(
+            "pub struct field features::AStruct::feature_c: ()\n",
+        ))
+        .success();
+}
+
+#[test]
+fn feature_set_that_does_not_exist() {
+    let mut cmd = TestCmd::new();
+    cmd.current_dir("../test-apis/features");
+    cmd.args(["--feature-set", "missing"]);
+    cmd.assert()
+        .stderr(contains(
+            "No feature set `missing` in `[package.metadata.public-api.feature-sets]`",
+        ))
+        .failure();
+}
+
+#[test]
+fn show_cfgs() {
+    let mut cmd = TestCmd::new().with_separate_target_dir();
+    cmd.current_dir("../test-apis/features");
+    cmd.args(["--show-cfgs", "--all-features"]);
+    cmd.assert()
+        .stdout(contains(
+            "#[cfg(feature = \"feature_b\")] pub struct field features::AStruct::feature_b: ()\n",
+        ))
+        .stdout(contains("#[non_exhaustive] pub struct features::AStruct\n"))
+        .success();
+}
+
 fn test_features(features: &F) {
     let mut cmd = TestCmd::new().with_separate_target_dir();
     cmd.current_dir("../test-apis/features");
@@ -835,6 +2721,11 @@ impl TestCmd {
         self
     }
 
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.cmd.env(key, value);
+        self
+    }
+
     pub fn assert(&mut self) -> Assert {
         self.cmd.assert()
     }
@@ -0,0 +1,33 @@
+//! Best-effort parsing of rustdoc JSON with a format version that we do not
+//! officially support. See [`crate::Options::format_version`].
+
+use rustdoc_types::{Crate, Item, ItemSummary};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::Result;
+
+/// Only the format version of the rustdoc JSON. Allows us to find out what
+/// version of the format we are dealing with even if the rest of the JSON
+/// can't be parsed.
+#[derive(Deserialize)]
+pub(crate) struct FormatVersion {
+    pub(crate) format_version: u32,
+}
+
+/// Drops all entries in `index` and `paths` that can't be parsed, and then
+/// parses what remains. Items that were dropped will be reported as missing
+/// items if they are referenced.
+pub(crate) fn deserialize(mut json: Value) -> Result<Crate> {
+    retain_parsable::<Item>(&mut json, "index");
+    retain_parsable::<ItemSummary>(&mut json, "paths");
+
+    Ok(serde_json::from_value(json)?)
+}
+
+fn retain_parsable<T: DeserializeOwned>(json: &mut Value, key: &str) {
+    if let Some(Value::Object(map)) = json.get_mut(key) {
+        map.retain(|_, value| serde_json::from_value::<T>(value.clone()).is_ok());
+    }
+}
@@ -3,12 +3,11 @@
 //! public-api`](https://github.com/Enselic/cargo-public-api) contains
 //! additional helpers for that.
 
-use crate::{
-    public_item::{PublicItem, PublicItemPath},
-    PublicApi,
-};
+use crate::plain_text;
+use crate::public_item::{PublicItem, PublicItemPath};
+use crate::tokens::Token;
 use hashbag::HashBag;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 type ItemsWithPath = HashMap<PublicItemPath, Vec<PublicItem>>;
 
@@ -23,6 +22,81 @@ pub struct ChangedPublicItem {
     pub new: PublicItem,
 }
 
+impl ChangedPublicItem {
+    /// Diffs the tokens of the old and the new item, so that you can for
+    /// example highlight exactly what changed when you render the item. The
+    /// diff is based on the longest common subsequence of the two token
+    /// streams. Tokens in the returned diff come in the order they shall be
+    /// rendered.
+    #[must_use]
+    pub fn token_diff(&self) -> Vec<TokenDiff> {
+        let old_tokens: Vec<&Token> = self.old.tokens().collect();
+        let new_tokens: Vec<&Token> = self.new.tokens().collect();
+        diff::slice(&old_tokens, &new_tokens)
+            .into_iter()
+            .map(|result| match result {
+                diff::Result::Left(&token) => TokenDiff::Removed(token.clone()),
+                diff::Result::Both(&token, _) => TokenDiff::Unchanged(token.clone()),
+                diff::Result::Right(&token) => TokenDiff::Added(token.clone()),
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the item became `#[non_exhaustive]`. That is a
+    /// breaking change even if nothing else changed, since downstream crates
+    /// can no longer construct the item with a struct expression or match on
+    /// it exhaustively.
+    #[must_use]
+    pub fn became_non_exhaustive(&self) -> bool {
+        !is_non_exhaustive(&self.old) && is_non_exhaustive(&self.new)
+    }
+}
+
+fn is_non_exhaustive(item: &PublicItem) -> bool {
+    item.tokens()
+        .any(|token| matches!(token, Token::Annotation(attr) if attr == "#[non_exhaustive]"))
+}
+
+/// A token in the diff between the old and the new version of a
+/// [`ChangedPublicItem`]. See [`ChangedPublicItem::token_diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TokenDiff {
+    /// The token is part of both the old and the new item.
+    Unchanged(Token),
+
+    /// The token is only part of the old item.
+    Removed(Token),
+
+    /// The token is only part of the new item.
+    Added(Token),
+}
+
+/// The kind of version bump that a [`PublicApiDiff`] requires, in semver
+/// terminology. See [`PublicApiDiff::semver_bump`]. Ordered from the smallest
+/// to the largest bump, so that e.g. `bump >= SemverBump::Minor` can be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SemverBump {
+    /// The public API is unchanged.
+    Patch,
+
+    /// Items were only added to the public API.
+    Minor,
+
+    /// Items were removed, changed or moved.
+    Major,
+}
+
+/// Renders e.g. `major`.
+impl std::fmt::Display for SemverBump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Patch => "patch",
+            Self::Minor => "minor",
+            Self::Major => "major",
+        })
+    }
+}
+
 /// The return value of [`Self::between`]. To quickly get a sense of what it
 /// contains, you can pretty-print it:
 /// ```txt
@@ -30,6 +104,7 @@ pub struct ChangedPublicItem {
 /// ```
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive] // More kinds of changes might be added in the future
 pub struct PublicApiDiff {
     /// Items that have been removed from the public API. A MAJOR change, in
     /// semver terminology. Sorted.
@@ -44,20 +119,35 @@ pub struct PublicApiDiff {
     /// Items that have been added to public API. A MINOR change, in semver
     /// terminology. Sorted.
     pub added: Vec<PublicItem>,
+
+    /// Items that have been moved to a different path, for example to a
+    /// different module, but that otherwise look the same. The first item of
+    /// each pair is the item at its old path, and the second item is the item
+    /// at its new path. A MAJOR change, in semver terminology, since the item
+    /// is no longer available at its old path. Sorted.
+    ///
+    /// Moved items are not part of [`Self::removed`] or [`Self::added`], so
+    /// look here too if you want all items that are no longer available at
+    /// some path, or that became available at a new path.
+    pub moved: Vec<(PublicItem, PublicItem)>,
 }
 
 impl PublicApiDiff {
     /// Allows you to diff the public API between two arbitrary versions of a
     /// library, e.g. different releases. The input parameters `old` and `new`
     /// is the output of two different invocations of
-    /// [`crate::PublicApi::from_rustdoc_json_str`].
+    /// [`crate::PublicApi::from_rustdoc_json_str`]. You can also pass the
+    /// items you got from [`crate::PublicApi::into_items()`].
     #[must_use]
-    pub fn between(old: PublicApi, new: PublicApi) -> Self {
+    pub fn between(
+        old: impl IntoIterator<Item = PublicItem>,
+        new: impl IntoIterator<Item = PublicItem>,
+    ) -> Self {
         // We must use a HashBag, because with a HashSet we would lose public
         // items that happen to have the same representation due to limitations
         // or bugs
-        let old = old.into_items().collect::<HashBag<_>>();
-        let new = new.into_items().collect::<HashBag<_>>();
+        let old = old.into_iter().collect::<HashBag<_>>();
+        let new = new.into_iter().collect::<HashBag<_>>();
 
         // First figure out what items have been removed and what have been
         // added. Later we will match added and removed items with the same path
@@ -98,6 +188,10 @@ impl PublicApiDiff {
             }
         }
 
+        // Among what remains, an item that was removed from one path and added
+        // at another path with the same signature has moved
+        let moved = extract_moved(&mut removed, &mut added);
+
         // Make output predictable and stable
         removed.sort();
         changed.sort();
@@ -107,14 +201,158 @@ impl PublicApiDiff {
             removed,
             changed,
             added,
+            moved,
+        }
+    }
+
+    /// Diffs a baseline plain-text listing, as printed by `cargo public-api`,
+    /// against a freshly built public API. Useful if you commit a listing of
+    /// your public API and want to diff against it without git.
+    ///
+    /// The `new` items are rendered and parsed back the same way as the
+    /// baseline, so that items that render the same are considered equal. See
+    /// [`crate::PublicApi::from_plain_text`] for caveats.
+    #[must_use]
+    pub fn between_baseline(baseline: &str, new: impl IntoIterator<Item = PublicItem>) -> Self {
+        let old = plain_text::parse_listing(baseline);
+        let new = new
+            .into_iter()
+            .map(|item| plain_text::parse_item(&item.to_string()));
+        Self::between(old, new)
+    }
+
+    /// The version bump that the diff requires, in semver terminology. Note
+    /// that every changed item is considered to require a MAJOR bump, even
+    /// though some changes, such as `ExplicitType` becoming `Self`, are
+    /// harmless. For `0.x.y` versions, cargo considers a MINOR bump to be
+    /// semver incompatible, so adjust accordingly.
+    #[must_use]
+    pub fn semver_bump(&self) -> SemverBump {
+        if !self.removed.is_empty() || !self.changed.is_empty() || !self.moved.is_empty() {
+            SemverBump::Major
+        } else if !self.added.is_empty() {
+            SemverBump::Minor
+        } else {
+            SemverBump::Patch
         }
     }
 
     /// Check whether the diff is empty
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.removed.is_empty() && self.changed.is_empty() && self.added.is_empty()
+        self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.added.is_empty()
+            && self.moved.is_empty()
+    }
+}
+
+/// Removes items with the same [`signature`] from `removed` and `added` and
+/// returns them as pairs of moved items, sorted.
+///
+/// An item in a removed or added item other than a module, e.g. a field of a
+/// removed struct, only counts as moved if the item it is in moved too.
+/// Otherwise the fields of two unrelated structs would pair up just because
+/// they have the same name and type.
+fn extract_moved(
+    removed: &mut Vec<PublicItem>,
+    added: &mut Vec<PublicItem>,
+) -> Vec<(PublicItem, PublicItem)> {
+    let removed_parents = non_module_paths(removed);
+    let added_parents = non_module_paths(added);
+
+    let mut removed_signatures: HashMap<Vec<Token>, Vec<PublicItem>> = HashMap::new();
+    let mut still_removed = vec![];
+    for old in removed.drain(..) {
+        match signature(&old) {
+            Some(signature) => removed_signatures.entry(signature).or_default().push(old),
+            None => still_removed.push(old),
+        }
+    }
+
+    // Pair items before the items in them, so that we know if their parent
+    // moved
+    added.sort_by_key(|item| item.sortable_path.len());
+
+    let mut moved: Vec<(PublicItem, PublicItem)> = vec![];
+    let mut still_added = vec![];
+    for new in added.drain(..) {
+        let new_parent = parent_path(&new);
+        let parents_moved_together = |old: &PublicItem| {
+            let old_parent = parent_path(old);
+            match (
+                removed_parents.contains(old_parent),
+                added_parents.contains(new_parent),
+            ) {
+                (false, false) => true,
+                (true, true) => moved.iter().any(|(moved_old, moved_new)| {
+                    moved_old.sortable_path == old_parent && moved_new.sortable_path == new_parent
+                }),
+                _ => false,
+            }
+        };
+        let old = signature(&new)
+            .and_then(|signature| removed_signatures.get_mut(&signature))
+            .and_then(|candidates| {
+                let index = candidates.iter().rposition(parents_moved_together)?;
+                Some(candidates.remove(index))
+            });
+        match old {
+            Some(old) => moved.push((old, new)),
+            None => still_added.push(new),
+        }
     }
+
+    still_removed.extend(removed_signatures.into_values().flatten());
+    *removed = still_removed;
+    *added = still_added;
+
+    moved.sort();
+    moved
+}
+
+/// The paths of the items that are not modules, see [`extract_moved`].
+fn non_module_paths(items: &[PublicItem]) -> HashSet<PublicItemPath> {
+    items
+        .iter()
+        .filter(|item| !item.tokens.contains(&Token::kind("mod")))
+        .map(|item| item.sortable_path.clone())
+        .collect()
+}
+
+/// The path of the item that the item is in, e.g. `["krate", "Struct"]` for a
+/// field `krate::Struct::field`.
+fn parent_path(item: &PublicItem) -> &[String] {
+    let path = &item.sortable_path;
+    &path[..path.len().saturating_sub(1)]
+}
+
+/// The tokens of an item, but with its path replaced by its name, e.g. `pub fn
+/// function(a: usize)` for `pub fn krate::module::function(a: usize)`. Items
+/// that belong to an `impl` have no signature, since they can't move without
+/// their `impl`.
+fn signature(item: &PublicItem) -> Option<Vec<Token>> {
+    if item.impl_kind.is_some() {
+        return None;
+    }
+
+    let tokens = &item.tokens;
+    let start = tokens.iter().position(|token| {
+        matches!(
+            token,
+            Token::Identifier(_) | Token::Type(_) | Token::Function(_)
+        )
+    })?;
+    let mut end = start + 1;
+    while matches!(tokens.get(end), Some(Token::Symbol(symbol)) if symbol == "::")
+        && end + 1 < tokens.len()
+    {
+        end += 2;
+    }
+
+    let mut signature = tokens[..start].to_vec();
+    signature.extend_from_slice(&tokens[end - 1..]);
+    Some(signature)
 }
 
 /// Converts a set (read: bag) of public items into a hash map that maps a given
@@ -132,7 +370,7 @@ fn bag_to_path_map<'a>(difference: impl Iterator<Item = (&'a PublicItem, usize)>
 
 #[cfg(test)]
 mod tests {
-    use crate::tokens::Token;
+    use crate::PublicApi;
 
     use super::*;
 
@@ -146,6 +384,7 @@ mod tests {
             removed: vec![item_with_path("foo")],
             changed: vec![],
             added: vec![],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
@@ -161,11 +400,30 @@ mod tests {
             removed: vec![],
             changed: vec![],
             added: vec![item_with_path("foo")],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
     }
 
+    #[test]
+    fn semver_bump() {
+        let bump = |old: &[&str], new: &[&str]| {
+            PublicApiDiff::between(
+                api(old.iter().map(|path| item_with_path(path))),
+                api(new.iter().map(|path| item_with_path(path))),
+            )
+            .semver_bump()
+        };
+
+        assert_eq!(bump(&["foo"], &["foo"]), SemverBump::Patch);
+        assert_eq!(bump(&["foo"], &["foo", "bar"]), SemverBump::Minor);
+        assert_eq!(bump(&["foo", "bar"], &["foo"]), SemverBump::Major);
+        assert_eq!(bump(&["foo"], &["bar"]), SemverBump::Major);
+        assert!(SemverBump::Patch < SemverBump::Minor);
+        assert!(SemverBump::Minor < SemverBump::Major);
+    }
+
     #[test]
     fn middle_item_added() {
         let old = api([item_with_path("1"), item_with_path("3")]);
@@ -180,6 +438,7 @@ mod tests {
             removed: vec![],
             changed: vec![],
             added: vec![item_with_path("2")],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
@@ -199,6 +458,7 @@ mod tests {
             removed: vec![item_with_path("2")],
             changed: vec![],
             added: vec![],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
@@ -244,6 +504,7 @@ mod tests {
                 },
             ],
             added: vec![item_with_path("4"), item_with_path("4")],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
@@ -271,12 +532,81 @@ mod tests {
             removed: vec![],
             changed: vec![],
             added: vec![fn_with_param_type(&["a", "b"], "u8")],
+            moved: vec![],
         };
         let actual = PublicApiDiff::between(old, new);
         assert_eq!(actual, expected);
         assert!(!actual.is_empty());
     }
 
+    #[test]
+    fn item_moved_to_other_module() {
+        let old = api([item_with_path("a"), fn_with_param_type(&["a", "f"], "i32")]);
+        let new = api([
+            item_with_path("a"),
+            item_with_path("b"),
+            fn_with_param_type(&["a", "b", "f"], "i32"),
+        ]);
+
+        let actual = PublicApiDiff::between(old, new);
+        let expected = PublicApiDiff {
+            removed: vec![],
+            changed: vec![],
+            added: vec![item_with_path("b")],
+            moved: vec![(
+                fn_with_param_type(&["a", "f"], "i32"),
+                fn_with_param_type(&["a", "b", "f"], "i32"),
+            )],
+        };
+        assert_eq!(actual, expected);
+        assert!(!actual.is_empty());
+    }
+
+    #[test]
+    fn item_with_other_signature_is_not_moved() {
+        let old = api([fn_with_param_type(&["a", "f"], "i32")]);
+        let new = api([fn_with_param_type(&["b", "f"], "i64")]);
+
+        let actual = PublicApiDiff::between(old, new);
+        let expected = PublicApiDiff {
+            removed: vec![fn_with_param_type(&["a", "f"], "i32")],
+            changed: vec![],
+            added: vec![fn_with_param_type(&["b", "f"], "i64")],
+            moved: vec![],
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn field_of_unrelated_item_is_not_moved() {
+        let diff = PublicApiDiff::between(
+            PublicApi::from_plain_text(
+                "pub struct krate::A\npub struct field krate::A::x: u8\npub enum krate::E\npub enum variant krate::E::V",
+            ),
+            PublicApi::from_plain_text(
+                "pub struct krate::B\npub struct field krate::B::x: u8\npub enum krate::F\npub enum variant krate::F::V",
+            ),
+        );
+        assert_eq!(diff.removed.len(), 4);
+        assert_eq!(diff.added.len(), 4);
+        assert!(diff.moved.is_empty());
+    }
+
+    #[test]
+    fn field_of_moved_item_is_moved() {
+        let diff = PublicApiDiff::between(
+            PublicApi::from_plain_text(
+                "pub mod krate::a\npub struct krate::a::S\npub struct field krate::a::S::x: u8",
+            ),
+            PublicApi::from_plain_text(
+                "pub mod krate::b\npub struct krate::b::S\npub struct field krate::b::S::x: u8",
+            ),
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.moved.len(), 2);
+    }
+
     #[test]
     fn no_diff_means_empty_diff() {
         let old = api([item_with_path("foo")]);
@@ -287,11 +617,45 @@ mod tests {
             removed: vec![],
             changed: vec![],
             added: vec![],
+            moved: vec![],
         };
         assert_eq!(actual, expected);
         assert!(actual.is_empty());
     }
 
+    #[test]
+    fn token_diff_marks_only_changed_token() {
+        let changed = ChangedPublicItem {
+            old: fn_with_param_type(&["a", "b"], "i32"),
+            new: fn_with_param_type(&["a", "b"], "i64"),
+        };
+        let diff = changed.token_diff();
+
+        let changed_tokens: Vec<_> = diff
+            .iter()
+            .filter(|token_diff| !matches!(token_diff, TokenDiff::Unchanged(_)))
+            .collect();
+        assert_eq!(
+            changed_tokens,
+            vec![&TokenDiff::Removed(t("i32")), &TokenDiff::Added(t("i64"))]
+        );
+        assert_eq!(diff.len(), changed.old.tokens().count() + 1);
+    }
+
+    #[test]
+    fn became_non_exhaustive() {
+        let diff = PublicApiDiff::between(
+            PublicApi::from_plain_text("pub enum krate::E\n#[non_exhaustive] pub struct krate::S"),
+            PublicApi::from_plain_text(
+                "#[non_exhaustive] pub enum krate::E\n#[repr(C)] #[non_exhaustive] pub struct krate::S",
+            ),
+        );
+        assert_eq!(diff.changed.len(), 2);
+        assert!(diff.changed[0].became_non_exhaustive());
+        assert!(!diff.changed[1].became_non_exhaustive());
+        assert_eq!(diff.semver_bump(), SemverBump::Major);
+    }
+
     fn item_with_path(path_str: &str) -> PublicItem {
         new_public_item(
             path_str
@@ -306,6 +670,12 @@ mod tests {
         PublicApi {
             items: items.into_iter().collect(),
             missing_item_ids: vec![],
+            public_dependencies: vec![],
+            format_adapter: None,
+            crate_docs: None,
+            crate_name: None,
+            crate_version: None,
+            item_count: 0,
         }
     }
 
@@ -335,6 +705,10 @@ mod tests {
         PublicItem {
             sortable_path: path,
             tokens,
+            impl_kind: None,
+            source_location: None,
+            generics: vec![],
+            docs: None,
         }
     }
 
@@ -10,6 +10,44 @@ pub enum Error {
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::Error),
 
+    /// Occurs if the rustdoc JSON you provide can't be parsed and its format
+    /// version differs from the one this library expects. Set
+    /// [`crate::Options::format_version`] to the `actual` version to attempt
+    /// best-effort parsing anyway.
+    #[error(
+        "rustdoc JSON format version {actual} is not supported (expected {expected}): {source}"
+    )]
+    UnsupportedFormatVersion {
+        /// The format version that we expected.
+        expected: u32,
+
+        /// The format version found in the rustdoc JSON.
+        actual: u32,
+
+        /// The error that occurred when parsing the rustdoc JSON.
+        source: serde_json::Error,
+    },
+
+    /// Occurs if the rustdoc JSON you provide has the format version that
+    /// this library expects, but still can't be parsed. Typically because a
+    /// new nightly changed the rustdoc JSON format without bumping the format
+    /// version.
+    #[error("rustdoc JSON could not be parsed at `{path}`: {source}")]
+    InvalidRustdocJson {
+        /// Where in the rustdoc JSON parsing failed, e.g. `index.0:42`
+        /// followed by the path within that item, if known.
+        path: String,
+
+        /// The error that occurred when parsing the rustdoc JSON.
+        source: serde_json::Error,
+    },
+
+    /// Occurs if the rustdoc JSON you provide ends in the middle of the
+    /// document or lacks the top-level keys of rustdoc JSON, typically because
+    /// the build that produced it was interrupted.
+    #[error("rustdoc JSON looks truncated or corrupt, try to build it again: {0}")]
+    CorruptRustdocJson(serde_json::Error),
+
     /// Some kind of IO error occurred. For example, we might not have read
     /// permissions on the rustdoc JSON input file.
     #[error(transparent)]
@@ -0,0 +1,96 @@
+//! Adapters that convert rustdoc JSON of an older format version into the
+//! format version we support, i.e. [`rustdoc_types::FORMAT_VERSION`]. This
+//! allows us to keep parsing the rustdoc JSON of a slightly older nightly
+//! toolchain rather than hard-failing.
+
+use serde_json::{json, Value};
+
+/// Converts rustdoc JSON of format version `from` to `from + 1`.
+pub(crate) struct FormatAdapter {
+    from: u32,
+    adapt: fn(&mut Value),
+}
+
+impl FormatAdapter {
+    /// Human readable description of the adapter, e.g. for `--verbose` output.
+    pub(crate) fn description(&self) -> String {
+        format!(
+            "rustdoc JSON format version {} adapted to format version {}",
+            self.from,
+            self.from + 1
+        )
+    }
+
+    /// Adapts the JSON and bumps its `format_version`.
+    pub(crate) fn adapt(&self, json: &mut Value) {
+        (self.adapt)(json);
+        json["format_version"] = json!(self.from + 1);
+    }
+}
+
+/// Adapters for previous format versions. Each adapter converts to the next
+/// format version, which must be the format version we support.
+const ADAPTERS: &[FormatAdapter] = &[FormatAdapter {
+    from: 21,
+    adapt: adapt_v21,
+}];
+
+/// Returns the adapter that converts `format_version` to the format version we
+/// support, if there is one.
+pub(crate) fn for_format_version(format_version: u32) -> Option<&'static FormatAdapter> {
+    ADAPTERS.iter().find(|adapter| {
+        adapter.from == format_version && adapter.from + 1 == rustdoc_types::FORMAT_VERSION
+    })
+}
+
+/// In format version 22, `"kind": "primitive_type", "inner": "u8"` became
+/// `"kind": "primitive", "inner": { "name": "u8", "impls": [...] }`. Format
+/// version 21 does not tell us what impls a primitive has, so we leave them
+/// empty.
+fn adapt_v21(json: &mut Value) {
+    let Some(Value::Object(index)) = json.get_mut("index") else {
+        return;
+    };
+
+    for item in index.values_mut() {
+        if item["kind"] == "primitive_type" {
+            let name = item["inner"].take();
+            item["kind"] = json!("primitive");
+            item["inner"] = json!({ "name": name, "impls": [] });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapts_primitive_type() {
+        let mut json = json!({
+            "index": {
+                "0:1": { "kind": "primitive_type", "inner": "u8" },
+                "0:2": { "kind": "module", "inner": {} },
+            },
+            "format_version": 21,
+        });
+
+        for_format_version(21).unwrap().adapt(&mut json);
+
+        assert_eq!(
+            json,
+            json!({
+                "index": {
+                    "0:1": { "kind": "primitive", "inner": { "name": "u8", "impls": [] } },
+                    "0:2": { "kind": "module", "inner": {} },
+                },
+                "format_version": 22,
+            })
+        );
+    }
+
+    #[test]
+    fn no_adapter_for_unknown_format_version() {
+        assert!(for_format_version(9999).is_none());
+    }
+}
@@ -0,0 +1,28 @@
+//! Lightweight check that rustdoc JSON is complete, so that a truncated or
+//! otherwise corrupt file, e.g. from an interrupted build, results in a clear
+//! error rather than a confusing parse error.
+
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// The top-level keys that rustdoc JSON of any format version has.
+#[derive(Deserialize)]
+#[allow(dead_code)] // The fields are only there to be required
+struct TopLevelKeys {
+    root: IgnoredAny,
+    index: IgnoredAny,
+    format_version: IgnoredAny,
+}
+
+/// Returns [`Error::CorruptRustdocJson`] if `rustdoc_json` ends in the middle
+/// of the document, or if it is a complete JSON document that lacks the
+/// top-level keys of rustdoc JSON. Other syntax errors are left for regular
+/// parsing to report.
+pub(crate) fn check(rustdoc_json: &[u8]) -> Result<()> {
+    match crate::deserialize_without_recursion_limit::<TopLevelKeys>(rustdoc_json) {
+        Err(error) if error.is_eof() || error.is_data() => Err(Error::CorruptRustdocJson(error)),
+        _ => Ok(()),
+    }
+}
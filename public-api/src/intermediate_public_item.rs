@@ -1,6 +1,8 @@
 use rustdoc_types::{Impl, Item, ItemEnum};
 
-use crate::{public_item::PublicItemPath, render::RenderingContext, tokens::Token};
+use crate::{
+    item_processor::ImplKind, public_item::PublicItemPath, render::RenderingContext, tokens::Token,
+};
 
 /// Wraps an [`Item`] and allows us to override its name.
 #[derive(Clone, Debug)]
@@ -91,6 +93,17 @@ impl<'c> IntermediatePublicItem<'c> {
         self.path().iter().any(|m| m.overridden_name.is_some())
     }
 
+    /// The kind of the innermost `impl` in the path, if any. For an `impl`
+    /// that is the `impl` itself, and for e.g. an `fn` in an `impl` it is the
+    /// `impl` that the `fn` belongs to.
+    #[must_use]
+    pub fn impl_kind(&self) -> Option<ImplKind> {
+        self.path().iter().rev().find_map(|p| match &p.item.inner {
+            ItemEnum::Impl(impl_) => Some(ImplKind::new(p.item, impl_)),
+            _ => None,
+        })
+    }
+
     pub fn render_token_stream(&self, context: &RenderingContext) -> Vec<Token> {
         context.token_stream(self)
     }
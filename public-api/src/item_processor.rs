@@ -32,8 +32,9 @@ struct UnprocessedItem<'c> {
 ///
 /// Note that this implementation iterates over everything, so if the rustdoc
 /// JSON is generated with `--document-private-items`, then private items will
-/// also be included in the output. Use with `--document-private-items` is not
-/// supported.
+/// also be included in the output. Items with restricted visibility, such as
+/// `pub(crate)` items, are rendered with their visibility, but apart from that,
+/// use with `--document-private-items` is not supported.
 pub struct ItemProcessor<'c> {
     /// The original and unmodified rustdoc JSON, in deserialized form.
     crate_: CrateWrapper<'c>,
@@ -170,7 +171,7 @@ impl<'c> ItemProcessor<'c> {
         item: &'c Item,
         impl_: &'c Impl,
     ) {
-        if !ImplKind::from(impl_).is_active(self.options) {
+        if !ImplKind::new(item, impl_).is_active(self.options) {
             return;
         }
 
@@ -289,8 +290,8 @@ pub(crate) fn sorting_prefix(item: &Item) -> u8 {
 
         ItemEnum::Typedef(_) => 19,
 
-        ItemEnum::Impl(impl_) => match ImplKind::from(impl_) {
-            ImplKind::Normal => 20,
+        ItemEnum::Impl(impl_) => match ImplKind::new(item, impl_) {
+            ImplKind::Inherent | ImplKind::Trait | ImplKind::AutoDerived => 20,
             ImplKind::AutoTrait => 21,
             ImplKind::Blanket => 22,
         },
@@ -303,31 +304,51 @@ pub(crate) fn sorting_prefix(item: &Item) -> u8 {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum ImplKind {
-    Normal,
-    AutoTrait,
+/// The kind of an `impl`. See [`PublicItem::impl_kind()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive] // More kinds might be distinguished in the future
+pub enum ImplKind {
+    /// An inherent impl, e.g. `impl Foo`.
+    Inherent,
+
+    /// A regular trait impl, e.g. `impl Display for Foo`.
+    Trait,
+
+    /// A Blanket Implementation, e.g. `impl<T> Any for T`.
     Blanket,
+
+    /// An Auto Trait Implementation, e.g. `impl Send for Foo`.
+    AutoTrait,
+
+    /// A trait impl generated by `#[derive(...)]`, e.g. `impl Debug for Foo`
+    /// from `#[derive(Debug)]`.
+    AutoDerived,
 }
 
-impl From<&Impl> for ImplKind {
-    fn from(impl_: &Impl) -> Self {
+impl ImplKind {
+    pub(crate) fn new(item: &Item, impl_: &Impl) -> Self {
         let has_blanket_impl = matches!(impl_.blanket_impl, Some(_));
+        let is_automatically_derived = item
+            .attrs
+            .iter()
+            .any(|attr| attr == "#[automatically_derived]");
 
         // See https://github.com/rust-lang/rust/blob/54f20bbb8a7aeab93da17c0019c1aaa10329245a/src/librustdoc/json/conversions.rs#L589-L590
         match (impl_.synthetic, has_blanket_impl) {
             (true, false) => ImplKind::AutoTrait,
             (false, true) => ImplKind::Blanket,
-            _ => ImplKind::Normal,
+            _ if impl_.trait_.is_none() => ImplKind::Inherent,
+            _ if is_automatically_derived => ImplKind::AutoDerived,
+            _ => ImplKind::Trait,
         }
     }
-}
 
-impl ImplKind {
-    fn is_active(&self, options: Options) -> bool {
+    fn is_active(self, options: Options) -> bool {
         match self {
-            ImplKind::Blanket | ImplKind::AutoTrait => !options.simplified,
-            ImplKind::Normal => true,
+            ImplKind::Blanket => !options.simplified || options.include_blanket_impls,
+            ImplKind::AutoTrait => !options.simplified,
+            ImplKind::AutoDerived => !options.omit_auto_derived_impls,
+            ImplKind::Inherent | ImplKind::Trait => true,
         }
     }
 }
@@ -362,6 +383,69 @@ pub fn impls_for_item(item: &Item) -> Option<&[Id]> {
 }
 
 pub fn public_api_in_crate(crate_: &Crate, options: Options) -> super::PublicApi {
+    let mut items = vec![];
+    let public_api = for_each_unsorted_public_item_in_crate(crate_, options, |item| {
+        items.push(item);
+    });
+
+    PublicApi {
+        items,
+        ..public_api
+    }
+}
+
+/// Like [`public_api_in_crate`], but instead of being collected, each item is
+/// passed to `on_item` as soon as it has been rendered. If [`Options::sorted`]
+/// is set, items are passed in sorted order. Rendering is the expensive part,
+/// but the paths that items are primarily sorted by are known up front, so
+/// only items that share a path need to be rendered before they can be sorted
+/// and passed on. The returned [`PublicApi`] has no items.
+pub fn for_each_public_item_in_crate(
+    crate_: &Crate,
+    options: Options,
+    mut on_item: impl FnMut(PublicItem),
+) -> super::PublicApi {
+    if !options.sorted {
+        return for_each_unsorted_public_item_in_crate(crate_, options, on_item);
+    }
+
+    with_processed_crate(crate_, options, |context, output| {
+        let mut by_path: Vec<_> = output
+            .iter()
+            .map(|item| (item.sortable_path(), item))
+            .collect();
+        by_path.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for group in by_path.chunk_by(|(a, _), (b, _)| a == b) {
+            let mut items: Vec<_> = group
+                .iter()
+                .map(|(_, item)| PublicItem::from_intermediate_public_item(context, item))
+                .collect();
+            items.sort();
+            items.into_iter().for_each(&mut on_item);
+        }
+    })
+}
+
+fn for_each_unsorted_public_item_in_crate(
+    crate_: &Crate,
+    options: Options,
+    mut on_item: impl FnMut(PublicItem),
+) -> super::PublicApi {
+    with_processed_crate(crate_, options, |context, output| {
+        for item in output {
+            on_item(PublicItem::from_intermediate_public_item(context, item));
+        }
+    })
+}
+
+/// Finds all public items in `crate_` and lets `render` render them. Returns
+/// everything but the items themselves.
+fn with_processed_crate(
+    crate_: &Crate,
+    options: Options,
+    render: impl FnOnce(&RenderingContext, &[IntermediatePublicItem]),
+) -> super::PublicApi {
     let mut item_processor = ItemProcessor::new(crate_, options);
     item_processor.add_to_work_queue(vec![], &crate_.root);
     item_processor.run();
@@ -369,15 +453,28 @@ pub fn public_api_in_crate(crate_: &Crate, options: Options) -> super::PublicApi
     let context = RenderingContext {
         crate_,
         id_to_items: item_processor.id_to_items(),
+        external_items_by_name: if options.qualify_external {
+            crate::render::external_items_by_name(crate_)
+        } else {
+            HashMap::new()
+        },
         options,
     };
+    render(&context, &item_processor.output);
 
+    let root = crate_.index.get(&crate_.root);
     PublicApi {
-        items: item_processor
-            .output
-            .iter()
-            .map(|item| PublicItem::from_intermediate_public_item(&context, item))
-            .collect::<Vec<_>>(),
+        items: vec![],
         missing_item_ids: item_processor.crate_.missing_item_ids(),
+        public_dependencies: if options.public_dependencies {
+            crate::public_dependencies::public_dependencies(crate_, &item_processor.output)
+        } else {
+            vec![]
+        },
+        format_adapter: None,
+        crate_docs: root.and_then(|root| root.docs.clone()),
+        crate_name: root.and_then(|root| root.name.clone()),
+        crate_version: crate_.crate_version.clone(),
+        item_count: item_processor.output.len(),
     }
 }
@@ -42,23 +42,38 @@
 // deny in CI, only warn here
 #![warn(clippy::all, clippy::pedantic, missing_docs)]
 
+mod best_effort;
 mod crate_wrapper;
 mod error;
+mod format_adapter;
+mod integrity;
 mod intermediate_public_item;
 mod item_processor;
+mod plain_text;
+mod public_dependencies;
 mod public_item;
 mod render;
+mod streaming;
 pub mod tokens;
 
 pub mod diff;
 
+pub mod sorting;
+
+pub mod metrics;
+
+pub mod module_tree;
+
 use std::path::Path;
 
 // Documented at the definition site so cargo doc picks it up
 pub use error::{Error, Result};
 
 // Documented at the definition site so cargo doc picks it up
-pub use public_item::PublicItem;
+pub use public_item::{GenericParam, GenericParamKind, PublicItem, SourceLocation};
+
+// Documented at the definition site so cargo doc picks it up
+pub use item_processor::ImplKind;
 
 /// This constant defines the minimum version of nightly that is required in
 /// order for the rustdoc JSON output to be parsable by this library. Note that
@@ -112,6 +127,85 @@ pub struct Options {
     /// The default value is `false` so that the listed public API is complete
     /// by default.
     pub simplified: bool,
+
+    /// If `true`, items that belong to Blanket Implementations are included
+    /// even if [`Self::simplified`] is `true`. Useful when diffing, since a
+    /// change to the bounds of a blanket impl can affect downstream crates.
+    ///
+    /// The default value is `false`
+    pub include_blanket_impls: bool,
+
+    /// If `true`, items that belong to impls generated by `#[derive(...)]`, e.g.
+    /// `impl Debug for Foo` from `#[derive(Debug)]`, are omitted from the
+    /// output. Derived impls are recognized by their
+    /// `#[automatically_derived]` attribute, see `ImplKind::AutoDerived`.
+    ///
+    /// The default value is `false`
+    pub omit_auto_derived_impls: bool,
+
+    /// If `true`, references to items in other crates that can't be resolved
+    /// by their rustdoc JSON Id are looked up by name in the path index of the
+    /// rustdoc JSON. If exactly one external item matches, it is rendered with
+    /// its full path, e.g. `alloc::vec::Vec` instead of `Vec`. Otherwise the
+    /// name is rendered as it appears in the source code.
+    ///
+    /// The default value is `false`
+    pub qualify_external: bool,
+
+    /// If `true`, the values of `const` and `static` items are rendered, e.g.
+    /// `pub const krate::MAX: u32 = 100`. The value is rendered the way it is
+    /// written in the source code if rustdoc JSON provides that, otherwise
+    /// the evaluated value is rendered. Enum discriminants are always
+    /// rendered.
+    ///
+    /// The default value is `false`, since values are typically not part of
+    /// the public API that you want to review.
+    pub show_values: bool,
+
+    /// If `true`, trait methods that have a default implementation, so called
+    /// provided methods, are rendered with a `{ ... }` body, like rustdoc does,
+    /// e.g. `pub fn krate::Trait::provided(&self) { ... }`. Methods that
+    /// implementors must implement themselves are rendered without a body.
+    ///
+    /// The default value is `false`
+    pub mark_provided_methods: bool,
+
+    /// If `true`, items are annotated with the `#[cfg(...)]` predicate they
+    /// require, e.g. `#[cfg(feature = "x")] pub fn krate::f()`. Both
+    /// `#[cfg(...)]` and `#[doc(cfg(...))]` attributes are rendered, the
+    /// latter as `#[cfg(...)]`. Note that only items whose cfg is active when
+    /// building the rustdoc JSON are part of it in the first place.
+    ///
+    /// The default value is `false`
+    pub show_cfgs: bool,
+
+    /// If `true`, paths to items of the crate itself are rendered relative to
+    /// the crate root, i.e. without the crate name, e.g. `pub fn
+    /// module::function(x: Struct)` instead of `pub fn
+    /// krate::module::function(x: krate::Struct)`. Paths to items of other
+    /// crates keep their crate name. The crate root module is still rendered
+    /// as `pub mod krate`.
+    ///
+    /// The default value is `false`
+    pub relative_paths: bool,
+
+    /// If `true`, the dependency crates that are part of the public API are
+    /// found, see [`PublicApi::public_dependencies()`]. This requires an
+    /// extra pass over all public items.
+    ///
+    /// The default value is `false`
+    pub public_dependencies: bool,
+
+    /// If set, and the rustdoc JSON fails to parse, attempt best-effort
+    /// parsing as long as the `format_version` of the rustdoc JSON equals this
+    /// value. Items that can't be parsed are skipped and end up in
+    /// [`PublicApi::missing_item_ids()`]. Intended for experimenting with new
+    /// nightly toolchains. The resulting public API might be incomplete.
+    ///
+    /// The default value is `None`, which means rustdoc JSON with an
+    /// unexpected format version results in
+    /// [`Error::UnsupportedFormatVersion`].
+    pub format_version: Option<u32>,
 }
 
 /// Enables options to be set up like this (note that `Options` is marked
@@ -131,6 +225,15 @@ impl Default for Options {
             sorted: true,
             debug_sorting: false,
             simplified: false,
+            include_blanket_impls: false,
+            omit_auto_derived_impls: false,
+            qualify_external: false,
+            show_values: false,
+            mark_provided_methods: false,
+            show_cfgs: false,
+            relative_paths: false,
+            public_dependencies: false,
+            format_version: None,
         }
     }
 }
@@ -170,13 +273,32 @@ pub struct PublicApi {
 
     /// See [`Self::missing_item_ids()`]
     pub(crate) missing_item_ids: Vec<String>,
+
+    /// See [`Self::public_dependencies()`]
+    pub(crate) public_dependencies: Vec<String>,
+
+    /// See [`Self::format_adapter()`]
+    pub(crate) format_adapter: Option<String>,
+
+    /// See [`Self::crate_docs()`]
+    pub(crate) crate_docs: Option<String>,
+
+    /// See [`Self::crate_info()`]
+    pub(crate) crate_name: Option<String>,
+
+    /// See [`Self::crate_info()`]
+    pub(crate) crate_version: Option<String>,
+
+    /// See [`Self::crate_info()`]. Unlike `items.len()`, also known for a
+    /// [`PublicApi`] returned by [`Self::for_each_item_in_rustdoc_json`].
+    pub(crate) item_count: usize,
 }
 
 impl PublicApi {
     /// Takes a [`Path`] to a rustdoc JSON file and returns a [`PublicApi`] with
     /// [`PublicItem`]s where each [`PublicItem`] is one public item of the
     /// crate, i.e. part of the crate's public API. Use [`Self::items()`] or
-    /// `[Self::into_items()` to get the items.
+    /// [`Self::into_items()`] to get the items.
     ///
     /// There exists a convenient `cargo public-api` subcommand wrapper for this
     /// function found at <https://github.com/Enselic/cargo-public-api> that
@@ -199,11 +321,39 @@ impl PublicApi {
     ///
     /// E.g. if the JSON is invalid or if the file can't be read.
     pub fn from_rustdoc_json(path: impl AsRef<Path>, options: Options) -> Result<PublicApi> {
-        Self::from_rustdoc_json_str(&std::fs::read_to_string(path)?, options)
+        let (crate_, format_adapter) = streaming::read_crate(path.as_ref(), options)?;
+        Ok(Self::from_crate(&crate_, options, format_adapter))
+    }
+
+    /// Same as [`Self::from_rustdoc_json`], but instead of being collected,
+    /// each item is passed to `on_item` as soon as it has been rendered, in
+    /// the same order as [`Self::items`] would have them. For crates with very
+    /// many items, this makes the first items available long before all of
+    /// them have been rendered, and the rendered items never need to be in
+    /// memory at the same time. The docs of all items are kept in memory
+    /// until all items have been rendered though, since [`PublicItem::docs()`]
+    /// needs them. The returned [`PublicApi`] has no items, but
+    /// e.g. [`Self::missing_item_ids`] works as usual.
+    ///
+    /// # Errors
+    ///
+    /// E.g. if the JSON is invalid or if the file can't be read.
+    pub fn for_each_item_in_rustdoc_json(
+        path: impl AsRef<Path>,
+        options: Options,
+        on_item: impl FnMut(PublicItem),
+    ) -> Result<PublicApi> {
+        let (crate_, format_adapter) = streaming::read_crate(path.as_ref(), options)?;
+        let mut public_api =
+            item_processor::for_each_public_item_in_crate(&crate_, options, on_item);
+        public_api.format_adapter = format_adapter;
+        Ok(public_api)
     }
 
     /// Same as [`Self::from_rustdoc_json`], but the rustdoc JSON is read from a
-    /// `&str` rather than a file.
+    /// `&str` rather than a file. Note that [`Self::from_rustdoc_json`] needs
+    /// less memory, since it does not need the whole JSON in memory at once,
+    /// which matters for very large crates.
     ///
     /// # Errors
     ///
@@ -212,15 +362,110 @@ impl PublicApi {
         rustdoc_json_str: impl AsRef<str>,
         options: Options,
     ) -> Result<PublicApi> {
-        let crate_ = deserialize_without_recursion_limit(rustdoc_json_str.as_ref())?;
+        Self::from_rustdoc_json_bytes(rustdoc_json_str.as_ref().as_bytes(), options)
+    }
 
-        let mut public_api = item_processor::public_api_in_crate(&crate_, options);
+    /// Same as [`Self::from_rustdoc_json`], but the rustdoc JSON is read from
+    /// bytes rather than a file. Useful if you already have the JSON in a
+    /// `Vec<u8>`, since the bytes do not need to be converted to a [`String`]
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// E.g. if the JSON is invalid, including if it is not valid UTF-8.
+    pub fn from_rustdoc_json_bytes(
+        rustdoc_json_bytes: impl AsRef<[u8]>,
+        options: Options,
+    ) -> Result<PublicApi> {
+        let (crate_, format_adapter) = deserialize_crate(rustdoc_json_bytes.as_ref(), options)?;
+        Ok(Self::from_crate(&crate_, options, format_adapter))
+    }
+
+    /// Same as [`Self::from_rustdoc_json`], but combines the public APIs of
+    /// several crates into one, e.g. to review the public API of a facade
+    /// crate together with the crates it aggregates. The path of each item
+    /// begins with the name of its crate, so items of different crates remain
+    /// distinguishable. [`Self::missing_item_ids`] are prefixed with the name
+    /// of their crate since IDs are only unique within a crate.
+    /// [`Self::crate_docs`] and [`Self::crate_info`] are always `None`, since
+    /// the combined API does not belong to a single crate.
+    ///
+    /// # Errors
+    ///
+    /// E.g. if any of the JSON is invalid or if any of the files can't be read.
+    pub fn from_multiple(paths: &[&Path], options: Options) -> Result<PublicApi> {
+        let mut combined = PublicApi::empty();
+        for path in paths {
+            let public_api = Self::from_rustdoc_json(path, options)?;
+            let crate_name = public_api.crate_name.unwrap_or_default();
+            combined.items.extend(public_api.items);
+            let missing_item_ids = public_api.missing_item_ids.into_iter();
+            combined
+                .missing_item_ids
+                .extend(missing_item_ids.map(|id| format!("{crate_name}/{id}")));
+            combined
+                .public_dependencies
+                .extend(public_api.public_dependencies);
+            combined.format_adapter = combined.format_adapter.or(public_api.format_adapter);
+        }
+
+        combined.public_dependencies.sort();
+        combined.public_dependencies.dedup();
+        if options.sorted {
+            combined.items.sort();
+        }
+
+        Ok(combined)
+    }
+
+    fn from_crate(
+        crate_: &rustdoc_types::Crate,
+        options: Options,
+        format_adapter: Option<String>,
+    ) -> PublicApi {
+        let mut public_api = item_processor::public_api_in_crate(crate_, options);
+        public_api.format_adapter = format_adapter;
 
         if options.sorted {
             public_api.items.sort();
         }
 
-        Ok(public_api)
+        public_api
+    }
+
+    /// Reconstructs a [`PublicApi`] from the plain-text listing that `cargo
+    /// public-api` prints, i.e. one item per line in the format of the
+    /// [`std::fmt::Display`] impl of [`PublicItem`]. This allows you to keep
+    /// a listing around as a baseline, see
+    /// [`diff::PublicApiDiff::between_baseline`].
+    ///
+    /// Note that the plain-text format is not stable. It changes when
+    /// rendering is improved, so only use listings created by the same version
+    /// of this library. Since rendering loses information, the returned items
+    /// are an approximation. For example, [`PublicItem::impl_kind()`] is
+    /// always `None` and items are not sorted the same way as in
+    /// [`Self::from_rustdoc_json_str`].
+    #[must_use]
+    pub fn from_plain_text(listing: &str) -> PublicApi {
+        PublicApi {
+            items: plain_text::parse_listing(listing),
+            ..PublicApi::empty()
+        }
+    }
+
+    /// A [`PublicApi`] without any items, to start from when combining or
+    /// reconstructing public APIs.
+    fn empty() -> PublicApi {
+        PublicApi {
+            items: vec![],
+            missing_item_ids: vec![],
+            public_dependencies: vec![],
+            format_adapter: None,
+            crate_docs: None,
+            crate_name: None,
+            crate_version: None,
+            item_count: 0,
+        }
     }
 
     /// Returns an iterator over all public items in the public API
@@ -228,10 +473,46 @@ impl PublicApi {
         self.items.iter()
     }
 
+    /// Like [`Self::items()`], but the items are in the order they are
+    /// declared in the source code, i.e. sorted by file and then by line, see
+    /// [`sorting::by_source_location`]. Items at the same location, such as
+    /// the items of a `#[derive(...)]`, keep their relative order.
+    #[must_use]
+    pub fn items_in_source_order(&self) -> Vec<&PublicItem> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by(|a, b| sorting::by_source_location(a, b));
+        items
+    }
+
+    /// Size metrics of the public API, such as the number of items of each
+    /// kind. Useful to track how the public API grows over time.
+    #[must_use]
+    pub fn metrics(&self) -> metrics::Metrics {
+        metrics::metrics(self.items.iter())
+    }
+
     /// Like [`Self::items()`], but ownership of all `PublicItem`s are
-    /// transferred to the caller.
-    pub fn into_items(self) -> impl Iterator<Item = PublicItem> {
-        self.items.into_iter()
+    /// transferred to the caller. The items can for example be passed directly
+    /// to [`diff::PublicApiDiff::between`].
+    #[must_use]
+    pub fn into_items(self) -> Vec<PublicItem> {
+        self.items
+    }
+
+    /// Sorts the items with the given [`sorting::Comparator`], for example
+    /// [`sorting::by_kind`]. Use [`Options::sorted`] if you just want the
+    /// default sorting.
+    #[must_use]
+    pub fn sorted_by(mut self, comparator: impl sorting::Comparator) -> Self {
+        self.items.sort_by(move |a, b| comparator.compare(a, b));
+        self
+    }
+
+    /// Groups the items by the module they belong to, e.g. to render the
+    /// public API as a tree rather than as a flat list.
+    #[must_use]
+    pub fn module_tree(&self) -> module_tree::ModuleNode {
+        module_tree::module_tree(&self.items)
     }
 
     /// The rustdoc JSON IDs of missing but referenced items. Intended for use
@@ -248,13 +529,195 @@ impl PublicApi {
     pub fn missing_item_ids(&self) -> impl Iterator<Item = &String> {
         self.missing_item_ids.iter()
     }
+
+    /// The names of the dependency crates that are part of the public API, in
+    /// alphabetical order. A dependency is part of the public API if one of
+    /// its items is re-exported, or if one of its items appears in the
+    /// signature of a public item, such as in the type of a function
+    /// parameter or as a trait that a public type implements. Crates that
+    /// come with the toolchain, such as `std` and `core`, are not included.
+    ///
+    /// A breaking change in a public dependency, including bumping it to a
+    /// semver incompatible version, is typically a breaking change of the
+    /// crate itself.
+    ///
+    /// Always empty unless [`Options::public_dependencies`] is set, and for a
+    /// [`PublicApi`] created by [`Self::from_plain_text`].
+    pub fn public_dependencies(&self) -> impl Iterator<Item = &String> {
+        self.public_dependencies.iter()
+    }
+
+    /// If the rustdoc JSON was of the format version just before the one this
+    /// library supports, it is adapted to the supported format version before
+    /// it is parsed. This returns a description of the adapter that was used,
+    /// if any. Intended for use with `--verbose` flags or similar.
+    ///
+    /// If best-effort parsing was used, see [`Options::format_version`], this
+    /// returns a description of that instead.
+    #[must_use]
+    pub fn format_adapter(&self) -> Option<&str> {
+        self.format_adapter.as_deref()
+    }
+
+    /// The crate-level docs, i.e. the `//!` docs of the crate root, if any.
+    ///
+    /// Always `None` for a [`PublicApi`] created by [`Self::from_plain_text`].
+    #[must_use]
+    pub fn crate_docs(&self) -> Option<&str> {
+        self.crate_docs.as_deref()
+    }
+
+    /// Summary info about the crate, such as its name and version.
+    ///
+    /// Always `None` for a [`PublicApi`] created by [`Self::from_plain_text`].
+    #[must_use]
+    pub fn crate_info(&self) -> Option<CrateInfo> {
+        Some(CrateInfo {
+            name: self.crate_name.clone()?,
+            version: self.crate_version.clone(),
+            item_count: self.item_count,
+        })
+    }
+
+    /// Returns `true` if there is an item with the given path, e.g.
+    /// `"example_api::Struct"` or `"example_api::Struct::field"`. Useful to
+    /// check that e.g. links in documentation point to public items. `impl`s
+    /// have no path, so they never match.
+    #[must_use]
+    pub fn contains_path(&self, path: &str) -> bool {
+        self.items.iter().any(|item| item.has_path(path))
+    }
+}
+
+/// Summary info about a crate. See [`PublicApi::crate_info()`]. The edition
+/// of the crate is not included, since rustdoc JSON does not record it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive] // More fields might be added in the future
+pub struct CrateInfo {
+    /// The name of the crate, e.g. `public_api`. Note that it is the name of
+    /// the library target, so `-` in the package name is replaced by `_`.
+    pub name: String,
+
+    /// The version of the crate, e.g. `0.1.0`. `cargo rustdoc` passes the
+    /// package version to rustdoc, so this is typically `Some`.
+    pub version: Option<String>,
+
+    /// The number of items in the public API. Also for a [`PublicApi`]
+    /// returned by [`PublicApi::for_each_item_in_rustdoc_json`], even though
+    /// it has no items.
+    pub item_count: usize,
+}
+
+/// Enables a [`PublicApi`] to be passed directly to
+/// [`diff::PublicApiDiff::between`].
+impl IntoIterator for PublicApi {
+    type Item = PublicItem;
+    type IntoIter = std::vec::IntoIter<PublicItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+/// Diffs the public API of two rustdoc JSON documents in one call. This is a
+/// shorthand for calling [`PublicApi::from_rustdoc_json_str`] on both `old`
+/// and `new` with the same `options` and then passing the results to
+/// [`diff::PublicApiDiff::between`].
+///
+/// # Errors
+///
+/// E.g. if either JSON is invalid.
+pub fn diff_json_strs(
+    old: impl AsRef<str>,
+    new: impl AsRef<str>,
+    options: Options,
+) -> Result<diff::PublicApiDiff> {
+    let old = PublicApi::from_rustdoc_json_str(old, options)?;
+    let new = PublicApi::from_rustdoc_json_str(new, options)?;
+    Ok(diff::PublicApiDiff::between(old, new))
+}
+
+/// Returns the items that have been added to `current` since `baseline`, for
+/// example to generate a changelog. The items are the same as
+/// [`diff::PublicApiDiff::added`] in a full diff between the two, so changed
+/// and moved items are not included. Sorted.
+#[must_use]
+pub fn added_since(baseline: &PublicApi, current: &PublicApi) -> Vec<PublicItem> {
+    diff::PublicApiDiff::between(baseline.items().cloned(), current.items().cloned()).added
+}
+
+/// Deserializes the rustdoc JSON into a [`rustdoc_types::Crate`]. If that
+/// fails, we look at the format version of the JSON to adapt it if it is of
+/// the previous format version, to give a better error, or to fall back to
+/// best-effort parsing if [`Options::format_version`] says so. Also returns a
+/// description of the format adapter that was used, if any.
+fn deserialize_crate(
+    rustdoc_json: &[u8],
+    options: Options,
+) -> Result<(rustdoc_types::Crate, Option<String>)> {
+    let error = match deserialize_without_recursion_limit(rustdoc_json) {
+        Ok(crate_) => return Ok((crate_, None)),
+        Err(error) => error,
+    };
+
+    integrity::check(rustdoc_json)?;
+
+    let Ok(best_effort::FormatVersion {
+        format_version: actual,
+    }) = deserialize_without_recursion_limit(rustdoc_json)
+    else {
+        return Err(error.into());
+    };
+
+    if options.format_version.is_none() {
+        if let Some(adapter) = format_adapter::for_format_version(actual) {
+            let mut json: serde_json::Value = deserialize_without_recursion_limit(rustdoc_json)?;
+            adapter.adapt(&mut json);
+            return Ok((serde_json::from_value(json)?, Some(adapter.description())));
+        }
+    }
+
+    let expected = options
+        .format_version
+        .unwrap_or(rustdoc_types::FORMAT_VERSION);
+    if actual != expected {
+        Err(Error::UnsupportedFormatVersion {
+            expected,
+            actual,
+            source: error,
+        })
+    } else if options.format_version.is_some() {
+        let crate_ = best_effort::deserialize(deserialize_without_recursion_limit(rustdoc_json)?)?;
+        let description = format!("best-effort parsing of rustdoc JSON format version {actual}");
+        Ok((crate_, Some(description)))
+    } else {
+        Err(error_with_json_path(rustdoc_json, error))
+    }
+}
+
+/// Deserializes the rustdoc JSON once more, but this time keeps track of where
+/// in the JSON deserialization fails, so that the error can tell e.g. what item
+/// in the `index` that rustdoc JSON from a new nightly breaks on. Falls back to
+/// `error` if the path can't be determined.
+fn error_with_json_path(rustdoc_json: &[u8], error: serde_json::Error) -> Error {
+    let mut deserializer = serde_json::Deserializer::from_slice(rustdoc_json);
+    deserializer.disable_recursion_limit();
+    match serde_path_to_error::deserialize::<_, rustdoc_types::Crate>(&mut deserializer) {
+        Err(path_error) => Error::InvalidRustdocJson {
+            path: path_error.path().to_string(),
+            source: path_error.into_inner(),
+        },
+        Ok(_) => error.into(),
+    }
 }
 
 /// Helper to deserialize the JSON with `serde_json`, but with the recursion
 /// limit disabled. Otherwise we hit the recursion limit on crates such as
 /// `diesel`.
-fn deserialize_without_recursion_limit(rustdoc_json_str: &str) -> Result<rustdoc_types::Crate> {
-    let mut deserializer = serde_json::Deserializer::from_str(rustdoc_json_str);
+fn deserialize_without_recursion_limit<T: serde::de::DeserializeOwned>(
+    rustdoc_json: &[u8],
+) -> serde_json::Result<T> {
+    let mut deserializer = serde_json::Deserializer::from_slice(rustdoc_json);
     deserializer.disable_recursion_limit();
-    Ok(serde::de::Deserialize::deserialize(&mut deserializer)?)
+    serde::de::Deserialize::deserialize(&mut deserializer)
 }
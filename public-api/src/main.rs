@@ -86,6 +86,12 @@ fn print_diff_with_headers(
         writeln!(w, "-{}", item.old)?;
         writeln!(w, "+{}", item.new)
     })?;
+    if !diff.moved.is_empty() {
+        print_items_with_header(w, "Moved:", &diff.moved, |w, (old, new)| {
+            writeln!(w, "-{old}")?;
+            writeln!(w, "+{new}")
+        })?;
+    }
     print_items_with_header(w, header_added, &diff.added, |w, item| {
         writeln!(w, "+{}", item)
     })?;
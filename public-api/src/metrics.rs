@@ -0,0 +1,70 @@
+//! Contains [`Metrics`], which describes the size of a public API. See
+//! [`crate::PublicApi::metrics`].
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{plain_text::visible_path, sorting, PublicItem};
+
+/// Size metrics of a public API, e.g. for tracking how the public API grows
+/// over time. See [`crate::PublicApi::metrics`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The number of items of each kind. The kind is the kind as rendered,
+    /// e.g. `"fn"`, `"struct"` or `"struct field"`. `impl`s are counted as
+    /// `"impl"`.
+    pub items_per_kind: BTreeMap<String, usize>,
+
+    /// The total number of generic parameters of all items, see
+    /// [`PublicItem::generics()`].
+    pub generic_params: usize,
+
+    /// The number of methods of public traits, including methods with a
+    /// default implementation.
+    pub trait_methods: usize,
+}
+
+impl Metrics {
+    /// The total number of items, of all kinds.
+    #[must_use]
+    pub fn items(&self) -> usize {
+        self.items_per_kind.values().sum()
+    }
+
+    /// The number of items of the given kind, e.g. `"fn"`. See
+    /// [`Self::items_per_kind`].
+    #[must_use]
+    pub fn items_of_kind(&self, kind: &str) -> usize {
+        self.items_per_kind.get(kind).copied().unwrap_or_default()
+    }
+}
+
+pub(crate) fn metrics<'a>(items: impl Iterator<Item = &'a PublicItem> + Clone) -> Metrics {
+    let trait_paths: HashSet<Vec<String>> = items
+        .clone()
+        .filter(|item| sorting::kind(item) == "trait")
+        .filter_map(|item| visible_path(&item.tokens))
+        .collect();
+
+    let mut metrics = Metrics::default();
+    for item in items {
+        let kind = sorting::kind(item);
+        if kind == "fn" && is_in_trait(item, &trait_paths) {
+            metrics.trait_methods += 1;
+        }
+        *metrics.items_per_kind.entry(kind).or_default() += 1;
+        metrics.generic_params += item.generics().len();
+    }
+    metrics
+}
+
+/// Returns `true` if `item` is declared inside one of the traits with the
+/// given paths, as opposed to e.g. in an `impl`. Note that items of an `impl`
+/// of a trait for a foreign type are rendered with the path of the trait.
+fn is_in_trait(item: &PublicItem, trait_paths: &HashSet<Vec<String>>) -> bool {
+    item.impl_kind().is_none()
+        && visible_path(&item.tokens).is_some_and(|mut path| {
+            path.pop();
+            trait_paths.contains(&path)
+        })
+}
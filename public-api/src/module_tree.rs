@@ -0,0 +1,152 @@
+//! Contains facilities to group [`PublicItem`]s by the module they belong to.
+//! See [`crate::PublicApi::module_tree`].
+
+use std::collections::HashMap;
+
+use crate::{public_item::PublicItemPath, sorting, PublicItem};
+
+/// A module in the public API, together with its items and child modules.
+/// Items and child modules are in the same order as in the [`crate::PublicApi`]
+/// the tree was created from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModuleNode {
+    /// The `pub mod` item of the module. `None` for the root node if the
+    /// public API does not have exactly one top-level module, e.g. if it is
+    /// empty.
+    pub module: Option<PublicItem>,
+
+    /// The items directly in this module, except child modules. Also contains
+    /// e.g. struct fields and `impl`s of the structs in the module.
+    pub items: Vec<PublicItem>,
+
+    /// The child modules of this module.
+    pub modules: Vec<ModuleNode>,
+}
+
+/// Builds the tree. Each item belongs to the module with the longest path
+/// that is a prefix of the path of the item.
+pub(crate) fn module_tree<'a>(items: impl IntoIterator<Item = &'a PublicItem>) -> ModuleNode {
+    let (modules, items): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .partition(|item| sorting::kind(item) == "mod");
+
+    let index_of: HashMap<&[String], usize> = modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| (module.sortable_path.as_slice(), index))
+        .collect();
+    let parent_of = |path: &PublicItemPath| {
+        (0..path.len())
+            .rev()
+            .find_map(|len| index_of.get(&path[..len]).copied())
+    };
+
+    let mut items_of = vec![vec![]; modules.len()];
+    let mut child_modules_of = vec![vec![]; modules.len()];
+    let mut top_level_modules = vec![];
+    let mut orphans = vec![];
+    for (index, module) in modules.iter().enumerate() {
+        match parent_of(&module.sortable_path) {
+            Some(parent) => child_modules_of[parent].push(index),
+            None => top_level_modules.push(index),
+        }
+    }
+    for item in items {
+        match parent_of(&item.sortable_path) {
+            Some(parent) => items_of[parent].push(item.clone()),
+            None => orphans.push(item.clone()),
+        }
+    }
+
+    let mut nodes: Vec<Option<ModuleNode>> = modules
+        .iter()
+        .zip(items_of)
+        .map(|(module, items)| {
+            Some(ModuleNode {
+                module: Some((*module).clone()),
+                items,
+                modules: vec![],
+            })
+        })
+        .collect();
+
+    // Children always have longer paths than their parents, so if we attach
+    // the modules with the longest paths first, each child is complete before
+    // it is attached
+    let mut by_depth: Vec<usize> = (0..modules.len()).collect();
+    by_depth.sort_by_key(|&index| std::cmp::Reverse(modules[index].sortable_path.len()));
+    for index in by_depth {
+        let children = child_modules_of[index]
+            .iter()
+            .filter_map(|&child| nodes[child].take())
+            .collect();
+        if let Some(node) = &mut nodes[index] {
+            node.modules = children;
+        }
+    }
+
+    let mut top_level: Vec<ModuleNode> = top_level_modules
+        .into_iter()
+        .filter_map(|index| nodes[index].take())
+        .collect();
+    if top_level.len() == 1 {
+        let mut root = top_level.remove(0);
+        root.items.extend(orphans);
+        root
+    } else {
+        ModuleNode {
+            module: None,
+            items: orphans,
+            modules: top_level,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PublicApi;
+
+    use super::*;
+
+    fn depth(node: &ModuleNode) -> usize {
+        1 + node.modules.iter().map(depth).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn nested_modules() {
+        let api = PublicApi::from_plain_text(
+            "\
+pub mod krate
+pub fn krate::f()
+pub mod krate::a
+pub struct krate::a::S
+pub struct field krate::a::S::field: usize
+pub mod krate::a::inner
+pub fn krate::a::inner::g()
+pub mod krate::b
+",
+        );
+        let tree = api.module_tree();
+
+        assert_eq!(depth(&tree), 3);
+        assert_eq!(tree.module.as_ref().unwrap().to_string(), "pub mod krate");
+        assert_eq!(tree.items.len(), 1);
+        assert_eq!(tree.modules.len(), 2);
+
+        let a = &tree.modules[0];
+        assert_eq!(a.module.as_ref().unwrap().to_string(), "pub mod krate::a");
+        assert_eq!(a.items.len(), 2);
+        assert_eq!(a.modules.len(), 1);
+        assert_eq!(a.modules[0].items.len(), 1);
+
+        let b = &tree.modules[1];
+        assert!(b.items.is_empty());
+        assert!(b.modules.is_empty());
+    }
+
+    #[test]
+    fn empty_api() {
+        let tree = PublicApi::from_plain_text("").module_tree();
+        assert_eq!(tree, ModuleNode::default());
+    }
+}
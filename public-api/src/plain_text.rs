@@ -0,0 +1,248 @@
+//! Reconstructs [`PublicItem`]s from the plain-text listing that `cargo
+//! public-api` prints, i.e. one item per line as rendered by the
+//! [`std::fmt::Display`] impl of [`PublicItem`].
+//!
+//! Rendering throws information away, so the reconstruction is approximate.
+//! Tokens are classified with simple heuristics, and the path used to match
+//! changed items is the path that is visible in the rendered item. Two items
+//! parsed from the same text are always equal though, which is what diffing
+//! needs.
+
+use crate::{public_item::PublicItemPath, tokens::Token, PublicItem};
+
+/// Words that render as [`Token::Qualifier`].
+const QUALIFIERS: [&str; 5] = ["pub", "const", "unsafe", "async", "extern"];
+
+/// Words that render as [`Token::Kind`].
+const KINDS: [&str; 13] = [
+    "fn", "struct", "enum", "mod", "trait", "type", "union", "static", "macro", "use", "crate",
+    "field", "variant",
+];
+
+/// Words that render as [`Token::Keyword`].
+const KEYWORDS: [&str; 8] = ["impl", "for", "where", "dyn", "as", "mut", "Self", "auto"];
+
+/// Words that render as [`Token::Primitive`].
+const PRIMITIVES: [&str; 17] = [
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64",
+];
+
+/// Parses a listing with one rendered item per line. Empty lines are ignored.
+pub(crate) fn parse_listing(listing: &str) -> Vec<PublicItem> {
+    listing
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(parse_item)
+        .collect()
+}
+
+/// Parses a single rendered item.
+pub(crate) fn parse_item(line: &str) -> PublicItem {
+    let tokens = tokenize(line);
+    let sortable_path = visible_path(&tokens).unwrap_or_else(|| vec![line.to_owned()]);
+    PublicItem {
+        sortable_path,
+        tokens,
+        impl_kind: None,
+        source_location: None,
+        generics: vec![],
+        docs: None,
+    }
+}
+
+/// The path of the item, e.g. `["krate", "Struct", "method"]` for `pub fn
+/// krate::Struct::method(&self)`. Returns `None` for `impl`s, since the self
+/// type of an `impl` does not identify it.
+pub(crate) fn visible_path(tokens: &[Token]) -> Option<PublicItemPath> {
+    let mut tokens = tokens.iter().skip_while(|token| {
+        matches!(
+            token,
+            Token::Annotation(_) | Token::Qualifier(_) | Token::Kind(_) | Token::Whitespace
+        )
+    });
+
+    let mut path = vec![];
+    loop {
+        match tokens.next()? {
+            Token::Identifier(name) | Token::Type(name) | Token::Function(name) => {
+                path.push(name.clone());
+            }
+            _ => return None,
+        }
+        if !matches!(tokens.next(), Some(Token::Symbol(symbol)) if symbol == "::") {
+            return Some(path);
+        }
+    }
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut rest = line;
+
+    // Attributes such as `#[non_exhaustive]` are only rendered at the start
+    while let Some(annotation) = leading_annotation(rest) {
+        tokens.push(Token::Annotation(annotation.to_owned()));
+        rest = &rest[annotation.len()..];
+        if let Some(stripped) = rest.strip_prefix(' ') {
+            tokens.push(Token::Whitespace);
+            rest = stripped;
+        }
+    }
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c == ' ' {
+            tokens.push(Token::Whitespace);
+            1
+        } else if c == '\'' {
+            let len = 1 + word_len(&rest[1..]);
+            tokens.push(Token::Lifetime(rest[..len].to_owned()));
+            len
+        } else if c == '"' {
+            let len = rest[1..].find('"').map_or(rest.len(), |end| end + 2);
+            tokens.push(Token::Qualifier(rest[..len].to_owned()));
+            len
+        } else if is_word_char(c) {
+            let len = word_len(rest);
+            let in_path = matches!(tokens.last(), Some(Token::Symbol(s)) if s == "::");
+            tokens.push(classify_word(&rest[..len], &rest[len..], in_path));
+            len
+        } else {
+            let len = ["::", "->"]
+                .into_iter()
+                .find(|symbol| rest.starts_with(symbol))
+                .map_or(c.len_utf8(), str::len);
+            tokens.push(Token::Symbol(rest[..len].to_owned()));
+            len
+        };
+        rest = &rest[len..];
+    }
+
+    tokens
+}
+
+/// Returns e.g. `#[repr(C)]` if `text` starts with it.
+fn leading_annotation(text: &str) -> Option<&str> {
+    if !text.starts_with("#[") {
+        return None;
+    }
+
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 1 => return Some(&text[..=index]),
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Classifies a word. Words that are part of a path, like `field` in
+/// `krate::S::field`, are never keywords.
+fn classify_word(word: &str, following: &str, in_path: bool) -> Token {
+    let owned = word.to_owned();
+    if in_path {
+        path_segment(owned, following)
+    } else if QUALIFIERS.contains(&word) {
+        Token::Qualifier(owned)
+    } else if KINDS.contains(&word) {
+        Token::Kind(owned)
+    } else if KEYWORDS.contains(&word) {
+        Token::Keyword(owned)
+    } else if word == "self" {
+        Token::Self_(owned)
+    } else if PRIMITIVES.contains(&word) || word.starts_with(|c: char| c.is_ascii_digit()) {
+        Token::Primitive(owned)
+    } else {
+        path_segment(owned, following)
+    }
+}
+
+fn path_segment(word: String, following: &str) -> Token {
+    if following.starts_with("::") {
+        Token::Identifier(word)
+    } else if following.starts_with('(') || following.starts_with('<') && is_lowercase(&word) {
+        Token::Function(word)
+    } else if is_lowercase(&word) {
+        Token::Identifier(word)
+    } else {
+        Token::Type(word)
+    }
+}
+
+fn is_lowercase(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_lowercase() || c == '_')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn word_len(text: &str) -> usize {
+    text.find(|c| !is_word_char(c)).unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_rendered_items() {
+        for line in [
+            "#[non_exhaustive] pub struct example_api::Struct",
+            "#[repr(C)] #[non_exhaustive] pub enum krate::E",
+            "pub fn krate::f<'a, T>(t: &'a T) -> core::option::Option<&'a str> where T: core::fmt::Debug",
+            "pub unsafe extern \"C\" fn krate::c(x: *const u8)",
+            "impl<T> core::any::Any for krate::S where T: 'static + ?core::marker::Sized",
+        ] {
+            assert_eq!(parse_item(line).to_string(), line);
+        }
+    }
+
+    #[test]
+    fn sortable_path_is_visible_path() {
+        let path_of = |line| parse_item(line).sortable_path;
+        assert_eq!(
+            path_of("pub fn krate::S::method(&self) -> u8"),
+            vec!["krate", "S", "method"]
+        );
+        assert_eq!(
+            path_of("pub struct field krate::S::field: alloc::string::String"),
+            vec!["krate", "S", "field"]
+        );
+        assert_eq!(
+            path_of("#[non_exhaustive] pub struct krate::S"),
+            vec!["krate", "S"]
+        );
+        assert_eq!(
+            path_of("impl core::fmt::Debug for krate::S"),
+            vec!["impl core::fmt::Debug for krate::S"]
+        );
+    }
+
+    #[test]
+    fn classifies_tokens() {
+        assert_eq!(
+            parse_item("pub fn krate::f(self) -> Self").tokens,
+            vec![
+                Token::Qualifier("pub".to_owned()),
+                Token::Whitespace,
+                Token::Kind("fn".to_owned()),
+                Token::Whitespace,
+                Token::Identifier("krate".to_owned()),
+                Token::Symbol("::".to_owned()),
+                Token::Function("f".to_owned()),
+                Token::Symbol("(".to_owned()),
+                Token::Self_("self".to_owned()),
+                Token::Symbol(")".to_owned()),
+                Token::Whitespace,
+                Token::Symbol("->".to_owned()),
+                Token::Whitespace,
+                Token::Keyword("Self".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Finds the dependency crates that are part of the public API, i.e. whose
+//! items are re-exported or appear in the signatures of public items.
+
+use std::collections::BTreeSet;
+
+use rustdoc_types::{
+    Crate, FnDecl, GenericArg, GenericArgs, GenericBound, GenericParamDef, GenericParamDefKind,
+    Generics, Id, ItemEnum, Path, Term, Type, TypeBindingKind, WherePredicate,
+};
+
+use crate::intermediate_public_item::IntermediatePublicItem;
+
+/// Crates that come with the toolchain. Every crate depends on them, so
+/// listing them as public dependencies is just noise.
+const SYSROOT_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// Returns the names of the dependency crates that `items` refer to, sorted
+/// and without duplicates.
+pub(crate) fn public_dependencies(crate_: &Crate, items: &[IntermediatePublicItem]) -> Vec<String> {
+    let mut crate_ids = BTreeSet::new();
+    let mut names = BTreeSet::new();
+
+    for public_item in items {
+        let item = public_item.item();
+        crate_ids.insert(item.crate_id);
+
+        if let ItemEnum::ExternCrate { name, .. } = &item.inner {
+            names.insert(name.clone());
+        }
+
+        let mut collector = CrateIdCollector {
+            crate_,
+            crate_ids: &mut crate_ids,
+        };
+        collector.item_enum(&item.inner);
+    }
+
+    names.extend(
+        crate_ids
+            .iter()
+            .filter_map(|crate_id| crate_.external_crates.get(crate_id))
+            .map(|external_crate| external_crate.name.clone()),
+    );
+    names.retain(|name| !SYSROOT_CRATES.contains(&name.as_str()));
+
+    names.into_iter().collect()
+}
+
+/// Collects the crates of the items that the paths and imports in an item
+/// refer to. Items that are part of the item tree themselves, such as struct
+/// fields and the items of impls, are visited separately.
+struct CrateIdCollector<'c, 's> {
+    crate_: &'c Crate,
+    crate_ids: &'s mut BTreeSet<u32>,
+}
+
+impl CrateIdCollector<'_, '_> {
+    fn item_enum(&mut self, item: &ItemEnum) {
+        match item {
+            ItemEnum::Import(import) => {
+                if let Some(id) = &import.id {
+                    self.id(id);
+                }
+            }
+            ItemEnum::Union(union_) => self.generics(&union_.generics),
+            ItemEnum::Struct(struct_) => self.generics(&struct_.generics),
+            ItemEnum::StructField(type_) => self.type_(type_),
+            ItemEnum::Enum(enum_) => self.generics(&enum_.generics),
+            ItemEnum::Function(function) => {
+                self.generics(&function.generics);
+                self.fn_decl(&function.decl);
+            }
+            ItemEnum::Method(method) => {
+                self.generics(&method.generics);
+                self.fn_decl(&method.decl);
+            }
+            ItemEnum::Trait(trait_) => {
+                self.generics(&trait_.generics);
+                self.generic_bounds(&trait_.bounds);
+            }
+            ItemEnum::TraitAlias(trait_alias) => {
+                self.generics(&trait_alias.generics);
+                self.generic_bounds(&trait_alias.params);
+            }
+            ItemEnum::Impl(impl_) => {
+                self.generics(&impl_.generics);
+                if let Some(trait_) = &impl_.trait_ {
+                    self.path(trait_);
+                }
+                self.type_(&impl_.for_);
+            }
+            ItemEnum::Typedef(typedef) => {
+                self.generics(&typedef.generics);
+                self.type_(&typedef.type_);
+            }
+            ItemEnum::OpaqueTy(opaque_ty) => {
+                self.generics(&opaque_ty.generics);
+                self.generic_bounds(&opaque_ty.bounds);
+            }
+            ItemEnum::Constant(constant) => self.type_(&constant.type_),
+            ItemEnum::Static(static_) => self.type_(&static_.type_),
+            ItemEnum::AssocConst { type_, .. } => self.type_(type_),
+            ItemEnum::AssocType {
+                generics,
+                bounds,
+                default,
+            } => {
+                self.generics(generics);
+                self.generic_bounds(bounds);
+                if let Some(default) = default {
+                    self.type_(default);
+                }
+            }
+            ItemEnum::Module(_)
+            | ItemEnum::ExternCrate { .. }
+            | ItemEnum::Variant(_)
+            | ItemEnum::ForeignType
+            | ItemEnum::Macro(_)
+            | ItemEnum::ProcMacro(_)
+            | ItemEnum::Primitive(_) => {}
+        }
+    }
+
+    fn type_(&mut self, type_: &Type) {
+        match type_ {
+            Type::ResolvedPath(path) => self.path(path),
+            Type::DynTrait(dyn_trait) => {
+                for poly_trait in &dyn_trait.traits {
+                    self.generic_param_defs(&poly_trait.generic_params);
+                    self.path(&poly_trait.trait_);
+                }
+            }
+            Type::FunctionPointer(ptr) => {
+                self.generic_param_defs(&ptr.generic_params);
+                self.fn_decl(&ptr.decl);
+            }
+            Type::Tuple(types) => types.iter().for_each(|type_| self.type_(type_)),
+            Type::Slice(type_)
+            | Type::Array { type_, .. }
+            | Type::RawPointer { type_, .. }
+            | Type::BorrowedRef { type_, .. } => self.type_(type_),
+            Type::ImplTrait(bounds) => self.generic_bounds(bounds),
+            Type::QualifiedPath {
+                args,
+                self_type,
+                trait_,
+                ..
+            } => {
+                self.generic_args(args);
+                self.type_(self_type);
+                self.path(trait_);
+            }
+            Type::Generic(_) | Type::Primitive(_) | Type::Infer => {}
+        }
+    }
+
+    fn path(&mut self, path: &Path) {
+        self.id(&path.id);
+        if let Some(args) = &path.args {
+            self.generic_args(args);
+        }
+    }
+
+    fn generic_args(&mut self, args: &GenericArgs) {
+        match args {
+            GenericArgs::AngleBracketed { args, bindings } => {
+                for arg in args {
+                    match arg {
+                        GenericArg::Type(type_) => self.type_(type_),
+                        GenericArg::Const(constant) => self.type_(&constant.type_),
+                        GenericArg::Lifetime(_) | GenericArg::Infer => {}
+                    }
+                }
+                for binding in bindings {
+                    self.generic_args(&binding.args);
+                    match &binding.binding {
+                        TypeBindingKind::Equality(term) => self.term(term),
+                        TypeBindingKind::Constraint(bounds) => self.generic_bounds(bounds),
+                    }
+                }
+            }
+            GenericArgs::Parenthesized { inputs, output } => {
+                inputs.iter().for_each(|type_| self.type_(type_));
+                if let Some(output) = output {
+                    self.type_(output);
+                }
+            }
+        }
+    }
+
+    fn term(&mut self, term: &Term) {
+        match term {
+            Term::Type(type_) => self.type_(type_),
+            Term::Constant(constant) => self.type_(&constant.type_),
+        }
+    }
+
+    fn fn_decl(&mut self, decl: &FnDecl) {
+        for (_, type_) in &decl.inputs {
+            self.type_(type_);
+        }
+        if let Some(output) = &decl.output {
+            self.type_(output);
+        }
+    }
+
+    fn generics(&mut self, generics: &Generics) {
+        self.generic_param_defs(&generics.params);
+        for where_predicate in &generics.where_predicates {
+            match where_predicate {
+                WherePredicate::BoundPredicate {
+                    type_,
+                    bounds,
+                    generic_params,
+                } => {
+                    self.generic_param_defs(generic_params);
+                    self.type_(type_);
+                    self.generic_bounds(bounds);
+                }
+                WherePredicate::RegionPredicate { bounds, .. } => self.generic_bounds(bounds),
+                WherePredicate::EqPredicate { lhs, rhs } => {
+                    self.type_(lhs);
+                    self.term(rhs);
+                }
+            }
+        }
+    }
+
+    fn generic_param_defs(&mut self, params: &[GenericParamDef]) {
+        for param in params {
+            match &param.kind {
+                GenericParamDefKind::Type {
+                    bounds, default, ..
+                } => {
+                    self.generic_bounds(bounds);
+                    if let Some(default) = default {
+                        self.type_(default);
+                    }
+                }
+                GenericParamDefKind::Const { type_, .. } => self.type_(type_),
+                GenericParamDefKind::Lifetime { .. } => {}
+            }
+        }
+    }
+
+    fn generic_bounds(&mut self, bounds: &[GenericBound]) {
+        for bound in bounds {
+            if let GenericBound::TraitBound {
+                trait_,
+                generic_params,
+                ..
+            } = bound
+            {
+                self.generic_param_defs(generic_params);
+                self.path(trait_);
+            }
+        }
+    }
+
+    fn id(&mut self, id: &Id) {
+        self.crate_ids.extend(crate_id_for_id(self.crate_, id));
+    }
+}
+
+fn crate_id_for_id(crate_: &Crate, id: &Id) -> Option<u32> {
+    crate_.index.get(id).map(|item| item.crate_id).or_else(|| {
+        crate_
+            .paths
+            .get(id)
+            .map(|item_summary| item_summary.crate_id)
+    })
+}
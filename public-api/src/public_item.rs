@@ -1,7 +1,12 @@
 use std::cmp::Ordering;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use rustdoc_types::{GenericParamDefKind, Generics, ItemEnum};
 
 use crate::intermediate_public_item::IntermediatePublicItem;
+use crate::item_processor::ImplKind;
 use crate::render::RenderingContext;
 use crate::tokens::tokens_to_string;
 use crate::tokens::Token;
@@ -16,13 +21,86 @@ pub(crate) type PublicItemPath = Vec<String>;
 /// of the public API of a crate. Implements [`Display`] so it can be printed. It
 /// also implements [`Ord`], but how items are ordered are not stable yet, and
 /// will change in later versions.
-#[derive(Clone, Eq, PartialEq, Hash)]
+///
+/// The [`Self::source_location()`] and [`Self::docs()`] are not taken into
+/// account when comparing items, so that an item that merely moved or got
+/// better docs does not show up in diffs.
+#[derive(Clone)]
 pub struct PublicItem {
     /// Read [`crate::item_processor::sorting_prefix()`] docs for more info
     pub(crate) sortable_path: PublicItemPath,
 
     /// The rendered item as a stream of [`Token`]s
     pub(crate) tokens: Vec<Token>,
+
+    /// See [`Self::impl_kind()`]
+    pub(crate) impl_kind: Option<ImplKind>,
+
+    /// See [`Self::source_location()`]
+    pub(crate) source_location: Option<SourceLocation>,
+
+    /// See [`Self::generics()`]
+    pub(crate) generics: Vec<GenericParam>,
+
+    /// See [`Self::docs()`]
+    pub(crate) docs: Option<String>,
+}
+
+/// Where in the source code of a crate a [`PublicItem`] is defined. See
+/// [`PublicItem::source_location()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    file: PathBuf,
+    line: usize,
+}
+
+impl SourceLocation {
+    /// The source file, relative to the dir that `rustdoc` was invoked in,
+    /// e.g. `src/lib.rs`.
+    #[must_use]
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// The line in [`Self::file()`] where the item begins, starting at 1.
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+/// A generic parameter of a [`PublicItem`]. See [`PublicItem::generics()`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GenericParam {
+    name: String,
+    kind: GenericParamKind,
+}
+
+/// The kind of a [`GenericParam`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GenericParamKind {
+    /// E.g. `'a` in `fn f<'a>()`
+    Lifetime,
+
+    /// E.g. `T` in `fn f<T>()`
+    Type,
+
+    /// E.g. `N` in `fn f<const N: usize>()`
+    Const,
+}
+
+impl GenericParam {
+    /// The name of the generic parameter, e.g. `T`, or `'a` for lifetimes.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The kind of the generic parameter.
+    #[must_use]
+    pub fn kind(&self) -> GenericParamKind {
+        self.kind
+    }
 }
 
 impl PublicItem {
@@ -33,6 +111,13 @@ impl PublicItem {
         PublicItem {
             sortable_path: public_item.sortable_path(),
             tokens: public_item.render_token_stream(context),
+            impl_kind: public_item.impl_kind(),
+            source_location: public_item.item().span.as_ref().map(|span| SourceLocation {
+                file: span.filename.clone(),
+                line: span.begin.0,
+            }),
+            generics: generic_params(&public_item.item().inner),
+            docs: public_item.item().docs.clone(),
         }
     }
 
@@ -40,21 +125,160 @@ impl PublicItem {
     pub fn tokens(&self) -> impl Iterator<Item = &Token> {
         self.tokens.iter()
     }
+
+    /// The complete rendered item as a single line, e.g. `pub fn
+    /// krate::function(arg: usize)`. Same as the [`Display`] impl, but more
+    /// convenient if all you want is a `String`.
+    #[must_use]
+    pub fn render_plain(&self) -> String {
+        tokens_to_string(&self.tokens)
+    }
+
+    /// If this item is an `impl`, or belongs to an `impl`, returns what kind
+    /// of `impl` it is. Allows you to e.g. filter out items of Blanket
+    /// Implementations yourself. Returns `None` for items that have nothing to
+    /// do with an `impl`, such as a free `fn` or a `struct`.
+    #[must_use]
+    pub fn impl_kind(&self) -> Option<ImplKind> {
+        self.impl_kind
+    }
+
+    /// Where in the source code the item is defined, if known. Not known for
+    /// e.g. items parsed from a plain-text listing.
+    #[must_use]
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
+
+    /// The doc comment of the item itself, without the leading `///`, e.g.
+    /// for changelog tooling. `None` if the item has no doc comment, and for
+    /// e.g. items parsed from a plain-text listing.
+    #[must_use]
+    pub fn docs(&self) -> Option<&str> {
+        self.docs.as_deref()
+    }
+
+    /// The generic parameters of the item, in declaration order, e.g. `T` and
+    /// `N` for `fn f<T, const N: usize>()`. Parameters that the compiler adds
+    /// for `impl Trait` arguments are not included. Empty for items without
+    /// generic parameters, and for items parsed from a plain-text listing.
+    #[must_use]
+    pub fn generics(&self) -> &[GenericParam] {
+        &self.generics
+    }
+
+    /// Returns `true` if the item has the given path, e.g.
+    /// `"krate::Struct::field"` for `pub struct field krate::Struct::field:
+    /// usize`. `impl`s have no path, so they never match.
+    #[must_use]
+    pub fn has_path(&self, path: &str) -> bool {
+        crate::plain_text::visible_path(&self.tokens).is_some_and(|visible_path| {
+            visible_path.iter().map(String::as_str).eq(path.split("::"))
+        })
+    }
+
+    /// How deeply the item is nested in the item tree of the crate. The crate
+    /// root has depth 0 and e.g. `pub fn krate::m::f()` has depth 2. The items
+    /// of an `impl` are one level deeper than the `impl`, which in turn is one
+    /// level deeper than the type or trait it is listed under. This is what
+    /// `"{:#}"` indents by.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.sortable_path.len().saturating_sub(1)
+    }
+
+    /// Returns `true` if the item is the root module of the crate, i.e. `pub
+    /// mod krate`, which is part of every public API. Unlike the rendered
+    /// path, this does not depend on e.g. [`crate::Options::relative_paths`].
+    #[must_use]
+    pub fn is_crate_root(&self) -> bool {
+        self.sortable_path.len() == 1
+            && self
+                .tokens
+                .iter()
+                .any(|token| matches!(token, Token::Kind(kind) if kind == "mod"))
+    }
+}
+
+/// Returns the generic parameters that are declared on `item`.
+fn generic_params(item: &ItemEnum) -> Vec<GenericParam> {
+    let generics = match item {
+        ItemEnum::Function(function) => &function.generics,
+        ItemEnum::Method(method) => &method.generics,
+        ItemEnum::Struct(struct_) => &struct_.generics,
+        ItemEnum::Enum(enum_) => &enum_.generics,
+        ItemEnum::Union(union_) => &union_.generics,
+        ItemEnum::Trait(trait_) => &trait_.generics,
+        ItemEnum::TraitAlias(trait_alias) => &trait_alias.generics,
+        ItemEnum::Impl(impl_) => &impl_.generics,
+        ItemEnum::Typedef(typedef) => &typedef.generics,
+        ItemEnum::AssocType { generics, .. } => generics,
+        _ => return vec![],
+    };
+    generic_params_of(generics)
+}
+
+fn generic_params_of(generics: &Generics) -> Vec<GenericParam> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| {
+            let kind = match &param.kind {
+                GenericParamDefKind::Lifetime { .. } => GenericParamKind::Lifetime,
+                GenericParamDefKind::Type {
+                    synthetic: true, ..
+                } => return None,
+                GenericParamDefKind::Type { .. } => GenericParamKind::Type,
+                GenericParamDefKind::Const { .. } => GenericParamKind::Const,
+            };
+            Some(GenericParam {
+                name: param.name.clone(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+impl PartialEq for PublicItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.sortable_path == other.sortable_path
+            && self.tokens == other.tokens
+            && self.impl_kind == other.impl_kind
+    }
+}
+
+impl Eq for PublicItem {}
+
+impl Hash for PublicItem {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sortable_path.hash(state);
+        self.tokens.hash(state);
+        self.impl_kind.hash(state);
+    }
 }
 
 /// We want pretty-printing (`"{:#?}"`) of [`crate::diff::PublicApiDiff`] to print
-/// each public item as `Display`, so implement `Debug` with `Display`.
+/// each public item as `Display`, so implement `Debug` with `Display`. Without
+/// the indentation of `"{:#}"` though, since `"{:#?}"` indents by itself.
 impl std::fmt::Debug for PublicItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Display::fmt(self, f)
+        write!(f, "{}", self.render_plain())
     }
 }
 
 /// One of the basic uses cases is printing a sorted `Vec` of `PublicItem`s. So
 /// we implement `Display` for it.
+///
+/// The alternate form `"{:#}"` indents the item by two spaces per level of
+/// nesting in its path, so that a sorted list of items reads like a tree.
+/// Items at the crate root, like `pub mod krate`, are not indented, and e.g.
+/// `pub fn krate::m::f()` is indented by four spaces.
 impl Display for PublicItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", tokens_to_string(&self.tokens))
+        if f.alternate() {
+            write!(f, "{:indent$}", "", indent = self.depth() * 2)?;
+        }
+        write!(f, "{}", self.render_plain())
     }
 }
 
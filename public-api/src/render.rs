@@ -8,9 +8,9 @@ use std::{cmp::Ordering, collections::HashMap, vec};
 
 use rustdoc_types::{
     Abi, Constant, Crate, FnDecl, FunctionPointer, GenericArg, GenericArgs, GenericBound,
-    GenericParamDef, GenericParamDefKind, Generics, Header, Id, Impl, Item, ItemEnum, MacroKind,
-    Path, PolyTrait, StructKind, Term, Trait, Type, TypeBinding, TypeBindingKind, Variant,
-    WherePredicate,
+    GenericParamDef, GenericParamDefKind, Generics, Header, Id, Impl, Item, ItemEnum, ItemSummary,
+    MacroKind, Path, PolyTrait, StructKind, Term, Trait, TraitBoundModifier, Type, TypeBinding,
+    TypeBindingKind, Variant, Visibility, WherePredicate,
 };
 
 /// A simple macro to write `Token::Whitespace` in less characters.
@@ -32,9 +32,27 @@ pub struct RenderingContext<'c> {
     /// Given a rustdoc JSON ID, keeps track of what public items that have this Id.
     pub id_to_items: HashMap<&'c Id, Vec<&'c IntermediatePublicItem<'c>>>,
 
+    /// Given the last path component of an item in another crate, e.g. `Vec`,
+    /// keeps track of what items have that name. Only populated if
+    /// [`Options::qualify_external`] is set. See [`external_items_by_name`].
+    pub external_items_by_name: HashMap<&'c str, Vec<&'c ItemSummary>>,
+
     pub options: Options,
 }
 
+/// Indexes the items of other crates in `crate_` by the last component of their
+/// path, so that [`RenderingContext`] can look them up by name without going
+/// through all of them each time.
+pub fn external_items_by_name(crate_: &Crate) -> HashMap<&str, Vec<&ItemSummary>> {
+    let mut index: HashMap<&str, Vec<&ItemSummary>> = HashMap::new();
+    for item in crate_.paths.values().filter(|item| item.crate_id != 0) {
+        if let Some(name) = item.path.last() {
+            index.entry(name).or_default().push(item);
+        }
+    }
+    index
+}
+
 impl<'c> RenderingContext<'c> {
     #[allow(clippy::too_many_lines)]
     pub fn token_stream(&self, public_item: &IntermediatePublicItem<'c>) -> Vec<Token> {
@@ -47,26 +65,45 @@ impl<'c> RenderingContext<'c> {
             if attr_relevant_for_public_apis(attr) {
                 tokens.push(Token::Annotation(attr.clone()));
                 tokens.push(ws!());
+            } else if let Some(cfg) = cfg_attr(attr).filter(|_| self.options.show_cfgs) {
+                // Crates often have both `#[cfg(x)]` and `#[doc(cfg(x))]`
+                let cfg = Token::Annotation(cfg);
+                if !tokens.contains(&cfg) {
+                    tokens.push(cfg);
+                    tokens.push(ws!());
+                }
             }
         }
 
-        let inner_tokens = match &item.inner {
+        let mut inner_tokens = match &item.inner {
             ItemEnum::Module(_) => self.render_simple(&["mod"], item_path),
             ItemEnum::ExternCrate { .. } => self.render_simple(&["extern", "crate"], item_path),
             ItemEnum::Import(_) => self.render_simple(&["use"], item_path),
-            ItemEnum::Union(_) => self.render_simple(&["union"], item_path),
+            ItemEnum::Union(u) => {
+                let mut output = self.render_simple(&["union"], item_path);
+                output.extend(self.render_generics(&u.generics));
+                output
+            }
             ItemEnum::Struct(s) => {
                 let mut output = self.render_simple(&["struct"], item_path);
-                output.extend(self.render_generics(&s.generics));
+                output.extend(self.render_generic_param_defs(&s.generics.params));
+                // The where clause of a tuple struct comes after its fields,
+                // as in `struct Wrapper<T>(pub T) where T: Clone`
                 if let StructKind::Tuple(fields) = &s.kind {
                     output.extend(
                         self.render_option_tuple(&self.resolve_tuple_fields(fields), Some(&pub_())),
                     );
                 }
+                output.extend(self.render_where_predicates(&s.generics.where_predicates));
                 output
             }
             ItemEnum::StructField(inner) => {
-                let mut output = self.render_simple(&["struct", "field"], item_path);
+                let kind = if is_union_field(item_path) {
+                    "union"
+                } else {
+                    "struct"
+                };
+                let mut output = self.render_simple(&[kind, "field"], item_path);
                 output.extend(colon());
                 output.extend(self.render_type(inner));
                 output
@@ -79,7 +116,11 @@ impl<'c> RenderingContext<'c> {
             ItemEnum::Variant(inner) => {
                 let mut output = self.render_simple(&["enum", "variant"], item_path);
                 match inner {
-                    Variant::Struct { .. } => {} // Each struct field is printed individually
+                    // Each struct field is also printed individually
+                    Variant::Struct {
+                        fields,
+                        fields_stripped,
+                    } => output.extend(self.render_struct_variant_fields(fields, *fields_stripped)),
                     Variant::Plain(discriminant) => {
                         if let Some(discriminant) = discriminant {
                             output.extend(equals());
@@ -100,12 +141,19 @@ impl<'c> RenderingContext<'c> {
                 &inner.generics,
                 &inner.header,
             ),
-            ItemEnum::Method(inner) => self.render_function(
-                self.render_path(item_path),
-                &inner.decl,
-                &inner.generics,
-                &inner.header,
-            ),
+            ItemEnum::Method(inner) => {
+                let mut output = self.render_function(
+                    self.render_path(item_path),
+                    &inner.decl,
+                    &inner.generics,
+                    &inner.header,
+                );
+                if self.options.mark_provided_methods && inner.has_body && is_trait_item(item_path)
+                {
+                    output.extend(provided_body());
+                }
+                output
+            }
             ItemEnum::Trait(trait_) => self.render_trait(trait_, item_path),
             ItemEnum::TraitAlias(_) => self.render_simple(&["trait", "alias"], item_path),
             ItemEnum::Impl(impl_) => self.render_impl(impl_, item_path),
@@ -134,13 +182,23 @@ impl<'c> RenderingContext<'c> {
             ItemEnum::Constant(con) => {
                 let mut output = self.render_simple(&["const"], item_path);
                 output.extend(colon());
-                output.extend(self.render_constant(con));
+                if self.options.show_values {
+                    output.extend(self.render_type(&con.type_));
+                    output.extend(render_value(&con.expr, con.value.as_deref()));
+                } else {
+                    output.extend(self.render_constant(con));
+                }
                 output
             }
-            ItemEnum::AssocConst { type_, .. } => {
+            ItemEnum::AssocConst { type_, default } => {
                 let mut output = self.render_simple(&["const"], item_path);
                 output.extend(colon());
                 output.extend(self.render_type(type_));
+                // In a trait the value is only a default, but in an impl it
+                // is the value
+                if let Some(default) = default.as_deref().filter(|_| is_impl_item(item_path)) {
+                    output.extend(render_value(default, None));
+                }
                 output
             }
             ItemEnum::Static(inner) => {
@@ -152,6 +210,9 @@ impl<'c> RenderingContext<'c> {
                 let mut output = self.render_simple(&tags, item_path);
                 output.extend(colon());
                 output.extend(self.render_type(&inner.type_));
+                if self.options.show_values {
+                    output.extend(render_value(&inner.expr, None));
+                }
                 output
             }
             ItemEnum::ForeignType => self.render_simple(&["type"], item_path),
@@ -163,8 +224,16 @@ impl<'c> RenderingContext<'c> {
             }
             ItemEnum::ProcMacro(inner) => {
                 let mut output = self.render_simple(&["proc", "macro"], item_path);
-                output.pop(); // Remove name of macro to possibly wrap it in `#[]`
-                let name = Token::identifier(item.name.as_deref().unwrap_or(""));
+                // Remove name of macro to possibly wrap it in `#[]`
+                output.pop();
+                // Use the name from the path so that renamed re-exports (`pub
+                // use other::macro as renamed;`) are rendered with their new name
+                let name = Token::identifier(
+                    item_path
+                        .last()
+                        .and_then(NameableItem::name)
+                        .unwrap_or_default(),
+                );
                 match inner.kind {
                     MacroKind::Bang => output.extend(vec![name, Token::symbol("!()")]),
                     MacroKind::Attr => {
@@ -193,11 +262,82 @@ impl<'c> RenderingContext<'c> {
             }
         };
 
+        // With `--document-private-items`, items that are only visible within
+        // the crate are included too, so tell them apart from public items
+        if let Some(visibility) = render_restricted_visibility(&item.visibility, item_path) {
+            if inner_tokens.starts_with(&pub_()) {
+                inner_tokens.splice(..pub_().len(), visibility);
+            }
+        }
+
         tokens.extend(inner_tokens);
 
+        if self.options.relative_paths {
+            tokens = self.without_crate_name(tokens);
+        }
+
         tokens
     }
 
+    /// Removes the crate name from the start of paths to items of the crate,
+    /// for [`Options::relative_paths`]. The name of the crate root module
+    /// itself is kept, since it is not followed by `::`.
+    fn without_crate_name(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let Some(crate_name) = self.crate_name() else {
+            return tokens;
+        };
+        let is_path_separator =
+            |token: Option<&Token>| matches!(token, Some(Token::Symbol(symbol)) if symbol == "::");
+
+        let mut output = Vec::with_capacity(tokens.len());
+        let mut tokens = tokens.into_iter().peekable();
+        let mut previous_was_path_separator = false;
+        while let Some(token) = tokens.next() {
+            let is_crate_name = matches!(&token, Token::Identifier(name) if name == crate_name);
+            if is_crate_name && !previous_was_path_separator && is_path_separator(tokens.peek()) {
+                tokens.next();
+                previous_was_path_separator = true;
+                continue;
+            }
+            previous_was_path_separator = is_path_separator(Some(&token));
+            output.push(token);
+        }
+        output
+    }
+
+    /// Renders e.g. ` { x: usize, y: bool }` for a struct variant. Fields that
+    /// are not part of the public API are rendered as `..`.
+    fn render_struct_variant_fields(&self, fields: &[Id], fields_stripped: bool) -> Vec<Token> {
+        let mut rendered_fields: Vec<Vec<Token>> = fields
+            .iter()
+            .filter_map(|id| self.crate_.index.get(id))
+            .filter_map(|item| match &item.inner {
+                ItemEnum::StructField(type_) => {
+                    let mut field = vec![Token::identifier(item.name.as_deref().unwrap_or("_"))];
+                    field.extend(colon());
+                    field.extend(self.render_type(type_));
+                    Some(field)
+                }
+                _ => None,
+            })
+            .collect();
+        if fields_stripped {
+            rendered_fields.push(vec![Token::symbol("..")]);
+        }
+
+        if rendered_fields.is_empty() {
+            vec![ws!(), Token::symbol("{}")]
+        } else {
+            self.render_sequence(
+                vec![ws!(), Token::symbol("{"), ws!()],
+                vec![ws!(), Token::symbol("}")],
+                comma(),
+                &rendered_fields,
+                Clone::clone,
+            )
+        }
+    }
+
     /// Tuple fields are referenced by ID in JSON, but we need to look up the
     /// actual types that the IDs correspond to, in order to render the fields.
     /// This helper does that for a slice of fields.
@@ -371,11 +511,6 @@ impl<'c> RenderingContext<'c> {
     fn render_dyn_trait(&self, dyn_trait: &rustdoc_types::DynTrait) -> Vec<Token> {
         let mut output = vec![];
 
-        let more_than_one = dyn_trait.traits.len() > 1 || dyn_trait.lifetime.is_some();
-        if more_than_one {
-            output.push(Token::symbol("("));
-        }
-
         output.extend(self.render_sequence_if_not_empty(
             vec![Token::keyword("dyn"), ws!()],
             vec![],
@@ -389,10 +524,26 @@ impl<'c> RenderingContext<'c> {
             output.extend(vec![Token::lifetime(lt)]);
         }
 
+        output
+    }
+
+    /// Renders the type behind a reference or a raw pointer. Since `&dyn A + B`
+    /// means `(&dyn A) + B`, a trait object with more than one bound must be
+    /// put in parentheses there, but not in e.g. `Box<dyn A + B>`.
+    fn render_pointee(&self, type_: &Type) -> Vec<Token> {
+        let more_than_one = matches!(
+            type_,
+            Type::DynTrait(dyn_trait) if dyn_trait.traits.len() > 1 || dyn_trait.lifetime.is_some()
+        );
+
+        let mut output = vec![];
+        if more_than_one {
+            output.push(Token::symbol("("));
+        }
+        output.extend(self.render_type(type_));
         if more_than_one {
             output.push(Token::symbol(")"));
         }
-
         output
     }
 
@@ -413,21 +564,7 @@ impl<'c> RenderingContext<'c> {
         if header.async_ {
             output.extend(vec![Token::qualifier("async"), ws!()]);
         };
-        if header.abi != Abi::Rust {
-            output.push(match &header.abi {
-                Abi::C { .. } => Token::qualifier("c"),
-                Abi::Cdecl { .. } => Token::qualifier("cdecl"),
-                Abi::Stdcall { .. } => Token::qualifier("stdcall"),
-                Abi::Fastcall { .. } => Token::qualifier("fastcall"),
-                Abi::Aapcs { .. } => Token::qualifier("aapcs"),
-                Abi::Win64 { .. } => Token::qualifier("win64"),
-                Abi::SysV64 { .. } => Token::qualifier("sysV64"),
-                Abi::System { .. } => Token::qualifier("system"),
-                Abi::Other(text) => Token::qualifier(text),
-                Abi::Rust => unreachable!(),
-            });
-            output.push(ws!());
-        }
+        output.extend(render_abi(&header.abi));
 
         output.extend(vec![Token::kind("fn"), ws!()]);
         output.extend(name);
@@ -506,6 +643,8 @@ impl<'c> RenderingContext<'c> {
             output.extend(self.render_path(item.path()));
         } else if let Some(item) = self.crate_.paths.get(&path.id) {
             output.extend(self.render_path_components(item.path.iter().map(Deref::deref)));
+        } else if let Some(item) = self.external_item_for_name(&path.name) {
+            output.extend(self.render_path_components(item.path.iter().map(Deref::deref)));
         } else if !path.name.is_empty() {
             // If we get here it means there was no item for this Path in the
             // rustdoc JSON. Examples of when this happens:
@@ -517,7 +656,7 @@ impl<'c> RenderingContext<'c> {
             // is equal to how it appears in the source text. It might not be
             // ideal and end up identical to the corresponding rustdoc HTML, but
             // it is good enough given the edge-case nature of this code path.
-            output.extend(self.render_path_name(&path.name));
+            output.extend(self.render_path_name(&self.normalize_path_name(&path.name)));
         }
         if let Some(args) = &path.args {
             output.extend(self.render_generic_args(args));
@@ -525,6 +664,64 @@ impl<'c> RenderingContext<'c> {
         output
     }
 
+    /// If [`Options::qualify_external`] is set, tries to find the full path of
+    /// an item from another crate based only on the `name` of a [`Path`] that
+    /// we could not resolve by Id. Only unambiguous matches are used.
+    fn external_item_for_name(&self, name: &str) -> Option<&'c ItemSummary> {
+        if !self.options.qualify_external || name.is_empty() {
+            return None;
+        }
+
+        let last_component = name.rsplit("::").next()?;
+        let mut candidates = self
+            .external_items_by_name
+            .get(last_component)?
+            .iter()
+            .filter(|item| path_ends_with(&item.path, name));
+
+        match (candidates.next(), candidates.next()) {
+            (Some(item), None) => Some(*item),
+            _ => None,
+        }
+    }
+
+    /// Paths to items in the current crate can be written as `crate::x` or,
+    /// in edition 2015, as `::x`. Render both as `krate::x`, like paths that
+    /// we could resolve, so that the edition of the crate and how the path was
+    /// written in the source do not matter.
+    fn normalize_path_name(&self, name: &str) -> String {
+        let Some(crate_name) = self.crate_name() else {
+            return name.to_owned();
+        };
+
+        if let Some(rest) = name.strip_prefix("crate::") {
+            format!("{crate_name}::{rest}")
+        } else if let Some(rest) = name.strip_prefix("::") {
+            let first = rest.split("::").next().unwrap_or_default();
+            if self.is_external_crate(first) {
+                rest.to_owned()
+            } else {
+                format!("{crate_name}::{rest}")
+            }
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn crate_name(&self) -> Option<&'c str> {
+        self.crate_
+            .index
+            .get(&self.crate_.root)
+            .and_then(|root| root.name.as_deref())
+    }
+
+    fn is_external_crate(&self, name: &str) -> bool {
+        self.crate_
+            .external_crates
+            .values()
+            .any(|external_crate| external_crate.name == name)
+    }
+
     fn render_path_name(&self, name: &str) -> Vec<Token> {
         self.render_path_components(name.split("::"))
     }
@@ -552,6 +749,7 @@ impl<'c> RenderingContext<'c> {
 
     fn render_function_pointer(&self, ptr: &FunctionPointer) -> Vec<Token> {
         let mut output = self.render_higher_rank_trait_bounds(&ptr.generic_params);
+        output.extend(render_abi(&ptr.header.abi));
         output.push(Token::kind("fn"));
         output.extend(self.render_fn_decl(&ptr.decl));
         output
@@ -646,7 +844,7 @@ impl<'c> RenderingContext<'c> {
         let mut output = vec![Token::symbol("*")];
         output.push(Token::keyword(if mutable { "mut" } else { "const" }));
         output.push(ws!());
-        output.extend(self.render_type(type_));
+        output.extend(self.render_pointee(type_));
         output
     }
 
@@ -663,7 +861,7 @@ impl<'c> RenderingContext<'c> {
         if mutable {
             output.extend(vec![Token::keyword("mut"), ws!()]);
         }
-        output.extend(self.render_type(type_));
+        output.extend(self.render_pointee(type_));
         output
     }
 
@@ -874,10 +1072,13 @@ impl<'c> RenderingContext<'c> {
                 output.extend(colon());
                 output.extend(self.render_generic_bounds(bounds));
             }
-            WherePredicate::RegionPredicate {
-                lifetime,
-                bounds: _,
-            } => output.push(Token::Lifetime(lifetime.clone())),
+            WherePredicate::RegionPredicate { lifetime, bounds } => {
+                output.push(Token::lifetime(lifetime));
+                if !bounds.is_empty() {
+                    output.extend(colon());
+                    output.extend(self.render_generic_bounds(bounds));
+                }
+            }
             WherePredicate::EqPredicate { lhs, rhs } => {
                 output.extend(self.render_type(lhs));
                 output.extend(equals());
@@ -892,10 +1093,17 @@ impl<'c> RenderingContext<'c> {
             GenericBound::TraitBound {
                 trait_,
                 generic_params,
-                ..
+                modifier,
             } => {
                 let mut output = vec![];
                 output.extend(self.render_higher_rank_trait_bounds(generic_params));
+                match modifier {
+                    TraitBoundModifier::None => {}
+                    TraitBoundModifier::Maybe => output.push(Token::symbol("?")),
+                    TraitBoundModifier::MaybeConst => {
+                        output.extend(vec![Token::symbol("~"), Token::keyword("const"), ws!()]);
+                    }
+                }
                 output.extend(self.render_resolved_path(trait_));
                 output
             }
@@ -966,10 +1174,80 @@ fn attr_relevant_for_public_apis<S: AsRef<str>>(attr: S) -> bool {
     false
 }
 
+/// Returns the attribute as `#[cfg(...)]` if it is a `#[cfg(...)]` or a
+/// `#[doc(cfg(...))]` attribute. Line breaks that rustdoc inserts in long
+/// attributes are removed.
+fn cfg_attr(attr: &str) -> Option<String> {
+    let predicate = attr
+        .strip_prefix("#[cfg(")
+        .and_then(|rest| rest.strip_suffix(")]"))
+        .or_else(|| {
+            attr.strip_prefix("#[doc(cfg(")
+                .and_then(|rest| rest.strip_suffix("))]"))
+        })?;
+    Some(format!(
+        "#[cfg({})]",
+        predicate.split_whitespace().collect::<Vec<_>>().join(" ")
+    ))
+}
+
 fn pub_() -> Vec<Token> {
     vec![Token::qualifier("pub"), ws!()]
 }
 
+/// Renders e.g. `pub(crate) ` for items that are only visible in part of the
+/// crate. Returns `None` for other items, including private items, since
+/// [`Visibility::Default`] is also used for e.g. items of trait impls.
+///
+/// rustdoc does not tell us how the visibility was written. `pub(self)`
+/// becomes [`Visibility::Default`] and `pub(super)` becomes a path to the
+/// module, so we render `pub(super)` if that module contains the module of the
+/// item.
+fn render_restricted_visibility(
+    visibility: &Visibility,
+    item_path: &[NameableItem],
+) -> Option<Vec<Token>> {
+    let restriction = match visibility {
+        Visibility::Crate => vec![Token::keyword("crate")],
+        Visibility::Restricted { parent, .. } if Some(parent) == super_module_id(item_path) => {
+            vec![Token::keyword("super")]
+        }
+        Visibility::Restricted { path, .. } => {
+            let mut output = vec![Token::keyword("in"), ws!()];
+            for (index, segment) in path.split("::").enumerate() {
+                if index > 0 {
+                    output.push(Token::symbol("::"));
+                }
+                match segment {
+                    "" => {}
+                    "crate" | "self" | "super" => output.push(Token::keyword(segment)),
+                    _ => output.push(Token::identifier(segment)),
+                }
+            }
+            output
+        }
+        Visibility::Public | Visibility::Default => return None,
+    };
+
+    let mut output = vec![Token::qualifier("pub"), Token::symbol("(")];
+    output.extend(restriction);
+    output.extend([Token::symbol(")"), ws!()]);
+    Some(output)
+}
+
+/// The id of the module that contains the module of the item, i.e. the module
+/// that `super` refers to in the item's module.
+fn super_module_id<'c>(item_path: &[NameableItem<'c>]) -> Option<&'c Id> {
+    item_path
+        .split_last()?
+        .1
+        .iter()
+        .rev()
+        .filter(|item| matches!(item.item.inner, ItemEnum::Module(_)))
+        .nth(1)
+        .map(|item| &item.item.id)
+}
+
 fn plus() -> Vec<Token> {
     vec![ws!(), Token::symbol("+"), ws!()]
 }
@@ -982,6 +1260,81 @@ fn comma() -> Vec<Token> {
     vec![Token::symbol(","), ws!()]
 }
 
+/// Renders e.g. ` = 100` for a `const` or `static`. Prefers `expr`, which is
+/// how the value is written in the source code, but rustdoc uses `_` for
+/// `expr`s that are not simple literals, in which case we fall back to the
+/// evaluated `value`, if any.
+fn render_value(expr: &str, value: Option<&str>) -> Vec<Token> {
+    let value = Some(expr).filter(|expr| *expr != "_").or(value);
+    value.map_or_else(Vec::new, |value| {
+        let mut output = equals();
+        output.push(Token::primitive(value));
+        output
+    })
+}
+
+/// If the item is defined directly in a trait, as opposed to in an `impl` of
+/// the trait.
+fn is_trait_item(item_path: &[NameableItem]) -> bool {
+    item_path.len() >= 2
+        && matches!(
+            item_path[item_path.len() - 2].item.inner,
+            ItemEnum::Trait(_)
+        )
+}
+
+/// If the item is defined directly in an `impl`, such as a method or an
+/// associated const.
+fn is_impl_item(item_path: &[NameableItem]) -> bool {
+    item_path.len() >= 2 && matches!(item_path[item_path.len() - 2].item.inner, ItemEnum::Impl(_))
+}
+
+fn is_union_field(item_path: &[NameableItem]) -> bool {
+    item_path.len() >= 2
+        && matches!(
+            item_path[item_path.len() - 2].item.inner,
+            ItemEnum::Union(_)
+        )
+}
+
+/// How rustdoc marks provided trait methods, i.e. trait methods with a default
+/// implementation.
+fn provided_body() -> Vec<Token> {
+    vec![
+        ws!(),
+        Token::symbol("{"),
+        ws!(),
+        Token::symbol("..."),
+        ws!(),
+        Token::symbol("}"),
+    ]
+}
+
+/// Renders e.g. `extern "C" ` for functions with a non-default ABI, and
+/// nothing for the default `Rust` ABI.
+fn render_abi(abi: &Abi) -> Vec<Token> {
+    let (name, unwind) = match abi {
+        Abi::Rust => return vec![],
+        Abi::C { unwind } => ("C", *unwind),
+        Abi::Cdecl { unwind } => ("cdecl", *unwind),
+        Abi::Stdcall { unwind } => ("stdcall", *unwind),
+        Abi::Fastcall { unwind } => ("fastcall", *unwind),
+        Abi::Aapcs { unwind } => ("aapcs", *unwind),
+        Abi::Win64 { unwind } => ("win64", *unwind),
+        Abi::SysV64 { unwind } => ("sysv64", *unwind),
+        Abi::System { unwind } => ("system", *unwind),
+        // rustdoc already quotes the name of other ABIs
+        Abi::Other(text) => (text.trim_matches('"'), false),
+    };
+    let unwind = if unwind { "-unwind" } else { "" };
+    vec![
+        Token::qualifier("extern"),
+        ws!(),
+        Token::qualifier(format!("\"{name}{unwind}\"")),
+        ws!(),
+    ]
+}
+
 fn equals() -> Vec<Token> {
     vec![ws!(), Token::symbol("="), ws!()]
 }
@@ -990,6 +1343,14 @@ fn arrow() -> Vec<Token> {
     vec![ws!(), Token::symbol("->"), ws!()]
 }
 
+/// Returns `true` if the last components of `path` are the components of
+/// `name`, e.g. if `["alloc", "vec", "Vec"]` ends with `vec::Vec`.
+fn path_ends_with(path: &[String], name: &str) -> bool {
+    let mut path = path.iter().rev();
+    name.rsplit("::")
+        .all(|component| path.next().map_or(false, |c| c == component))
+}
+
 #[cfg(test)]
 mod test {
     macro_rules! s {
@@ -1003,6 +1364,82 @@ mod test {
     use super::*;
     use rustdoc_types::Id;
 
+    #[test]
+    fn test_render_value() {
+        assert_eq!(
+            crate::tokens::tokens_to_string(&render_value("100", Some("100u32"))),
+            " = 100"
+        );
+        assert_eq!(
+            crate::tokens::tokens_to_string(&render_value("_", Some("3usize"))),
+            " = 3usize"
+        );
+        assert_eq!(render_value("_", None), vec![]);
+    }
+
+    #[test]
+    fn test_render_abi() {
+        let render = |abi| crate::tokens::tokens_to_string(&render_abi(&abi));
+
+        assert_eq!(render(Abi::Rust), "");
+        assert_eq!(render(Abi::C { unwind: false }), "extern \"C\" ");
+        assert_eq!(render(Abi::C { unwind: true }), "extern \"C-unwind\" ");
+        assert_eq!(render(Abi::System { unwind: false }), "extern \"system\" ");
+        assert_eq!(
+            render(Abi::Other(s!("\"vectorcall\""))),
+            "extern \"vectorcall\" "
+        );
+    }
+
+    #[test]
+    fn test_cfg_attr() {
+        assert_eq!(
+            cfg_attr(r#"#[cfg(feature = "x")]"#).as_deref(),
+            Some(r#"#[cfg(feature = "x")]"#)
+        );
+        assert_eq!(
+            cfg_attr("#[doc(cfg(all(unix,\nfeature = \"x\")))]").as_deref(),
+            Some(r#"#[cfg(all(unix, feature = "x"))]"#)
+        );
+        assert_eq!(cfg_attr("#[non_exhaustive]"), None);
+        assert_eq!(cfg_attr(r#"#[doc(alias = "x")]"#), None);
+    }
+
+    #[test]
+    fn test_render_restricted_visibility() {
+        let render = |visibility| {
+            render_restricted_visibility(&visibility, &[])
+                .map(|tokens| crate::tokens::tokens_to_string(&tokens))
+        };
+
+        assert_eq!(render(Visibility::Crate).as_deref(), Some("pub(crate) "));
+        assert_eq!(render(Visibility::Public), None);
+        assert_eq!(render(Visibility::Default), None);
+
+        // Each path segment is a token of its own
+        assert_eq!(
+            render_restricted_visibility(
+                &Visibility::Restricted {
+                    parent: Id(s!("id")),
+                    path: s!("::module::inner"),
+                },
+                &[],
+            ),
+            Some(vec![
+                Token::qualifier("pub"),
+                Token::symbol("("),
+                Token::keyword("in"),
+                ws!(),
+                Token::symbol("::"),
+                Token::identifier("module"),
+                Token::symbol("::"),
+                Token::identifier("inner"),
+                Token::symbol(")"),
+                ws!(),
+            ])
+        );
+    }
+
     #[test]
     fn test_type_infer() {
         assert_render(
@@ -1327,6 +1764,7 @@ mod test {
         let context = RenderingContext {
             crate_: &crate_,
             id_to_items: HashMap::new(),
+            external_items_by_name: HashMap::new(),
             options: Options::default(),
         };
 
@@ -0,0 +1,78 @@
+//! Contains facilities to sort [`PublicItem`]s in different ways. See
+//! [`crate::PublicApi::sorted_by`].
+
+use std::{cmp::Ordering, path::Path};
+
+use crate::{tokens::Token, PublicItem};
+
+/// Decides in what order two [`PublicItem`]s shall be sorted. Implemented for
+/// all closures and functions with a matching signature, so you can use
+/// [`by_path`], [`by_kind`], or your own function.
+pub trait Comparator {
+    /// Returns the ordering of `a` relative to `b`.
+    fn compare(&self, a: &PublicItem, b: &PublicItem) -> Ordering;
+}
+
+impl<F: Fn(&PublicItem, &PublicItem) -> Ordering> Comparator for F {
+    fn compare(&self, a: &PublicItem, b: &PublicItem) -> Ordering {
+        self(a, b)
+    }
+}
+
+/// Sorts items by path, so that e.g. a struct and its fields and `impl`s end
+/// up together. This is the order that [`crate::Options::sorted`] gives you.
+#[must_use]
+pub fn by_path(a: &PublicItem, b: &PublicItem) -> Ordering {
+    a.cmp(b)
+}
+
+/// Sorts items by their kind, such as `fn`, `struct`, or `impl`, in
+/// alphabetical order of the kind. Items of the same kind are sorted with
+/// [`by_path`].
+#[must_use]
+pub fn by_kind(a: &PublicItem, b: &PublicItem) -> Ordering {
+    kind(a).cmp(&kind(b)).then_with(|| by_path(a, b))
+}
+
+/// Sorts items by where in the source code they are defined, i.e. by file and
+/// then by line. Items without a [`PublicItem::source_location()`] come last.
+#[must_use]
+pub fn by_source_location(a: &PublicItem, b: &PublicItem) -> Ordering {
+    fn key(item: &PublicItem) -> Option<(&Path, usize)> {
+        item.source_location()
+            .map(|location| (location.file(), location.line()))
+    }
+    match (key(a), key(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// The kind of an item as rendered, e.g. `"struct field"`. For `impl`s, which
+/// are not rendered with any kind, this is `"impl"`.
+pub(crate) fn kind(item: &PublicItem) -> String {
+    // Only look at the start of the item, since e.g. `fn` can also appear in
+    // the type of an argument
+    let kinds: Vec<_> = item
+        .tokens()
+        .skip_while(|token| {
+            matches!(
+                token,
+                Token::Annotation(_) | Token::Qualifier(_) | Token::Whitespace
+            )
+        })
+        .take_while(|token| matches!(token, Token::Kind(_) | Token::Whitespace))
+        .filter_map(|token| match token {
+            Token::Kind(kind) => Some(kind.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    if kinds.is_empty() {
+        String::from("impl")
+    } else {
+        kinds.join(" ")
+    }
+}
@@ -0,0 +1,143 @@
+//! Deserializes rustdoc JSON straight from a reader, one item at a time, so
+//! that huge rustdoc JSON files do not need to fit in memory in addition to
+//! the deserialized [`Crate`]. Parts of each item that we never use, such as
+//! its intra-doc links, are dropped as soon as the item has been deserialized.
+//! Docs are kept, see [`crate::PublicItem::docs()`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use rustdoc_types::{Crate, ExternalCrate, Id, Item, ItemSummary};
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::{Error, Options, Result};
+
+/// Same as [`Crate`], but with an `index` that is deserialized with
+/// [`deserialize_index`].
+#[derive(Deserialize)]
+struct StreamedCrate {
+    root: Id,
+    crate_version: Option<String>,
+    includes_private: bool,
+    #[serde(deserialize_with = "deserialize_index")]
+    index: HashMap<Id, Item>,
+    paths: HashMap<Id, ItemSummary>,
+    external_crates: HashMap<u32, ExternalCrate>,
+    format_version: u32,
+}
+
+impl From<StreamedCrate> for Crate {
+    fn from(streamed: StreamedCrate) -> Self {
+        Self {
+            root: streamed.root,
+            crate_version: streamed.crate_version,
+            includes_private: streamed.includes_private,
+            index: streamed.index,
+            paths: streamed.paths,
+            external_crates: streamed.external_crates,
+            format_version: streamed.format_version,
+        }
+    }
+}
+
+/// Reads the rustdoc JSON at `path` into a [`Crate`] without reading the whole
+/// file into memory. Also returns a description of the format adapter that was
+/// used, if any, see [`crate::deserialize_crate`].
+pub(crate) fn read_crate(path: &Path, options: Options) -> Result<(Crate, Option<String>)> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    read_crate_from(reader, || std::fs::read(path), options)
+}
+
+/// Deserializes rustdoc JSON from `reader`. Only if the JSON does not have the
+/// shape of a [`Crate`] is all of it read with `read_all`, see
+/// [`read_crate`].
+fn read_crate_from(
+    reader: impl Read,
+    read_all: impl FnOnce() -> std::io::Result<Vec<u8>>,
+    options: Options,
+) -> Result<(Crate, Option<String>)> {
+    match deserialize_crate(reader) {
+        Ok(crate_) => Ok((crate_, None)),
+        // JSON of another format version has a different shape. The regular
+        // code path knows how to adapt other format versions and how to give
+        // good errors, so let it have a look
+        Err(error) if error.is_data() => crate::deserialize_crate(&read_all()?, options),
+        // The same error that `crate::integrity::check()` would give
+        Err(error) if error.is_eof() => Err(Error::CorruptRustdocJson(error)),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Deserializes rustdoc JSON from `reader`. The reader should be buffered,
+/// since it is read in small pieces.
+pub(crate) fn deserialize_crate(reader: impl Read) -> serde_json::Result<Crate> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer.disable_recursion_limit();
+    let crate_ = StreamedCrate::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(crate_.into())
+}
+
+fn deserialize_index<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<Id, Item>, D::Error> {
+    deserializer.deserialize_map(IndexVisitor)
+}
+
+struct IndexVisitor;
+
+impl<'de> Visitor<'de> for IndexVisitor {
+    type Value = HashMap<Id, Item>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a map of rustdoc JSON items")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut index = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((id, mut item)) = map.next_entry::<Id, Item>()? {
+            item.links = HashMap::new();
+            index.insert(id, item);
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all_must_not_be_called() -> std::io::Result<Vec<u8>> {
+        panic!("the whole rustdoc JSON must not be read");
+    }
+
+    #[test]
+    fn truncated_json_is_not_read_again() {
+        let result = read_crate_from(
+            r#"{"root": "0:0", "index": {"#.as_bytes(),
+            read_all_must_not_be_called,
+            Options::default(),
+        );
+        assert!(matches!(result, Err(Error::CorruptRustdocJson(_))));
+    }
+
+    #[test]
+    fn invalid_json_is_not_read_again() {
+        let result = read_crate_from(
+            "}}}}}}}}}".as_bytes(),
+            read_all_must_not_be_called,
+            Options::default(),
+        );
+        assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+    }
+
+    #[test]
+    fn json_of_other_shape_is_read_again() {
+        let json = r#"{"some": "json"}"#;
+        let result = read_crate_from(json.as_bytes(), || Ok(json.into()), Options::default());
+        assert!(matches!(result, Err(Error::CorruptRustdocJson(_))));
+    }
+}
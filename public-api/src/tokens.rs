@@ -84,6 +84,43 @@ impl Token {
     pub fn len(&self) -> usize {
         self.text().len()
     }
+    /// The `TextMate` scope name of this token, so that existing syntax
+    /// highlighters and color themes, e.g. those of `syntect`, can be used to
+    /// color rendered items. The mapping is:
+    ///
+    /// | Token                   | Scope                             |
+    /// |-------------------------|-----------------------------------|
+    /// | [`Token::Symbol`]       | `punctuation.rust`                |
+    /// | [`Token::Qualifier`]    | `storage.modifier.rust`           |
+    /// | [`Token::Kind`]         | `storage.type.rust`               |
+    /// | [`Token::Whitespace`]   | `source.rust`                     |
+    /// | [`Token::Identifier`]   | `variable.other.rust`             |
+    /// | [`Token::Annotation`]   | `meta.annotation.rust`            |
+    /// | [`Token::Self_`]        | `variable.language.rust`          |
+    /// | [`Token::Function`]     | `entity.name.function.rust`       |
+    /// | [`Token::Lifetime`]     | `storage.modifier.lifetime.rust`  |
+    /// | [`Token::Keyword`]      | `keyword.other.rust`              |
+    /// | [`Token::Generic`]      | `entity.name.type.parameter.rust` |
+    /// | [`Token::Primitive`]    | `support.type.primitive.rust`     |
+    /// | [`Token::Type`]         | `entity.name.type.rust`           |
+    #[must_use]
+    pub fn scope(&self) -> &'static str {
+        match self {
+            Self::Symbol(_) => "punctuation.rust",
+            Self::Qualifier(_) => "storage.modifier.rust",
+            Self::Kind(_) => "storage.type.rust",
+            Self::Whitespace => "source.rust",
+            Self::Identifier(_) => "variable.other.rust",
+            Self::Annotation(_) => "meta.annotation.rust",
+            Self::Self_(_) => "variable.language.rust",
+            Self::Function(_) => "entity.name.function.rust",
+            Self::Lifetime(_) => "storage.modifier.lifetime.rust",
+            Self::Keyword(_) => "keyword.other.rust",
+            Self::Generic(_) => "entity.name.type.parameter.rust",
+            Self::Primitive(_) => "support.type.primitive.rust",
+            Self::Type(_) => "entity.name.type.rust",
+        }
+    }
     /// Get the inner text of this token
     #[must_use]
     pub fn text(&self) -> &str {
@@ -3,7 +3,10 @@
 
 use std::{fmt::Write, path::Path};
 
-use public_api::{Error, Options, PublicApi};
+use public_api::{
+    diff::TokenDiff, module_tree::ModuleNode, tokens::Token, Error, GenericParamKind, ImplKind,
+    Options, PublicApi, PublicItem,
+};
 
 // rust-analyzer bug: https://github.com/rust-lang/rust-analyzer/issues/9173
 #[path = "../../test-utils/src/lib.rs"]
@@ -36,6 +39,63 @@ fn diff_with_added_items() {
     );
 }
 
+#[test]
+fn diff_between_owned_items() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let old = PublicApi::from_rustdoc_json(old_json, Options::default()).unwrap();
+    let new = PublicApi::from_rustdoc_json(new_json, Options::default()).unwrap();
+
+    let old_items: Vec<PublicItem> = old.into_items();
+    let new_items: Vec<PublicItem> = new.into_items();
+    let diff = public_api::diff::PublicApiDiff::between(old_items, new_items);
+
+    let pretty_printed = format!("{diff:#?}");
+    assert_eq_or_bless(
+        &pretty_printed,
+        "./tests/expected-output/diff_with_added_items.txt",
+    );
+}
+
+#[test]
+fn diff_json_strs() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let old_json = std::fs::read_to_string(old_json).unwrap();
+    let new_json = std::fs::read_to_string(new_json).unwrap();
+    let diff = public_api::diff_json_strs(&old_json, &new_json, Options::default()).unwrap();
+
+    let old = PublicApi::from_rustdoc_json_str(&old_json, Options::default()).unwrap();
+    let new = PublicApi::from_rustdoc_json_str(&new_json, Options::default()).unwrap();
+    assert_eq!(diff, public_api::diff::PublicApiDiff::between(old, new));
+    assert!(!diff.is_empty());
+}
+
+#[test]
+fn added_since() {
+    // Create independent build dirs so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let build_dir2 = tempdir().unwrap();
+
+    let old_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.1.0", &build_dir);
+    let new_json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir2);
+    let old = PublicApi::from_rustdoc_json(old_json, Options::default()).unwrap();
+    let new = PublicApi::from_rustdoc_json(new_json, Options::default()).unwrap();
+
+    let added = public_api::added_since(&old, &new);
+    let diff = public_api::diff::PublicApiDiff::between(old, new);
+    assert!(!added.is_empty());
+    assert_eq!(added, diff.added);
+}
+
 #[test]
 fn no_diff() {
     // Create independent build dirs so all tests can run in parallel
@@ -85,6 +145,809 @@ fn comprehensive_api_proc_macro() {
     );
 }
 
+#[test]
+fn renamed_proc_macro_reexports() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/proc_macro_reexport", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+
+    for expected in [
+        "pub proc macro proc_macro_reexport::renamed_proc_macro!()",
+        "pub proc macro proc_macro_reexport::#[renamed_proc_macro_attribute]",
+        "pub proc macro proc_macro_reexport::#[derive(RenamedDeriveMacro)]",
+    ] {
+        assert!(items.contains(&expected.to_owned()), "{items:#?}");
+    }
+}
+
+#[test]
+fn diff_against_own_plain_text_listing_is_empty() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let mut listing = String::new();
+    for item in api.items() {
+        writeln!(&mut listing, "{item}").unwrap();
+    }
+
+    let baseline = PublicApi::from_plain_text(&listing);
+    assert_eq!(baseline.items().count(), api.items().count());
+
+    let diff = public_api::diff::PublicApiDiff::between_baseline(&listing, api);
+    assert!(diff.is_empty(), "{diff:#?}");
+}
+
+#[test]
+fn render_plain() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    for item in api.items() {
+        assert_eq!(item.render_plain(), format!("{item}"));
+    }
+}
+
+#[test]
+fn impl_trait_in_argument_and_return_position() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let item = api
+        .items()
+        .map(PublicItem::render_plain)
+        .find(|item| item.contains("impl_trait_arg_and_return"))
+        .unwrap();
+
+    assert_eq!(
+        item,
+        "pub fn comprehensive_api::functions::impl_trait_arg_and_return(x: impl core::fmt::Display) -> impl core::iter::traits::iterator::Iterator<Item = u8>"
+    );
+}
+
+#[test]
+fn sorted_by_kind() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default())
+        .unwrap()
+        .sorted_by(public_api::sorting::by_kind);
+    let items: Vec<_> = api.items().map(PublicItem::render_plain).collect();
+
+    let position = |item: &str| items.iter().position(|i| i == item).unwrap();
+    assert!(
+        position("pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)")
+            < position("#[non_exhaustive] pub struct example_api::Struct")
+    );
+    assert!(
+        position("#[non_exhaustive] pub struct example_api::Struct")
+            < position("pub struct field example_api::Struct::v1_field: usize")
+    );
+
+    // Custom comparators work too
+    let api = api.sorted_by(|a: &PublicItem, b: &PublicItem| b.cmp(a));
+    let mut reversed: Vec<_> = api.items().collect();
+    reversed.reverse();
+    assert!(reversed.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn impl_kind() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let impl_kind_of = |item: &str| {
+        api.items()
+            .find(|i| i.to_string() == item)
+            .unwrap_or_else(|| panic!("missing item: {item}"))
+            .impl_kind()
+    };
+
+    assert_eq!(
+        impl_kind_of(
+            "pub fn example_api::function(v1_param: example_api::Struct, v2_param: usize)"
+        ),
+        None
+    );
+    assert_eq!(
+        impl_kind_of("#[non_exhaustive] pub struct example_api::Struct"),
+        None
+    );
+    assert_eq!(
+        impl_kind_of("impl core::fmt::Debug for example_api::Struct"),
+        Some(ImplKind::AutoDerived)
+    );
+    assert_eq!(
+        impl_kind_of("impl core::marker::Send for example_api::Struct"),
+        Some(ImplKind::AutoTrait)
+    );
+    assert_eq!(
+        impl_kind_of(
+            "impl<T> core::any::Any for example_api::Struct where T: 'static + ?core::marker::Sized"
+        ),
+        Some(ImplKind::Blanket)
+    );
+    assert_eq!(
+        impl_kind_of("pub fn example_api::Struct::type_id(&self) -> core::any::TypeId"),
+        Some(ImplKind::Blanket)
+    );
+}
+
+#[test]
+fn source_location() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let location = api
+        .items()
+        .find(|i| i.to_string() == "pub struct example_api::StructV2")
+        .and_then(PublicItem::source_location)
+        .unwrap();
+
+    assert_eq!(location.file(), Path::new("src/lib.rs"));
+    assert_eq!(location.line(), 12);
+}
+
+#[test]
+fn docs() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let json_str = std::fs::read_to_string(&json).unwrap();
+
+    // Files are deserialized differently than strings, so test both
+    for api in [
+        PublicApi::from_rustdoc_json(json, Options::default()).unwrap(),
+        PublicApi::from_rustdoc_json_str(json_str, Options::default()).unwrap(),
+    ] {
+        let docs = |path: &str| {
+            api.items()
+                .find(|i| i.has_path(path))
+                .unwrap()
+                .docs()
+                .map(ToOwned::to_owned)
+        };
+
+        assert_eq!(
+            docs("comprehensive_api::functions::documented").as_deref(),
+            Some("Returns `x` plus one.\n\nHas docs so that `PublicItem::docs()` can be tested.")
+        );
+        assert_eq!(docs("comprehensive_api::functions::plain"), None);
+    }
+}
+
+#[test]
+fn metrics() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir);
+    let mut options = Options::default();
+    options.simplified = true;
+    let metrics = PublicApi::from_rustdoc_json(json, options)
+        .unwrap()
+        .metrics();
+
+    assert_eq!(metrics.items_of_kind("mod"), 1);
+    assert_eq!(metrics.items_of_kind("struct"), 2);
+    assert_eq!(metrics.items_of_kind("struct field"), 3);
+    assert_eq!(metrics.items_of_kind("impl"), 1);
+    assert_eq!(metrics.items_of_kind("fn"), 2);
+    assert_eq!(metrics.items_of_kind("enum"), 0);
+    assert_eq!(metrics.items(), 9);
+    assert_eq!(metrics.generic_params, 0);
+    assert_eq!(metrics.trait_methods, 0);
+}
+
+#[test]
+fn metrics_trait_methods() {
+    let api = PublicApi::from_plain_text(
+        "pub mod krate
+pub trait krate::Trait
+pub fn krate::Trait::required(&self)
+pub fn krate::Trait::provided(&self)
+pub struct krate::S
+impl krate::Trait for krate::S
+pub fn krate::S::required(&self)",
+    );
+
+    let metrics = api.metrics();
+    assert_eq!(metrics.items_of_kind("fn"), 3);
+    assert_eq!(metrics.trait_methods, 2);
+}
+
+#[test]
+fn metrics_trait_methods_of_comprehensive_api() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let mut options = Options::default();
+    options.simplified = true;
+    let metrics = PublicApi::from_rustdoc_json(json, options)
+        .unwrap()
+        .metrics();
+
+    // E.g. `impl comprehensive_api::impls::ForUnit for ()` has a `pub fn
+    // comprehensive_api::impls::ForUnit::for_unit()` too, but only the fn in
+    // the trait itself counts
+    assert_eq!(metrics.trait_methods, 5);
+}
+
+#[test]
+fn items_in_source_order() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let locations: Vec<_> = api
+        .items_in_source_order()
+        .into_iter()
+        .map(PublicItem::source_location)
+        .collect();
+
+    assert_eq!(locations.len(), api.items().count());
+    for pair in locations.windows(2) {
+        match (pair[0], pair[1]) {
+            (Some(a), Some(b)) => assert!(
+                (a.file(), a.line()) <= (b.file(), b.line()),
+                "{a:?} is not before {b:?}"
+            ),
+            (None, Some(b)) => panic!("item without location before {b:?}"),
+            _ => {}
+        }
+    }
+
+    // Each file must only appear in one contiguous group
+    let mut files: Vec<_> = locations.iter().flatten().map(|l| l.file()).collect();
+    files.dedup();
+    let group_count = files.len();
+    files.sort();
+    files.dedup();
+    assert_eq!(files.len(), group_count);
+}
+
+#[test]
+fn generics() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let generics_of = |item: &str| {
+        api.items()
+            .find(|i| i.to_string() == item)
+            .unwrap()
+            .generics()
+            .iter()
+            .map(|param| (param.name().to_owned(), param.kind()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        generics_of("pub fn comprehensive_api::functions::const_generic<T, const N: usize>(t: [T; N]) -> [T; N]"),
+        vec![
+            ("T".to_owned(), GenericParamKind::Type),
+            ("N".to_owned(), GenericParamKind::Const),
+        ]
+    );
+    assert_eq!(
+        generics_of("pub fn comprehensive_api::functions::impl_multiple<T>(t: impl comprehensive_api::traits::Simple + core::convert::AsRef<T>) -> impl comprehensive_api::traits::Simple"),
+        vec![("T".to_owned(), GenericParamKind::Type)]
+    );
+    assert_eq!(
+        generics_of("pub fn comprehensive_api::functions::one_arg(x: usize)"),
+        vec![]
+    );
+}
+
+#[test]
+fn module_tree() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let tree = api.module_tree();
+
+    assert_eq!(items_in_tree(&tree), api.items().count());
+    assert_eq!(
+        tree.module.as_ref().map(ToString::to_string).as_deref(),
+        Some("pub mod comprehensive_api")
+    );
+    assert!(tree
+        .modules
+        .iter()
+        .any(
+            |m| m.module.as_ref().unwrap().to_string() == "pub mod comprehensive_api::structs"
+                && !m.items.is_empty()
+        ));
+}
+
+/// Counts all items in the tree, including the modules themselves
+fn items_in_tree(node: &ModuleNode) -> usize {
+    node.module.iter().count()
+        + node.items.len()
+        + node.modules.iter().map(items_in_tree).sum::<usize>()
+}
+
+#[test]
+fn lifetimes() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    for expected in [
+        "pub fn comprehensive_api::functions::named_lifetime<'a>(x: &'a str) -> &'a str",
+        "pub fn comprehensive_api::functions::elided_lifetime(x: &str) -> &str",
+        "pub fn comprehensive_api::functions::where_outlives<'a, 'b>(x: &'a str, _y: &'b str) -> &'a str where 'b: 'a",
+    ] {
+        assert!(items.contains(&expected.to_owned()), "missing item: {expected}");
+    }
+}
+
+#[test]
+fn enum_variant_fields() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    for expected in [
+        "pub enum variant comprehensive_api::enums::DiverseVariants::Struct { x: usize, y: comprehensive_api::enums::SingleVariant }",
+        "pub enum variant comprehensive_api::enums::DiverseVariants::StructWithHiddenField { visible: bool, .. }",
+        "pub enum variant comprehensive_api::enums::DiverseVariants::Tuple(usize, bool)",
+        "pub enum variant comprehensive_api::enums::EnumWithExplicitDiscriminants::TenPlusTen = 20",
+    ] {
+        assert!(items.contains(&expected.to_owned()), "missing item: {expected}");
+    }
+}
+
+#[test]
+fn extern_abi() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let extern_c = "pub extern \"C\" fn comprehensive_api::functions::extern_c()";
+    assert!(api.items().any(|item| item.to_string() == extern_c));
+
+    // Changing the ABI is a breaking change
+    let baseline = "pub extern \"system\" fn comprehensive_api::functions::extern_c()\n";
+    let new = api
+        .into_items()
+        .into_iter()
+        .filter(|item| item.to_string() == extern_c);
+    let diff = public_api::diff::PublicApiDiff::between_baseline(baseline, new);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].new.to_string(), extern_c);
+}
+
+#[test]
+fn async_fn() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let async_fn = "pub async fn comprehensive_api::functions::async_fn_ret_bool() -> bool";
+    assert!(api.items().any(|item| item.to_string() == async_fn));
+
+    // Making a function async is a breaking change
+    let baseline = "pub fn comprehensive_api::functions::async_fn_ret_bool() -> bool\n";
+    let new = api
+        .into_items()
+        .into_iter()
+        .filter(|item| item.to_string() == async_fn);
+    let diff = public_api::diff::PublicApiDiff::between_baseline(baseline, new);
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].new.to_string(), async_fn);
+    assert!(diff.changed[0]
+        .token_diff()
+        .contains(&TokenDiff::Added(Token::Qualifier("async".into()))));
+    assert!(diff.removed.is_empty());
+    assert!(diff.added.is_empty());
+}
+
+#[test]
+fn where_clauses_on_type_definitions() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    for expected in [
+        "pub struct comprehensive_api::structs::WithWhereClause<T> where T: core::clone::Clone",
+        "pub struct comprehensive_api::structs::TupleStructWithWhereClause<T>(pub T) where T: core::clone::Clone",
+        "pub enum comprehensive_api::enums::EnumWithGenerics<'a, T, D: core::fmt::Debug> where T: core::fmt::Display",
+    ] {
+        assert!(items.contains(&expected.to_owned()), "missing item: {expected}");
+    }
+}
+
+#[test]
+fn show_values() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let mut options = Options::default();
+    options.show_values = true;
+    let api = PublicApi::from_rustdoc_json(json, options).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    for expected in [
+        "pub const comprehensive_api::constants::CONST: &str = \"const\"",
+        "pub const comprehensive_api::constants::MAX: u32 = 100",
+        "pub static comprehensive_api::statics::ANSWER: i8 = 42",
+        "pub enum variant comprehensive_api::enums::EnumWithExplicitDiscriminants::First = 1",
+    ] {
+        assert!(
+            items.contains(&expected.to_owned()),
+            "missing item: {expected}"
+        );
+    }
+}
+
+#[test]
+fn show_values_keeps_const_generic_args() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let const_generic_items = |show_values| {
+        let mut options = Options::default();
+        options.simplified = false;
+        options.show_values = show_values;
+        PublicApi::from_rustdoc_json(&json, options)
+            .unwrap()
+            .items()
+            .map(ToString::to_string)
+            .filter(|item| item.contains("ConstArg") || item.contains("const_generic"))
+            .collect::<Vec<_>>()
+    };
+
+    let items = const_generic_items(true);
+    assert!(items
+        .iter()
+        .any(|item| item.contains("for comprehensive_api::structs::ConstArg<T, N>")));
+    assert!(items.contains(&"pub fn comprehensive_api::functions::const_generic<T, const N: usize>(t: [T; N]) -> [T; N]".to_owned()));
+    assert_eq!(items, const_generic_items(false));
+}
+
+#[test]
+fn relative_paths() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let mut options = Options::default();
+    options.relative_paths = true;
+    let api = PublicApi::from_rustdoc_json(json, options).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    for expected in [
+        "pub mod comprehensive_api",
+        "pub fn exports::issue_145::external_arg_type(_transform: exports::issue_145::external::External)",
+        "impl traits::Simple for structs::Unit",
+        "impl core::marker::Send for structs::Unit",
+    ] {
+        assert!(
+            items.contains(&expected.to_owned()),
+            "missing item: {expected}"
+        );
+    }
+    assert!(!items
+        .iter()
+        .any(|item| item.contains("comprehensive_api::")));
+
+    // E.g. `pub mod structs` has no `::` either, but is not the crate root
+    let crate_roots: Vec<_> = api
+        .items()
+        .filter(|item| item.is_crate_root())
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(crate_roots, vec!["pub mod comprehensive_api"]);
+}
+
+#[test]
+fn mark_provided_methods() {
+    let json = include_str!("./rustdoc-json/provided_methods.json");
+
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert!(items.contains(&"pub fn provided::Trait::provided()".to_owned()));
+
+    let mut options = Options::default();
+    options.mark_provided_methods = true;
+    let api = PublicApi::from_rustdoc_json_str(json, options).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert!(items.contains(&"pub fn provided::Trait::required()".to_owned()));
+    assert!(items.contains(&"pub fn provided::Trait::provided() { ... }".to_owned()));
+}
+
+#[test]
+fn repr() {
+    let json = include_str!("./rustdoc-json/repr.json");
+
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert_eq!(items, vec!["pub mod repr", "#[repr(C)] pub struct repr::S"]);
+
+    // `#[repr(Rust)]` is the default, so rustdoc does not show it
+    let default_repr_json = json.replace(r##""attrs": ["#[repr(C)]"]"##, r#""attrs": []"#);
+    let diff = public_api::diff_json_strs(json, default_repr_json, Options::default()).unwrap();
+    assert!(diff.removed.is_empty());
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(
+        diff.changed[0].old.to_string(),
+        "#[repr(C)] pub struct repr::S"
+    );
+    assert_eq!(diff.changed[0].new.to_string(), "pub struct repr::S");
+}
+
+#[test]
+fn edition_independent_paths() {
+    let json = include_str!("./rustdoc-json/edition_paths.json");
+
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert_eq!(
+        items,
+        vec![
+            "pub mod editions",
+            "pub fn editions::edition_2015() -> editions::private::Hidden",
+            "pub fn editions::edition_2018() -> editions::private::Hidden",
+            "pub fn editions::external() -> alloc::private::Hidden",
+        ]
+    );
+}
+
+#[test]
+fn alternate_display_is_indented() {
+    let json = include_str!("./rustdoc-json/edition_paths.json");
+
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(|item| format!("{item:#}")).collect();
+    assert_eq!(items[0], "pub mod editions");
+    assert_eq!(
+        items[1],
+        "  pub fn editions::edition_2015() -> editions::private::Hidden"
+    );
+
+    // `{:#?}` does its own indentation
+    assert_eq!(
+        format!("{:#?}", api.items().next().unwrap()),
+        "pub mod editions"
+    );
+}
+
+#[test]
+fn crate_docs() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let expected =
+        Some("Contains all kinds of public items, to test that each kind is handled\ncorrectly.");
+
+    let api = PublicApi::from_rustdoc_json(&json, Options::default()).unwrap();
+    assert_eq!(api.crate_docs(), expected);
+
+    let json = std::fs::read_to_string(json).unwrap();
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    assert_eq!(api.crate_docs(), expected);
+
+    let api = PublicApi::from_plain_text("pub mod krate");
+    assert_eq!(api.crate_docs(), None);
+}
+
+#[test]
+fn contains_path() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+
+    assert!(api.contains_path("example_api"));
+    assert!(api.contains_path("example_api::Struct"));
+    assert!(api.contains_path("example_api::Struct::v1_field"));
+    assert!(!api.contains_path("example_api::Struct::v3_field"));
+    assert!(!api.contains_path("example_api::NoSuchStruct"));
+    assert!(!api.contains_path("Struct"));
+    assert!(!api.contains_path(""));
+}
+
+#[test]
+fn crate_info() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir);
+    let api = PublicApi::from_rustdoc_json(&json, Options::default()).unwrap();
+
+    let crate_info = api.crate_info().unwrap();
+    assert_eq!(crate_info.name, "example_api");
+    assert_eq!(crate_info.version.as_deref(), Some("0.3.0"));
+    assert_eq!(crate_info.item_count, api.items().count());
+
+    // The items are not kept when streamed, but they are still counted
+    let streamed = PublicApi::for_each_item_in_rustdoc_json(json, Options::default(), |_| {})
+        .unwrap()
+        .crate_info()
+        .unwrap();
+    assert_eq!(streamed, crate_info);
+
+    assert!(PublicApi::from_plain_text("pub mod example_api")
+        .crate_info()
+        .is_none());
+}
+
+#[test]
+fn from_multiple() {
+    let build_dir = tempdir().unwrap();
+    let example_api = rustdoc_json_path_for_crate("../test-apis/example_api-v0.3.0", &build_dir);
+    let comprehensive_api =
+        rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_multiple(
+        &[example_api.as_path(), comprehensive_api.as_path()],
+        Options::default(),
+    )
+    .unwrap();
+
+    assert!(api.contains_path("example_api::Struct"));
+    assert!(api.contains_path("comprehensive_api::functions::async_fn_ret_bool"));
+    assert!(api
+        .items()
+        .any(|item| item.to_string() == "pub mod example_api"));
+    assert!(api
+        .items()
+        .any(|item| item.to_string() == "pub mod comprehensive_api"));
+    assert!(api.crate_info().is_none());
+
+    let example_api_items = PublicApi::from_rustdoc_json(example_api, Options::default())
+        .unwrap()
+        .items()
+        .count();
+    let comprehensive_api_items =
+        PublicApi::from_rustdoc_json(comprehensive_api, Options::default())
+            .unwrap()
+            .items()
+            .count();
+    assert_eq!(
+        api.items().count(),
+        example_api_items + comprehensive_api_items
+    );
+}
+
+#[test]
+fn restricted_visibility_with_private_items() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/comprehensive_api/Cargo.toml")
+        .target_dir(&build_dir)
+        .cargo_config("build.rustdocflags", r#"["--document-private-items"]"#)
+        .quiet(true)
+        .build()
+        .unwrap();
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+
+    assert!(items.contains(
+        &"pub(crate) struct field comprehensive_api::structs::PrivateField::x: usize".to_owned()
+    ));
+    assert!(items.contains(
+        &"pub(super) fn comprehensive_api::exports::v1::only_visible_in_exports()".to_owned()
+    ));
+    assert!(
+        items.contains(&"pub struct field comprehensive_api::structs::Plain::x: usize".to_owned())
+    );
+}
+
+#[test]
+fn exported_macros() {
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+
+    assert!(items.contains(&"pub macro comprehensive_api::simple_macro!".to_owned()));
+    assert!(
+        items.contains(&"pub macro comprehensive_api::macros::reexported_simple_macro!".to_owned())
+    );
+}
+
+/// Tests the documented `TextMate` scope of each kind of token, and renders
+/// items like a highlighter that styles text by scope, such as `syntect`,
+/// would, to make sure that the tokens make up the whole item.
+#[test]
+fn token_scopes() {
+    let s = String::from;
+    assert_eq!(Token::Symbol(s("::")).scope(), "punctuation.rust");
+    assert_eq!(Token::Qualifier(s("pub")).scope(), "storage.modifier.rust");
+    assert_eq!(Token::Kind(s("fn")).scope(), "storage.type.rust");
+    assert_eq!(Token::Whitespace.scope(), "source.rust");
+    assert_eq!(Token::Identifier(s("field")).scope(), "variable.other.rust");
+    assert_eq!(Token::Annotation(s("#[a]")).scope(), "meta.annotation.rust");
+    assert_eq!(Token::Self_(s("self")).scope(), "variable.language.rust");
+    assert_eq!(Token::Function(s("f")).scope(), "entity.name.function.rust");
+    assert_eq!(
+        Token::Lifetime(s("'a")).scope(),
+        "storage.modifier.lifetime.rust"
+    );
+    assert_eq!(Token::Keyword(s("where")).scope(), "keyword.other.rust");
+    assert_eq!(
+        Token::Generic(s("T")).scope(),
+        "entity.name.type.parameter.rust"
+    );
+    assert_eq!(
+        Token::Primitive(s("usize")).scope(),
+        "support.type.primitive.rust"
+    );
+    assert_eq!(Token::Type(s("Struct")).scope(), "entity.name.type.rust");
+
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+    let api = PublicApi::from_rustdoc_json(json, Options::default()).unwrap();
+
+    for item in api.items() {
+        let rendered: String = item.tokens().map(Token::text).collect();
+        assert_eq!(rendered, item.to_string());
+    }
+}
+
+#[test]
+fn qualify_external() {
+    let json = include_str!("./rustdoc-json/unresolved_external_path.json");
+
+    let api = PublicApi::from_rustdoc_json_str(json, Options::default()).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert!(items.contains(&"pub fn unresolved::bytes() -> Vec<u8>".to_owned()));
+
+    let mut options = Options::default();
+    options.qualify_external = true;
+    let api = PublicApi::from_rustdoc_json_str(json, options).unwrap();
+    let items: Vec<_> = api.items().map(ToString::to_string).collect();
+    assert!(items.contains(&"pub fn unresolved::bytes() -> alloc::vec::Vec<u8>".to_owned()));
+}
+
+#[test]
+fn from_bytes_same_as_from_str() {
+    let path = "./tests/rustdoc-json/unresolved_external_path.json";
+    let bytes = std::fs::read(path).unwrap();
+    let string = std::fs::read_to_string(path).unwrap();
+
+    let from_bytes = PublicApi::from_rustdoc_json_bytes(bytes, Options::default()).unwrap();
+    let from_str = PublicApi::from_rustdoc_json_str(string, Options::default()).unwrap();
+    assert!(from_bytes.items().count() > 0);
+    assert_eq!(
+        from_bytes.items().collect::<Vec<_>>(),
+        from_str.items().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn invalid_utf8() {
+    let result = PublicApi::from_rustdoc_json_bytes([b'"', 0xff, b'"'], Options::default());
+    assert!(matches!(result, Err(Error::SerdeJsonError(_))));
+}
+
 #[test]
 fn invalid_json() {
     let result = PublicApi::from_rustdoc_json_str("}}}}}}}}}", Options::default());
@@ -92,6 +955,141 @@ fn invalid_json() {
     assert!(matches!(result, Err(Error::SerdeJsonError(_))));
 }
 
+#[test]
+fn truncated_json() {
+    let json = std::fs::read("./tests/rustdoc-json/unresolved_external_path.json").unwrap();
+    let dir = tempdir().unwrap();
+    let truncated_path = dir.path().join("truncated.json");
+    std::fs::write(&truncated_path, &json[..json.len() / 2]).unwrap();
+
+    let result = PublicApi::from_rustdoc_json(&truncated_path, Options::default());
+    assert!(matches!(result, Err(Error::CorruptRustdocJson(_))));
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .starts_with("rustdoc JSON looks truncated or corrupt"));
+}
+
+#[test]
+fn json_without_rustdoc_keys() {
+    let result = PublicApi::from_rustdoc_json_str(r#"{"some": "json"}"#, Options::default());
+    assert!(matches!(result, Err(Error::CorruptRustdocJson(_))));
+}
+
+#[test]
+fn invalid_item_names_json_path() {
+    let json = include_str!("./rustdoc-json/provided_methods.json").replacen(
+        r#""c_variadic": false"#,
+        r#""c_variadic": "no""#,
+        1,
+    );
+
+    let result = PublicApi::from_rustdoc_json_str(json, Options::default());
+    let Err(Error::InvalidRustdocJson { path, .. }) = &result else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert!(path.starts_with("index.0:2"), "unexpected path: {path}");
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .starts_with("rustdoc JSON could not be parsed at `index.0:2"));
+}
+
+/// Rustdoc JSON with an unknown format version and an item of an unknown kind.
+const RUSTDOC_JSON_FROM_THE_FUTURE: &str = include_str!("./rustdoc-json/format_version_9999.json");
+
+#[test]
+fn unsupported_format_version() {
+    let result = PublicApi::from_rustdoc_json_str(RUSTDOC_JSON_FROM_THE_FUTURE, Options::default());
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedFormatVersion { actual: 9999, .. })
+    ));
+}
+
+#[test]
+fn format_version_override() {
+    let mut options = Options::default();
+    options.format_version = Some(9999);
+
+    let public_api = PublicApi::from_rustdoc_json_str(RUSTDOC_JSON_FROM_THE_FUTURE, options)
+        .expect("best-effort parsing should succeed");
+
+    let items: Vec<_> = public_api.items().map(ToString::to_string).collect();
+    assert_eq!(items, vec!["pub mod future"]);
+    assert_eq!(
+        public_api.missing_item_ids().collect::<Vec<_>>(),
+        vec!["0:1"]
+    );
+    assert_eq!(
+        public_api.format_adapter(),
+        Some("best-effort parsing of rustdoc JSON format version 9999")
+    );
+}
+
+#[test]
+fn format_version_override_with_other_version() {
+    let mut options = Options::default();
+    options.format_version = Some(9998);
+
+    let result = PublicApi::from_rustdoc_json_str(RUSTDOC_JSON_FROM_THE_FUTURE, options);
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedFormatVersion {
+            expected: 9998,
+            actual: 9999,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn public_dependencies() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+    let json = rustdoc_json_path_for_crate("../test-apis/comprehensive_api", &build_dir);
+
+    // Not computed unless asked for
+    let public_api = PublicApi::from_rustdoc_json(&json, Options::default()).unwrap();
+    assert_eq!(public_api.public_dependencies().count(), 0);
+
+    let mut options = Options::default();
+    options.public_dependencies = true;
+    let public_api = PublicApi::from_rustdoc_json(&json, options).unwrap();
+    assert_eq!(
+        public_api.public_dependencies().collect::<Vec<_>>(),
+        vec!["example_api"]
+    );
+}
+
+#[test]
+fn previous_format_version_is_adapted() {
+    let json = include_str!("./rustdoc-json/format_version_21.json");
+
+    let public_api = PublicApi::from_rustdoc_json_str(json, Options::default())
+        .expect("rustdoc JSON of the previous format version should be adapted");
+
+    let items: Vec<_> = public_api.items().map(ToString::to_string).collect();
+    assert_eq!(items, vec!["pub mod previous", "pub type u8"]);
+    assert_eq!(
+        public_api.format_adapter(),
+        Some("rustdoc JSON format version 21 adapted to format version 22")
+    );
+}
+
+#[test]
+fn format_adapter_is_none_for_current_format_version() {
+    // Create independent build dir so all tests can run in parallel
+    let build_dir = tempdir().unwrap();
+
+    let public_api = PublicApi::from_rustdoc_json(
+        rustdoc_json_path_for_crate("../test-apis/example_api-v0.2.0", &build_dir),
+        Options::default(),
+    )
+    .unwrap();
+    assert_eq!(public_api.format_adapter(), None);
+}
+
 #[test]
 fn options() {
     let options = Options::default();
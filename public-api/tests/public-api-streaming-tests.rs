@@ -0,0 +1,147 @@
+//! Lives in its own test binary, since it installs a global allocator that
+//! keeps track of the peak memory usage and the number of allocations, which
+//! other tests would disturb.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use public_api::{Options, PublicApi};
+use tempfile::tempdir;
+
+struct PeakTrackingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakTrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(current, Ordering::SeqCst);
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakTrackingAllocator = PeakTrackingAllocator;
+
+const ITEM_COUNT: usize = 1000;
+const LINK_COUNT: usize = 128;
+const LINK_LEN: usize = 128;
+
+/// Creates rustdoc JSON with [`ITEM_COUNT`] functions that each have
+/// [`LINK_COUNT`] intra-doc links of [`LINK_LEN`] bytes. Links are dropped as
+/// soon as an item has been deserialized, so they make up the bulk of the JSON
+/// but not of the memory needed. Docs are kept, so they are short.
+fn large_rustdoc_json() -> String {
+    let docs = "Some docs.";
+    let links: Vec<_> = (0..LINK_COUNT)
+        .map(|i| format!(r#""{}{i:03}": "0:0""#, "x".repeat(LINK_LEN - 3)))
+        .collect();
+    let links = links.join(", ");
+    let function_ids: Vec<_> = (1..=ITEM_COUNT).map(|i| format!("\"0:{i}\"")).collect();
+    let functions: Vec<_> = (1..=ITEM_COUNT)
+        .map(|i| {
+            format!(
+                r#""0:{i}": {{
+                    "id": "0:{i}", "crate_id": 0, "name": "function_{i}", "span": null,
+                    "visibility": "public", "docs": "{docs}", "links": {{ {links} }}, "attrs": [],
+                    "deprecation": null, "kind": "function",
+                    "inner": {{
+                        "decl": {{ "inputs": [], "output": null, "c_variadic": false }},
+                        "generics": {{ "params": [], "where_predicates": [] }},
+                        "header": {{ "const": false, "unsafe": false, "async": false, "abi": "Rust" }},
+                        "has_body": true
+                    }}
+                }}"#
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{
+            "root": "0:0",
+            "crate_version": null,
+            "includes_private": false,
+            "index": {{
+                "0:0": {{
+                    "id": "0:0", "crate_id": 0, "name": "large", "span": null,
+                    "visibility": "public", "docs": "{docs}", "links": {{}}, "attrs": [],
+                    "deprecation": null, "kind": "module",
+                    "inner": {{ "is_crate": true, "items": [{}], "is_stripped": false }}
+                }},
+                {}
+            }},
+            "paths": {{}},
+            "external_crates": {{}},
+            "format_version": {}
+        }}"#,
+        function_ids.join(", "),
+        functions.join(",\n"),
+        rustdoc_types::FORMAT_VERSION,
+    )
+}
+
+#[test]
+fn large_rustdoc_json_is_streamed() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("large.json");
+    let json = large_rustdoc_json();
+    let json_len = json.len();
+    std::fs::write(&path, &json).unwrap();
+    let expected = PublicApi::from_rustdoc_json_str(&json, Options::default()).unwrap();
+    drop(json);
+
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let public_api = PublicApi::from_rustdoc_json(&path, Options::default()).unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+
+    assert!(
+        peak < json_len / 4,
+        "peak memory usage {peak} is not small compared to the JSON size {json_len}"
+    );
+    assert_eq!(public_api.items().count(), ITEM_COUNT + 1);
+    assert_eq!(
+        public_api.into_items(),
+        expected.into_items(),
+        "streaming must not change what items are found"
+    );
+}
+
+#[test]
+fn items_are_passed_on_as_soon_as_they_are_rendered() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("large.json");
+    std::fs::write(&path, large_rustdoc_json()).unwrap();
+    let expected = PublicApi::from_rustdoc_json(&path, Options::default()).unwrap();
+
+    let mut items = vec![];
+    let mut allocations_at_first_item = None;
+    PublicApi::for_each_item_in_rustdoc_json(&path, Options::default(), |item| {
+        allocations_at_first_item.get_or_insert_with(|| ALLOCATIONS.load(Ordering::SeqCst));
+        items.push(item);
+    })
+    .unwrap();
+    let allocations_after_first_item =
+        ALLOCATIONS.load(Ordering::SeqCst) - allocations_at_first_item.unwrap();
+
+    // Rendering an item takes several allocations, so if most items had been
+    // rendered before the first one was passed on, there would be few
+    // allocations left
+    assert!(
+        allocations_after_first_item > 5 * ITEM_COUNT,
+        "only {allocations_after_first_item} allocations after the first item"
+    );
+    assert_eq!(
+        items,
+        expected.into_items(),
+        "items must be passed on in sorted order"
+    );
+}
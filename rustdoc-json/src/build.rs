@@ -1,9 +1,16 @@
+use super::BuildArtifacts;
 use super::BuildError;
 use super::Builder;
+use super::ColorChoice;
+use super::ProgressCallback;
 
 use std::{
+    io::{BufRead, BufReader},
+    panic::{RefUnwindSafe, UnwindSafe},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
 };
 
 /// For development purposes only. Sometimes when you work on this project you
@@ -13,22 +20,154 @@ const OVERRIDDEN_TOOLCHAIN: Option<&str> = option_env!("RUSTDOC_JSON_OVERRIDDEN_
 
 /// Run `cargo rustdoc` to produce rustdoc JSON and return the path to the built
 /// file.
-pub fn run_cargo_rustdoc(options: Builder) -> Result<PathBuf, BuildError> {
+pub fn run_cargo_rustdoc(mut options: Builder) -> Result<PathBuf, BuildError> {
+    if !options.build_std.is_empty() {
+        if let Some(toolchain) = OVERRIDDEN_TOOLCHAIN.or(options.toolchain.as_deref()) {
+            if !toolchain.starts_with("nightly") {
+                return Err(BuildError::BuildStdRequiresNightly(toolchain.to_owned()));
+            }
+        } else if let Some(version) = cargo_version(options.current_dir.as_deref()) {
+            // No toolchain was requested, so the `cargo` in PATH is used
+            if !is_nightly_version(&version) {
+                return Err(BuildError::BuildStdRequiresNightly(version));
+            }
+        }
+    }
+
     let mut cmd = cargo_rustdoc_command(&options);
-    if cmd.status()?.success() {
+    let (status, captured_stderr) = match options.progress_callback.take() {
+        Some(callback) => run_with_progress_callback(&mut cmd, callback, options.timeout),
+        None => spawn(&mut cmd).and_then(|mut child| {
+            Ok((
+                wait_with_timeout(&mut child, options.timeout)?,
+                String::from("See above"),
+            ))
+        }),
+    }
+    .map_err(|error| rustup_not_found_error(error, &cmd))?;
+    if status.success() {
         rustdoc_json_path_for_manifest_path(
             options.manifest_path,
-            options.package.as_deref(),
+            options.bin.as_deref().or(options.package.as_deref()),
             options.target_dir.as_deref(),
             options.target.as_deref(),
+            options.metadata.as_ref(),
         )
     } else {
         let manifest = cargo_manifest::Manifest::from_path(&options.manifest_path)?;
         if manifest.package.is_none() && manifest.workspace.is_some() {
             Err(BuildError::VirtualManifest(options.manifest_path))
+        } else if manifest.package.is_some()
+            && manifest.lib.is_none()
+            && options.package.is_none()
+            && options.bin.is_none()
+        {
+            Err(BuildError::NoLibraryTarget(options.manifest_path))
         } else {
-            Err(BuildError::General(String::from("See above")))
+            Err(BuildError::General(captured_stderr))
+        }
+    }
+}
+
+/// The output of `cargo --version` for the `cargo` in PATH, e.g. `cargo
+/// 1.70.0-nightly (9e2fe2a 2023-03-14)`. `None` if it could not be found out,
+/// in which case the build itself will fail with a better error.
+fn cargo_version(current_dir: Option<&Path>) -> Option<String> {
+    let mut command = Command::new("cargo");
+    if let Some(current_dir) = current_dir {
+        command.current_dir(current_dir);
+    }
+    let output = command.arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// If `version` is the output of `cargo --version` of a nightly toolchain, or
+/// of a toolchain built from source, which also supports `-Z` flags.
+fn is_nightly_version(version: &str) -> bool {
+    version.contains("-nightly") || version.contains("-dev")
+}
+
+fn spawn(cmd: &mut Command) -> Result<Child, BuildError> {
+    Ok(cmd.spawn()?)
+}
+
+/// If `cmd` could not be spawned because it runs `rustup` and `rustup` is not
+/// installed, turns `error` into [`BuildError::RustupNotFound`], since the
+/// plain IO error does not tell what the problem is.
+fn rustup_not_found_error(error: BuildError, cmd: &Command) -> BuildError {
+    match error {
+        BuildError::IoError(io_error)
+            if io_error.kind() == std::io::ErrorKind::NotFound && cmd.get_program() == "rustup" =>
+        {
+            // The args are `run <toolchain> cargo ...`
+            let toolchain = cmd.get_args().nth(1).unwrap_or_default();
+            BuildError::RustupNotFound(toolchain.to_string_lossy().into_owned())
         }
+        error => error,
+    }
+}
+
+/// Runs `cmd` and passes each line that it writes to stderr to `callback`
+/// rather than letting it write directly to our stderr. Returns the exit status
+/// together with everything that was written to stderr.
+fn run_with_progress_callback(
+    cmd: &mut Command,
+    mut callback: ProgressCallback,
+    timeout: Option<Duration>,
+) -> Result<(ExitStatus, String), BuildError> {
+    let mut child = spawn(cmd.stderr(Stdio::piped()))?;
+
+    // Read stderr on a separate thread so that we can enforce the timeout
+    // even if the build stops writing to stderr
+    let stderr = child.stderr.take();
+    let reader = std::thread::spawn(move || -> std::io::Result<String> {
+        let mut captured_stderr = String::new();
+        if let Some(stderr) = stderr {
+            for line in BufReader::new(stderr).lines() {
+                let line = line?;
+                (callback.0)(&line);
+                captured_stderr.push_str(&line);
+                captured_stderr.push('\n');
+            }
+        }
+        Ok(captured_stderr)
+    });
+
+    // If we time out we do not wait for the reader thread, since processes
+    // spawned by `cargo` might keep stderr open for a while longer
+    let status = wait_with_timeout(&mut child, timeout)?;
+    let captured_stderr = reader
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+
+    Ok((status, captured_stderr))
+}
+
+/// Waits for `child` to exit. If it has not exited within `timeout`, it is
+/// killed and [`BuildError::Timeout`] is returned.
+fn wait_with_timeout(
+    child: &mut Child,
+    timeout: Option<Duration>,
+) -> Result<ExitStatus, BuildError> {
+    let Some(timeout) = timeout else {
+        return Ok(child.wait()?);
+    };
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Err(BuildError::Timeout(timeout));
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(10)));
     }
 }
 
@@ -41,14 +180,25 @@ fn cargo_rustdoc_command(options: &Builder) -> Command {
     let Builder {
         toolchain: requested_toolchain,
         manifest_path,
+        current_dir,
         target_dir,
         target,
         quiet,
+        verbose,
+        keep_going,
         no_default_features,
         all_features,
         features,
+        build_std,
+        cargo_config,
+        extra_args,
         package,
+        bin,
         cap_lints,
+        timeout: _,
+        color,
+        progress_callback,
+        metadata: _,
     } = options;
 
     let mut command = OVERRIDDEN_TOOLCHAIN
@@ -62,15 +212,37 @@ fn cargo_rustdoc_command(options: &Builder) -> Command {
             },
         );
 
+    if let Some(current_dir) = current_dir {
+        command.current_dir(current_dir);
+    }
+    for (key, value) in cargo_config {
+        command.arg("--config");
+        command.arg(format!("{key}={value}"));
+    }
     command.arg("rustdoc");
-    command.arg("--lib");
+    if let Some(bin) = bin {
+        command.args(["--bin", bin]);
+    } else {
+        command.arg("--lib");
+    }
+    // Color codes are just noise in the lines passed to a progress callback
+    let color = color.or_else(|| progress_callback.as_ref().map(|_| ColorChoice::Never));
+    if let Some(color) = color {
+        command.args(["--color", color.as_arg()]);
+    }
     if let Some(target_dir) = target_dir {
         command.arg("--target-dir");
         command.arg(target_dir);
     }
-    if *quiet {
+    // `cargo` does not accept both `--quiet` and `--verbose`
+    if *verbose {
+        command.arg("--verbose");
+    } else if *quiet {
         command.arg("--quiet");
     }
+    if *keep_going {
+        command.arg("--keep-going");
+    }
     command.arg("--manifest-path");
     command.arg(manifest_path);
     if let Some(target) = target {
@@ -86,9 +258,13 @@ fn cargo_rustdoc_command(options: &Builder) -> Command {
     for feature in features {
         command.args(["--features", feature]);
     }
+    if !build_std.is_empty() {
+        command.arg(format!("-Zbuild-std={}", build_std.join(",")));
+    }
     if let Some(package) = package {
         command.args(["--package", package]);
     }
+    command.args(extra_args);
     command.arg("--");
     command.args(["-Z", "unstable-options"]);
     command.args(["--output-format", "json"]);
@@ -102,15 +278,18 @@ fn cargo_rustdoc_command(options: &Builder) -> Command {
 /// `crate-name` to `crate_name`.
 fn rustdoc_json_path_for_manifest_path(
     manifest_path: impl AsRef<Path>,
-    package: Option<&str>,
+    crate_name: Option<&str>,
     target_dir: Option<&Path>,
     target: Option<&str>,
+    metadata: Option<&cargo_metadata::Metadata>,
 ) -> Result<PathBuf, BuildError> {
-    let target_dir = match target_dir {
-        Some(target_dir) => target_dir.to_owned(),
-        None => target_directory(&manifest_path)?,
+    let target_dir = match (target_dir, metadata) {
+        (Some(target_dir), _) => target_dir.to_owned(),
+        (None, Some(metadata)) => metadata.target_directory.as_std_path().to_owned(),
+        (None, None) => target_directory(&manifest_path)?,
     };
-    let lib_name = package
+    let lib_name = crate_name
+        .or_else(|| Some(metadata?.root_package()?.name.as_str()))
         .map(ToOwned::to_owned)
         .map_or_else(|| package_name(&manifest_path), Ok)?;
 
@@ -125,6 +304,28 @@ fn rustdoc_json_path_for_manifest_path(
     Ok(rustdoc_json_path)
 }
 
+/// Creates a new, empty dir in the system temp dir to use as target dir for
+/// [`Builder::build_managed()`].
+fn create_temp_target_dir() -> Result<PathBuf, BuildError> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    // Processes and threads must not share target dir, and a stale dir from a
+    // process with the same pid might still be around, so retry until we get
+    // a dir that did not exist
+    loop {
+        let dir = std::env::temp_dir().join(format!(
+            "rustdoc-json-target-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
 /// Typically returns the absolute path to the regular cargo `./target`
 /// directory. But also handles packages part of workspaces.
 fn target_directory(manifest_path: impl AsRef<Path>) -> Result<PathBuf, BuildError> {
@@ -149,14 +350,25 @@ impl Default for Builder {
         Self {
             toolchain: None,
             manifest_path: PathBuf::from("Cargo.toml"),
+            current_dir: None,
             target_dir: None,
             target: None,
             quiet: false,
+            verbose: false,
+            keep_going: false,
             no_default_features: false,
             all_features: false,
             features: vec![],
+            build_std: vec![],
+            cargo_config: vec![],
+            extra_args: vec![],
             package: None,
+            bin: None,
             cap_lints: Some(String::from("warn")),
+            timeout: None,
+            color: None,
+            progress_callback: None,
+            metadata: None,
         }
     }
 }
@@ -187,6 +399,17 @@ impl Builder {
         self
     }
 
+    /// Set the working directory to run `cargo rustdoc` in. `cargo` looks for
+    /// `.cargo/config.toml` files in this dir and its parents, so this
+    /// decides e.g. what `[patch]` config that applies to the build. Relative
+    /// paths given to the builder are also relative to this dir, so prefer
+    /// absolute paths if you set it. Default: `None`, i.e. the current dir
+    #[must_use]
+    pub fn current_dir(mut self, current_dir: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(current_dir.as_ref().to_owned());
+        self
+    }
+
     /// Set what `--target-dir` to pass to `cargo`. Typically only needed if you
     /// want to be able to build rustdoc JSON for the same crate concurrently,
     /// for example to parallelize regression tests.
@@ -203,13 +426,24 @@ impl Builder {
         self
     }
 
-    /// Whether or not to pass `--quiet` to `cargo rustdoc`. Default: `false`
+    /// Whether or not to pass `--quiet` to `cargo rustdoc`. Not passed if
+    /// [`Self::verbose()`] is also enabled, since `cargo` does not accept both.
+    /// Default: `false`
     #[must_use]
     pub const fn quiet(mut self, quiet: bool) -> Self {
         self.quiet = quiet;
         self
     }
 
+    /// Whether or not to pass `--verbose` to `cargo rustdoc`. Useful to debug
+    /// build failures. Takes precedence over [`Self::quiet()`], since `cargo`
+    /// does not accept both. Default: `false`
+    #[must_use]
+    pub const fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
     /// Whether or not to pass `--target` to `cargo rustdoc`. Default: `None`
     #[must_use]
     pub fn target(mut self, target: String) -> Self {
@@ -217,6 +451,18 @@ impl Builder {
         self
     }
 
+    /// Whether to pass `--keep-going` to `cargo rustdoc`, so that cargo builds
+    /// as much as it can instead of aborting at the first error. The errors of
+    /// all broken dependencies are then reported at once, but the build still
+    /// fails if any dependency is broken. Broken packages of the workspace that
+    /// the package does not depend on are not built at all, with or without
+    /// this. Requires cargo 1.74 or later. Default: `false`
+    #[must_use]
+    pub const fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
     /// Whether to pass `--no-default-features` to `cargo rustdoc`. Default: `false`
     #[must_use]
     pub const fn no_default_features(mut self, no_default_features: bool) -> Self {
@@ -241,6 +487,47 @@ impl Builder {
         self
     }
 
+    /// Standard library crates to build from source via `-Z build-std`, e.g.
+    /// `["core", "alloc"]`. Needed for targets that do not ship a pre-built
+    /// standard library. Requires a nightly toolchain, the `rust-src`
+    /// component, and [`Self::target()`]. Default: an empty vector, i.e. use the
+    /// pre-built standard library
+    #[must_use]
+    pub fn build_std<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, components: I) -> Self {
+        self.build_std = components
+            .into_iter()
+            .map(|item| item.as_ref().to_owned())
+            .collect();
+        self
+    }
+
+    /// Override a `cargo` configuration value via `--config key=value`, e.g.
+    /// to use a different registry. The `value` is a TOML value, so a string
+    /// must be quoted, e.g. `"\"https://example.com/index\""`. Can be called
+    /// several times to override several values. See
+    /// <https://doc.rust-lang.org/cargo/reference/config.html#command-line-overrides>.
+    /// Default: no overrides
+    #[must_use]
+    pub fn cargo_config(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.cargo_config
+            .push((key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Extra args to pass verbatim to `cargo rustdoc`, after all args that
+    /// the builder itself passes, e.g. `["--offline"]`. An escape hatch for
+    /// `cargo` flags that the builder does not model. The args are not
+    /// validated, so passing the wrong args can make the build fail or not
+    /// produce rustdoc JSON at all. Default: an empty vector
+    #[must_use]
+    pub fn extra_args<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, args: I) -> Self {
+        self.extra_args = args
+            .into_iter()
+            .map(|item| item.as_ref().to_owned())
+            .collect();
+        self
+    }
+
     /// Package to use for `cargo rustdoc` via `-p`. Default: `None`
     #[must_use]
     pub fn package(mut self, package: impl AsRef<str>) -> Self {
@@ -248,6 +535,15 @@ impl Builder {
         self
     }
 
+    /// Document the binary target with the given name, via `--bin`, instead
+    /// of the library target. Useful for packages without a library target.
+    /// Default: `None`, i.e. `--lib`
+    #[must_use]
+    pub fn bin(mut self, bin: impl AsRef<str>) -> Self {
+        self.bin = Some(bin.as_ref().to_owned());
+        self
+    }
+
     /// What to pass as `--cap-lints` to rustdoc JSON build command
     #[must_use]
     pub fn cap_lints(mut self, cap_lints: Option<impl AsRef<str>>) -> Self {
@@ -255,6 +551,58 @@ impl Builder {
         self
     }
 
+    /// Abort the build with [`BuildError::Timeout`] if `cargo rustdoc` has not
+    /// finished within the given duration. Note that only the `cargo` process
+    /// is killed, not processes it might have spawned. Default: `None`, i.e. no
+    /// timeout
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// What to pass as `--color` to `cargo`. Default: `None`, which means
+    /// [`ColorChoice::Never`] if a [`Self::progress_callback()`] is installed,
+    /// and `cargo`'s own default otherwise
+    #[must_use]
+    pub const fn color(mut self, color: Option<ColorChoice>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Install a callback that is called with each line that `cargo rustdoc`
+    /// writes to stderr, instead of letting `cargo rustdoc` write to stderr
+    /// directly. Useful to e.g. show progress while building. If the build
+    /// fails, the captured stderr is included in [`BuildError::General`].
+    /// Implies `--color never` unless [`Self::color()`] says otherwise.
+    /// Default: `None`
+    #[must_use]
+    pub fn progress_callback(
+        mut self,
+        callback: impl FnMut(&str) + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    ) -> Self {
+        self.progress_callback = Some(ProgressCallback(Box::new(callback)));
+        self
+    }
+
+    /// Use already computed `cargo metadata` output for the package, so that
+    /// [`Self::build()`] does not need to run `cargo metadata` again to find
+    /// the target dir and the name of the package. The metadata must be for
+    /// the package of [`Self::manifest_path()`]. Default: `None`, i.e. run
+    /// `cargo metadata` if needed
+    #[must_use]
+    pub fn metadata(mut self, metadata: cargo_metadata::Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// The `cargo rustdoc` command that [`Self::build()`] would run, without
+    /// running it. Useful to e.g. show users how to reproduce a failing build.
+    #[must_use]
+    pub fn command(&self) -> Command {
+        cargo_rustdoc_command(self)
+    }
+
     /// Generate rustdoc JSON for a library crate. Returns the path to the freshly
     /// built rustdoc JSON file.
     ///
@@ -267,12 +615,107 @@ impl Builder {
     pub fn build(self) -> Result<PathBuf, BuildError> {
         run_cargo_rustdoc(self)
     }
+
+    /// Same as [`Self::build()`], but if no [`Self::target_dir()`] is set, a
+    /// new temporary target dir is used, which is removed when the returned
+    /// [`BuildArtifacts`] is dropped. Useful if you only need the rustdoc JSON
+    /// briefly and do not want build artifacts to pile up.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::build()`]. The temporary target dir is removed also if the
+    /// build fails.
+    pub fn build_managed(mut self) -> Result<BuildArtifacts, BuildError> {
+        if self.target_dir.is_some() {
+            return Ok(BuildArtifacts {
+                json_path: self.build()?,
+                temp_target_dir: None,
+            });
+        }
+
+        let temp_target_dir = create_temp_target_dir()?;
+        self.target_dir = Some(temp_target_dir.clone());
+        // Take ownership of the dir before building so it is removed on errors
+        let mut artifacts = BuildArtifacts {
+            json_path: PathBuf::new(),
+            temp_target_dir: Some(temp_target_dir),
+        };
+        artifacts.json_path = self.build()?;
+        Ok(artifacts)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn verbose() {
+        let verbose_arg = |builder: &Builder| {
+            cargo_rustdoc_command(builder)
+                .get_args()
+                .any(|arg| arg == "--verbose")
+        };
+
+        assert!(!verbose_arg(&Builder::default()));
+        assert!(verbose_arg(&Builder::default().verbose(true)));
+        assert!(verbose_arg(&Builder::default().verbose(true).quiet(false)));
+    }
+
+    /// `cargo` rejects `--quiet` together with `--verbose`, so
+    /// [`Builder::verbose()`] takes precedence, regardless of the order of the
+    /// calls. The two settings are otherwise independent.
+    #[test]
+    fn quiet_and_verbose() {
+        let verbosity_args = |builder: &Builder| -> Vec<String> {
+            cargo_rustdoc_command(builder)
+                .get_args()
+                .filter(|arg| *arg == "--quiet" || *arg == "--verbose")
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            verbosity_args(&Builder::default().quiet(true).verbose(true)),
+            vec!["--verbose"]
+        );
+        assert_eq!(
+            verbosity_args(&Builder::default().verbose(true).quiet(true)),
+            vec!["--verbose"]
+        );
+        assert_eq!(
+            verbosity_args(&Builder::default().quiet(true).verbose(true).verbose(false)),
+            vec!["--quiet"]
+        );
+    }
+
+    #[test]
+    fn bin() {
+        // Skip e.g. `run nightly cargo` if rustup is used
+        let args = |builder: &Builder| -> Vec<String> {
+            cargo_rustdoc_command(builder)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .skip_while(|arg| arg != "rustdoc")
+                .collect()
+        };
+
+        assert_eq!(args(&Builder::default())[..2], ["rustdoc", "--lib"]);
+        assert_eq!(
+            args(&Builder::default().bin("some-bin"))[..3],
+            ["rustdoc", "--bin", "some-bin"]
+        );
+    }
+
+    #[test]
+    fn current_dir() {
+        let command = cargo_rustdoc_command(&Builder::default());
+        assert_eq!(command.get_current_dir(), None);
+
+        let command = cargo_rustdoc_command(&Builder::default().current_dir("/some/dir"));
+        assert_eq!(command.get_current_dir(), Some(Path::new("/some/dir")));
+    }
+
     #[test]
     fn ensure_toolchain_not_overridden() {
         // The override is only meant to be changed locally, do not git commit!
@@ -282,4 +725,150 @@ mod tests {
             assert!(OVERRIDDEN_TOOLCHAIN.is_none());
         }
     }
+
+    #[test]
+    fn color() {
+        let color_arg = |builder: &Builder| -> Option<String> {
+            let args: Vec<_> = cargo_rustdoc_command(builder)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            let index = args.iter().position(|arg| arg == "--color")?;
+            args.get(index + 1).cloned()
+        };
+
+        assert_eq!(color_arg(&Builder::default()), None);
+        assert_eq!(
+            color_arg(&Builder::default().progress_callback(|_| {})),
+            Some(String::from("never"))
+        );
+        assert_eq!(
+            color_arg(
+                &Builder::default()
+                    .progress_callback(|_| {})
+                    .color(Some(ColorChoice::Always))
+            ),
+            Some(String::from("always"))
+        );
+    }
+
+    #[test]
+    fn build_std() {
+        let build_std_arg = |builder: &Builder| {
+            cargo_rustdoc_command(builder)
+                .get_args()
+                .find(|arg| arg.to_string_lossy().starts_with("-Zbuild-std"))
+                .map(|arg| arg.to_string_lossy().into_owned())
+        };
+
+        assert_eq!(build_std_arg(&Builder::default()), None);
+        assert_eq!(
+            build_std_arg(&Builder::default().build_std(["core", "alloc"])),
+            Some(String::from("-Zbuild-std=core,alloc"))
+        );
+    }
+
+    #[test]
+    fn cargo_config() {
+        let config_args = |builder: &Builder| -> Vec<String> {
+            let args: Vec<_> = cargo_rustdoc_command(builder)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            args.windows(2)
+                .filter(|pair| pair[0] == "--config")
+                .map(|pair| pair[1].clone())
+                .collect()
+        };
+
+        assert!(config_args(&Builder::default()).is_empty());
+        assert_eq!(
+            config_args(
+                &Builder::default()
+                    .cargo_config("net.offline", "true")
+                    .cargo_config("build.jobs", "1")
+            ),
+            ["net.offline=true", "build.jobs=1"]
+        );
+    }
+
+    #[test]
+    fn extra_args() {
+        let cargo_args = |builder: &Builder| -> Vec<String> {
+            cargo_rustdoc_command(builder)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .take_while(|arg| arg != "--")
+                .collect()
+        };
+
+        assert!(!cargo_args(&Builder::default()).contains(&String::from("--offline")));
+        let args = cargo_args(&Builder::default().extra_args(vec![String::from("--offline")]));
+        assert_eq!(args.last().map(String::as_str), Some("--offline"));
+    }
+
+    #[test]
+    fn prebuilt_metadata() {
+        let metadata: cargo_metadata::Metadata = serde_json::from_str(
+            r#"{
+                "packages": [],
+                "workspace_members": [],
+                "resolve": null,
+                "workspace_root": "/workspace",
+                "target_directory": "/workspace/prebuilt-target",
+                "version": 1
+            }"#,
+        )
+        .unwrap();
+
+        // `cargo metadata` would fail for a manifest that does not exist, so
+        // this only succeeds if the prebuilt metadata is used instead
+        let json_path = rustdoc_json_path_for_manifest_path(
+            "does/not/exist/Cargo.toml",
+            Some("some-crate"),
+            None,
+            None,
+            Some(&metadata),
+        )
+        .unwrap();
+        assert_eq!(
+            json_path,
+            Path::new("/workspace/prebuilt-target/doc/some_crate.json")
+        );
+    }
+
+    #[test]
+    fn command() {
+        let builder = Builder::default().manifest_path("some/Cargo.toml");
+        let command = builder.command();
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args.contains(&"some/Cargo.toml".as_ref()));
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["--output-format", "json"]));
+    }
+
+    #[test]
+    fn nightly_version() {
+        assert!(is_nightly_version(
+            "cargo 1.70.0-nightly (9e2fe2a 2023-03-14)"
+        ));
+        assert!(is_nightly_version("cargo 1.70.0-dev"));
+        assert!(!is_nightly_version("cargo 1.68.0 (115f34552 2023-02-26)"));
+        assert!(!is_nightly_version(
+            "cargo 1.69.0-beta.2 (0ac75a8a2 2023-03-09)"
+        ));
+    }
+
+    #[test]
+    fn build_std_requires_nightly() {
+        let result = Builder::default()
+            .toolchain(String::from("stable"))
+            .build_std(["core"])
+            .build();
+        assert!(matches!(
+            result,
+            Err(BuildError::BuildStdRequiresNightly(_))
+        ));
+    }
 }
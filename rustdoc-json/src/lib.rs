@@ -14,13 +14,22 @@
 //! println!("Built and wrote rustdoc JSON to {:?}", &json_path);
 //! ```
 //!
+//! If the default build options and the active toolchain are fine, there is a
+//! shorthand:
+//!
+//! ```no_run
+//! let json_path = rustdoc_json::build("Cargo.toml").unwrap();
+//! ```
+//!
 //! A compilable example can be found
 //! [here](https://github.com/Enselic/cargo-public-api/blob/main/rustdoc-json/examples/build-rustdoc-json.rs)
 
 // deny in CI, only warn here
 #![warn(clippy::all, clippy::pedantic, missing_docs)]
 
-use std::path::PathBuf;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 mod build;
 
@@ -33,6 +42,28 @@ pub enum BuildError {
     #[error("Manifest must be for an actual package. `{0:?}` is a virtual manifest")]
     VirtualManifest(PathBuf),
 
+    /// You tried to generate rustdoc JSON for the library of a package that
+    /// has no library target. Use [`Builder::bin()`] to document a binary
+    /// target instead.
+    #[error("No library target in `{0:?}`")]
+    NoLibraryTarget(PathBuf),
+
+    /// Building rustdoc JSON took longer than the timeout set with
+    /// [`Builder::timeout()`], so the build was aborted.
+    #[error("Building rustdoc JSON timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// You used [`Builder::build_std()`] with a toolchain that is not a
+    /// nightly toolchain. `-Z build-std` is only available on nightly.
+    #[error("`-Z build-std` requires a nightly toolchain, but the toolchain is `{0}`")]
+    BuildStdRequiresNightly(String),
+
+    /// A toolchain was requested with [`Builder::toolchain()`], but `rustup`,
+    /// which is used to run `cargo` with the requested toolchain, could not be
+    /// found.
+    #[error("The `{0}` toolchain was requested, but `rustup` could not be found")]
+    RustupNotFound(String),
+
     /// A general error. Refer to the attached error message for more info.
     #[error("Failed to build rustdoc JSON. Stderr: {0}")]
     General(String),
@@ -50,18 +81,120 @@ pub enum BuildError {
     IoError(#[from] std::io::Error),
 }
 
+/// Builds rustdoc JSON for the package with the given `Cargo.toml` with the
+/// default build options, and returns the path to the built rustdoc JSON file.
+/// Shorthand for
+/// `Builder::default().manifest_path(manifest_path).build()`.
+///
+/// No toolchain is requested, so the active toolchain is used, and it must be a
+/// nightly toolchain. Use [`Builder::toolchain()`] to build with a specific
+/// toolchain instead.
+///
+/// # Errors
+///
+/// See [`Builder::build()`].
+pub fn build(manifest_path: impl AsRef<Path>) -> Result<PathBuf, BuildError> {
+    Builder::default().manifest_path(manifest_path).build()
+}
+
+/// The result of [`Builder::build_managed()`]. Owns the target dir that the
+/// rustdoc JSON was built in, if the builder created it, and removes it when
+/// dropped. Read the rustdoc JSON before that happens.
+#[derive(Debug)]
+pub struct BuildArtifacts {
+    json_path: PathBuf,
+    temp_target_dir: Option<PathBuf>,
+}
+
+impl BuildArtifacts {
+    /// The path to the built rustdoc JSON file.
+    #[must_use]
+    pub fn json_path(&self) -> &Path {
+        &self.json_path
+    }
+
+    /// The temporary target dir that is removed on drop, or `None` if
+    /// [`Builder::target_dir()`] was set, in which case nothing is removed.
+    #[must_use]
+    pub fn temp_target_dir(&self) -> Option<&Path> {
+        self.temp_target_dir.as_deref()
+    }
+
+    /// Takes ownership of the path to the rustdoc JSON file. The target dir
+    /// is then kept, so it is up to you to remove it.
+    #[must_use]
+    pub fn into_json_path(mut self) -> PathBuf {
+        self.temp_target_dir = None;
+        std::mem::take(&mut self.json_path)
+    }
+}
+
+impl Drop for BuildArtifacts {
+    fn drop(&mut self) {
+        if let Some(temp_target_dir) = &self.temp_target_dir {
+            // Nothing sensible to do with an error while dropping
+            let _ = std::fs::remove_dir_all(temp_target_dir);
+        }
+    }
+}
+
+/// What to pass as `--color` to `cargo`. See [`Builder::color()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color if `cargo` writes to a terminal.
+    Auto,
+
+    /// Always color.
+    Always,
+
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    const fn as_arg(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+}
+
 /// Builds rustdoc JSON. There are many build options. Refer to the docs to
 /// learn about them all. See [top-level docs](crate) for an example on how to use this builder.
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Builder {
     toolchain: Option<String>,
     manifest_path: PathBuf,
+    current_dir: Option<PathBuf>,
     target_dir: Option<PathBuf>,
     target: Option<String>,
     quiet: bool,
+    verbose: bool,
+    keep_going: bool,
     no_default_features: bool,
     all_features: bool,
     features: Vec<String>,
+    build_std: Vec<String>,
+    cargo_config: Vec<(String, String)>,
+    extra_args: Vec<String>,
     package: Option<String>,
+    bin: Option<String>,
     cap_lints: Option<String>,
+    timeout: Option<Duration>,
+    color: Option<ColorChoice>,
+    progress_callback: Option<ProgressCallback>,
+    metadata: Option<cargo_metadata::Metadata>,
+}
+
+/// See [`Builder::progress_callback()`]. Wrapped in a struct so that
+/// [`Builder`] can keep implementing [`Debug`].
+struct ProgressCallback(Box<dyn FnMut(&str) + Send + Sync + UnwindSafe + RefUnwindSafe>);
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
 }
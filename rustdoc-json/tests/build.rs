@@ -0,0 +1,100 @@
+/// Test that the `build()` shorthand builds valid rustdoc JSON
+#[test]
+fn build_with_default_options() {
+    // The default target dir is next to the manifest, so build a copy of the
+    // crate in a temp dir rather than to write into the source tree
+    let crate_dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(crate_dir.path().join("src")).unwrap();
+    for file in ["Cargo.toml", "Cargo.lock", "src/lib.rs"] {
+        std::fs::copy(
+            std::path::Path::new("../test-apis/example_api-v0.1.0").join(file),
+            crate_dir.path().join(file),
+        )
+        .unwrap();
+    }
+
+    // `build()` uses the active toolchain, which does not have to be nightly
+    // where the tests run, so make nightly the active toolchain. The other
+    // tests in this file build with nightly anyway.
+    std::env::set_var("RUSTUP_TOOLCHAIN", "nightly");
+    let path = rustdoc_json::build(crate_dir.path().join("Cargo.toml")).unwrap();
+
+    assert!(path.exists(), "{path:?} does not exist");
+    let target_dir = crate_dir.path().canonicalize().unwrap().join("target");
+    assert!(path.canonicalize().unwrap().starts_with(target_dir));
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(json["format_version"].is_u64());
+}
+
+/// Test that `build_managed()` removes its temporary target dir on drop
+#[test]
+fn build_managed_removes_temp_target_dir() {
+    let artifacts = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/example_api-v0.1.0/Cargo.toml")
+        .quiet(true)
+        .build_managed()
+        .unwrap();
+
+    let json_path = artifacts.json_path().to_owned();
+    let temp_target_dir = artifacts.temp_target_dir().unwrap().to_owned();
+    assert!(json_path.starts_with(&temp_target_dir));
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert!(json["format_version"].is_u64());
+
+    drop(artifacts);
+    assert!(
+        !temp_target_dir.exists(),
+        "{temp_target_dir:?} still exists"
+    );
+}
+
+/// Test that `build_managed()` leaves a target dir set by the user alone
+#[test]
+fn build_managed_keeps_user_target_dir() {
+    let target_dir = tempfile::tempdir().unwrap();
+    let artifacts = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/example_api-v0.1.0/Cargo.toml")
+        .target_dir(&target_dir)
+        .quiet(true)
+        .build_managed()
+        .unwrap();
+    assert_eq!(artifacts.temp_target_dir(), None);
+
+    let json_path = artifacts.into_json_path();
+    assert!(json_path.exists(), "{json_path:?} does not exist");
+}
+
+/// Test that `build()` takes the target dir from prebuilt metadata rather than
+/// from running `cargo metadata` again
+#[test]
+fn build_with_prebuilt_metadata() {
+    let manifest_path = "../test-apis/example_api-v0.1.0/Cargo.toml";
+    let target_dir = tempfile::tempdir().unwrap();
+    let mut metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(manifest_path)
+        .exec()
+        .unwrap();
+    metadata.target_directory =
+        cargo_metadata::camino::Utf8PathBuf::from_path_buf(target_dir.path().to_owned()).unwrap();
+
+    // `cargo metadata` does not know about this override, so the JSON is only
+    // found if the target dir of the prebuilt metadata is used
+    let path = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path(manifest_path)
+        .cargo_config(
+            "build.target-dir",
+            format!("{:?}", target_dir.path().to_str().unwrap()),
+        )
+        .metadata(metadata)
+        .quiet(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(path, target_dir.path().join("doc/example_api.json"));
+    assert!(path.exists(), "{path:?} does not exist");
+}
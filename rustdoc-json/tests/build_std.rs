@@ -0,0 +1,18 @@
+/// Test that we can build rustdoc JSON for a target without a pre-built
+/// standard library
+#[test]
+#[ignore = "requires the rust-src component of the nightly toolchain"]
+fn build_std_for_target_without_std() {
+    let target_dir = tempfile::tempdir().unwrap();
+    let json_path = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/example_api-v0.3.0/Cargo.toml")
+        .target_dir(&target_dir)
+        .target(String::from("thumbv7em-none-eabi"))
+        .build_std(["core"])
+        .quiet(true)
+        .build()
+        .unwrap();
+
+    assert!(json_path.exists());
+}
@@ -0,0 +1,28 @@
+use std::sync::{Arc, Mutex};
+
+/// Test that the lines passed to a progress callback contain no color codes,
+/// even if the user has configured `cargo` to always use colors
+#[test]
+fn progress_callback_gets_lines_without_color() {
+    // Only test in this file, so this does not affect other tests
+    std::env::set_var("CARGO_TERM_COLOR", "always");
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let lines_clone = lines.clone();
+
+    let target_dir = tempfile::tempdir().unwrap();
+    rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/example_api-v0.3.0/Cargo.toml")
+        .target_dir(&target_dir)
+        .progress_callback(move |line| lines_clone.lock().unwrap().push(line.to_owned()))
+        .build()
+        .unwrap();
+
+    let lines = lines.lock().unwrap();
+    assert!(!lines.is_empty());
+    assert!(
+        lines.iter().all(|line| !line.contains('\x1b')),
+        "Unexpected lines: {lines:?}"
+    );
+}
@@ -0,0 +1,26 @@
+use std::sync::{Arc, Mutex};
+
+/// Test that the progress callback gets the lines that `cargo rustdoc` writes
+/// to stderr
+#[test]
+fn progress_callback_gets_stderr_lines() {
+    let lines = Arc::new(Mutex::new(vec![]));
+    let lines_clone = lines.clone();
+
+    let target_dir = tempfile::tempdir().unwrap();
+    rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/example_api-v0.3.0/Cargo.toml")
+        .target_dir(&target_dir)
+        .progress_callback(move |line| lines_clone.lock().unwrap().push(line.to_owned()))
+        .build()
+        .unwrap();
+
+    let lines = lines.lock().unwrap();
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains("Documenting example_api")),
+        "Unexpected lines: {lines:?}"
+    );
+}
@@ -18,3 +18,48 @@ fn ensure_workspace_inheritance_works() {
             .join("test-apis/workspace-inheritance/target/doc/package_with_inheritance.json")
     );
 }
+
+/// Test that with `keep_going(true)`, cargo does not stop at the first broken
+/// dependency, but reports the errors of all broken dependencies at once. One
+/// job at a time, so that the other dependency is not built anyway.
+#[test]
+fn keep_going_with_broken_dependencies() {
+    let target_dir = tempfile::tempdir().unwrap();
+    let result = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/workspace-with-broken-dependencies/Cargo.toml")
+        .package("dependent")
+        .target_dir(&target_dir)
+        .extra_args(["--jobs", "1"])
+        .keep_going(true)
+        .progress_callback(|_| {}) // Captures stderr into the error
+        .build();
+
+    let stderr = match result {
+        Err(rustdoc_json::BuildError::General(stderr)) => stderr,
+        result => panic!("Expected the build to fail, but got {result:?}"),
+    };
+    assert!(stderr.contains("could not compile `broken`"), "{stderr}");
+    assert!(
+        stderr.contains("could not compile `also-broken`"),
+        "{stderr}"
+    );
+}
+
+/// Test that a package builds with `keep_going(true)` even though a sibling
+/// package in its workspace is broken
+#[test]
+fn keep_going_with_broken_sibling() {
+    let target_dir = tempfile::tempdir().unwrap();
+    let path = rustdoc_json::Builder::default()
+        .toolchain("nightly".to_owned())
+        .manifest_path("../test-apis/workspace-with-broken-dependencies/Cargo.toml")
+        .package("good")
+        .target_dir(&target_dir)
+        .keep_going(true)
+        .quiet(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(path, target_dir.path().join("doc/good.json"));
+}
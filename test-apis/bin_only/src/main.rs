@@ -0,0 +1,5 @@
+pub fn public_function_in_bin() {}
+
+fn main() {
+    public_function_in_bin();
+}
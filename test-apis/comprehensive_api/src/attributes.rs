@@ -12,6 +12,16 @@ pub struct C {
     pub b: bool,
 }
 
+#[repr(u8)]
+pub enum U8 {
+    Variant,
+}
+
+#[repr(C)]
+pub union CUnion {
+    pub b: bool,
+}
+
 #[doc(hidden)]
 pub fn doc_hidden() {}
 
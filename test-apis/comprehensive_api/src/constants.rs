@@ -1 +1,3 @@
 pub const CONST: &str = "const";
+
+pub const MAX: u32 = 100;
@@ -14,8 +14,18 @@ pub enum EnumWithExplicitDiscriminants {
 pub enum DiverseVariants {
     Simple,
     Tuple(usize, bool),
-    Struct { x: usize, y: SingleVariant },
-    Recursive { child: Box<DiverseVariants> },
+    Struct {
+        x: usize,
+        y: SingleVariant,
+    },
+    StructWithHiddenField {
+        visible: bool,
+        #[doc(hidden)]
+        hidden: bool,
+    },
+    Recursive {
+        child: Box<DiverseVariants>,
+    },
 }
 
 pub enum EnumWithGenerics<'a, T, D: Debug>
@@ -8,7 +8,10 @@ pub mod v1 {
 
     pub fn foo2() {
         foo();
+        only_visible_in_exports();
     }
+
+    pub(super) fn only_visible_in_exports() {}
 }
 
 pub mod recursion_1 {
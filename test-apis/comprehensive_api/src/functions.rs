@@ -11,6 +11,17 @@ pub fn plain() {}
 
 pub const fn const_fn() {}
 
+/// Returns `x` plus one.
+///
+/// Has docs so that `PublicItem::docs()` can be tested.
+pub fn documented(x: usize) -> usize {
+    x + 1
+}
+
+pub extern "C" fn extern_c() {}
+
+pub extern "system" fn extern_system() {}
+
 pub fn one_arg(x: usize) {
     println!("{}", x);
 }
@@ -57,10 +68,29 @@ pub fn generic_bound<T: Sized>(t: T) -> T {
     t
 }
 
+pub fn const_generic<T, const N: usize>(t: [T; N]) -> [T; N] {
+    t
+}
+
 pub fn inferred_lifetime(foo: &'_ usize) -> usize {
     *foo
 }
 
+pub fn named_lifetime<'a>(x: &'a str) -> &'a str {
+    x
+}
+
+pub fn elided_lifetime(x: &str) -> &str {
+    x
+}
+
+pub fn where_outlives<'a, 'b>(x: &'a str, _y: &'b str) -> &'a str
+where
+    'b: 'a,
+{
+    x
+}
+
 pub fn outlives<'a, 'b: 'a, 'c: 'b + 'a>(
     x: &'a bool,
     y: &'b i128,
@@ -81,6 +111,18 @@ pub fn impl_multiple<T>(t: impl Simple + AsRef<T>) -> impl Simple {
     Unit
 }
 
+pub fn impl_trait_arg_and_return(x: impl Display) -> impl Iterator<Item = u8> {
+    x.to_string().into_bytes().into_iter()
+}
+
+pub fn impl_trait_with_lifetime<'a>(x: &'a [u8]) -> impl Iterator<Item = &'a u8> + 'a {
+    x.iter()
+}
+
+pub fn impl_trait_nested(v: Vec<impl Display>) -> Box<impl Display + ?Sized> {
+    Box::new(v.len())
+}
+
 pub fn somewhere<T, U>(t: T, u: U)
 where
     T: Display,
@@ -105,6 +147,16 @@ pub fn dyn_arg_two_traits(d: &(dyn std::io::Write + Send)) {}
 
 pub fn dyn_arg_two_traits_one_lifetime(d: &(dyn std::io::Write + Send + 'static)) {}
 
+pub fn dyn_raw_pointer_two_traits(d: *const (dyn std::io::Write + Send)) {}
+
+pub fn dyn_boxed_two_traits() -> Box<dyn Iterator<Item = u8> + Send> {
+    Box::new(std::iter::empty())
+}
+
+pub fn dyn_boxed_two_traits_one_lifetime<'a>() -> Box<dyn Iterator<Item = u8> + Send + 'a> {
+    Box::new(std::iter::empty())
+}
+
 pub unsafe fn unsafe_fn() {}
 
 pub async fn async_fn() {}
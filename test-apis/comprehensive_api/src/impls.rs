@@ -1,6 +1,6 @@
 use crate::{
     structs::{Plain, Unit, WithLifetimeAndGenericParam},
-    traits::{Simple, TraitReferencingOwnAssociatedType, TraitWithGenerics},
+    traits::{AssociatedConst, Simple, TraitReferencingOwnAssociatedType, TraitWithGenerics},
 };
 
 impl Plain {
@@ -21,6 +21,10 @@ impl<'a> Plain {
     pub fn s4(&'a self) {}
 }
 
+impl Plain {
+    pub const MAX: u32 = 5;
+}
+
 impl<'b> WithLifetimeAndGenericParam<'b, String> {
     pub fn new(unit_ref: &'b Unit, t: String) -> Self {
         WithLifetimeAndGenericParam { unit_ref, t }
@@ -31,6 +35,10 @@ impl Simple for Unit {
     fn act() {}
 }
 
+impl AssociatedConst for Unit {
+    const CONST: bool = true;
+}
+
 pub trait ForUnit {
     fn for_unit();
 }
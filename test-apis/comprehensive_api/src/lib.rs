@@ -1,3 +1,6 @@
+//! Contains all kinds of public items, to test that each kind is handled
+//! correctly.
+
 // Allow stuff that prevents us from testing unidiomatic but valid public APIs
 #![allow(unused_variables, dead_code)]
 
@@ -27,3 +27,14 @@ pub struct ConstArg<T, const N: usize> {
 pub struct WithTraitBounds<T: Display + Debug> {
     t: T,
 }
+
+pub struct WithWhereClause<T>
+where
+    T: Clone,
+{
+    pub t: T,
+}
+
+pub struct TupleStructWithWhereClause<T>(pub T)
+where
+    T: Clone;
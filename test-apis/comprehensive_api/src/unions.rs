@@ -2,3 +2,8 @@ pub union Basic {
     pub x: usize,
     pub y: usize,
 }
+
+pub union WithGenerics<T: Copy> {
+    pub t: T,
+    pub u: usize,
+}
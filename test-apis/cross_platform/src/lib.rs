@@ -0,0 +1,12 @@
+// Without `core`, rustdoc JSON can be built for any target, without having to
+// install the standard library for it first
+#![feature(no_core)]
+#![no_core]
+
+pub struct Everywhere;
+
+#[cfg(windows)]
+pub struct WindowsOnly;
+
+#[cfg(unix)]
+pub struct UnixOnly;
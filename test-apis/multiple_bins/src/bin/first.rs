@@ -0,0 +1,5 @@
+pub fn first() {}
+
+fn main() {
+    first();
+}
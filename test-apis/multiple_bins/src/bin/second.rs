@@ -0,0 +1,5 @@
+pub fn second() {}
+
+fn main() {
+    second();
+}
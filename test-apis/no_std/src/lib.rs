@@ -0,0 +1,11 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub fn core_only() {}
+
+#[cfg(feature = "std")]
+pub fn needs_std() -> std::string::String {
+    std::string::String::new()
+}
+
+#[cfg(feature = "extra")]
+pub fn extra() {}
@@ -0,0 +1,3 @@
+pub use comprehensive_api_proc_macro::simple_proc_macro as renamed_proc_macro;
+pub use comprehensive_api_proc_macro::simple_proc_macro_attribute as renamed_proc_macro_attribute;
+pub use comprehensive_api_proc_macro::SimpleDeriveMacro as RenamedDeriveMacro;
@@ -0,0 +1 @@
+pub fn also_broken() -> AlsoDoesNotExist {}